@@ -151,12 +151,19 @@ EPHYR_IMAGE_TAG="restreamer${EPHYR_VER}"
 
 EPHYR_CONFIG_DIR="/var/lib/${EPHYR_CONTAINER_NAME}"
 EPHYR_CONFIG_SRS_PATH="${EPHYR_CONFIG_DIR}/srs.conf"
-EPHYR_CONFIG_STATE_PATH="${EPHYR_CONFIG_DIR}/state.json"
+EPHYR_CONFIG_STATE_DIR="${EPHYR_CONFIG_DIR}/state"
+EPHYR_CONFIG_STATE_PATH="${EPHYR_CONFIG_STATE_DIR}/state.json"
 EPHYR_CONFIG_RUNTIME_ENV="${EPHYR_CONFIG_DIR}/ephyr-restreamer-env.list"
 EPHYR_RESTREAMER_STATE_PATH="/tmp/workdir/state.json"
 
 # Create required dir and files
-mkdir -p $EPHYR_CONFIG_DIR
+mkdir -p $EPHYR_CONFIG_STATE_DIR
+# State file is replaced atomically by renaming, so its directory is mounted
+# instead of the file itself.
+if [ -f "${EPHYR_CONFIG_DIR}/state.json" ] \
+   && [ ! -s "$EPHYR_CONFIG_STATE_PATH" ]; then
+  mv "${EPHYR_CONFIG_DIR}/state.json" "$EPHYR_CONFIG_STATE_PATH"
+fi
 touch $EPHYR_CONFIG_STATE_PATH
 touch $EPHYR_CONFIG_SRS_PATH
 touch $EPHYR_CONFIG_RUNTIME_ENV
@@ -179,22 +186,35 @@ cat <<EOF > /usr/local/bin/run-ephyr-restreamer.sh
 set -e
 
 clear_state_on_restart=$CLEAR_EPHYR_STATE_ON_RESTART
+state_dir=$EPHYR_CONFIG_STATE_DIR
 state_path=$EPHYR_CONFIG_STATE_PATH
 
 # Detect directory for DVR.
 ephyr_www_dir="/var/www/ephyr-restreamer"
+ephyr_dvr_dir="/var/lib/ephyr-restreamer/dvr"
 do_volume="\$(set +e; find /mnt/volume_* -type d | head -1 | tr -d '\n')"
 if [ -d "\$do_volume" ]; then
   ephyr_www_dir="\$do_volume/www"
+  ephyr_dvr_dir="\$do_volume/dvr"
 fi
 hcloud_volume="\$(set +e; find /mnt/HC_Volume_* -type d | head -1 | tr -d '\n')"
 if [ -d "\$hcloud_volume" ]; then
   ephyr_www_dir="\$hcloud_volume/www"
+  ephyr_dvr_dir="\$hcloud_volume/dvr"
 fi
 
 echo "ephyr_www_dir=\$ephyr_www_dir"
+echo "ephyr_dvr_dir=\$ephyr_dvr_dir"
 mkdir -p "\$ephyr_www_dir/"
 
+# DVR files were served publicly from the SRS HTTP directory before, so move
+# them out of it.
+if [ -d "\$ephyr_www_dir/dvr" ] && [ ! -d "\$ephyr_dvr_dir" ]; then
+  mkdir -p "\$(dirname "\$ephyr_dvr_dir")"
+  mv "\$ephyr_www_dir/dvr" "\$ephyr_dvr_dir"
+fi
+mkdir -p "\$ephyr_dvr_dir/"
+
 # Print all required Environment variables.
 echo "EPHYR_IMAGE_TAG=\$EPHYR_IMAGE_TAG"
 echo "EPHYR_CLI_ARGS=\$EPHYR_CLI_ARGS"
@@ -207,8 +227,9 @@ fi
 /usr/bin/docker run \
   --network=host \
   -v $EPHYR_CONFIG_SRS_PATH:/usr/local/srs/conf/srs.conf \
-  -v \$state_path:$EPHYR_RESTREAMER_STATE_PATH \
+  -v \$state_dir/:$(dirname $EPHYR_RESTREAMER_STATE_PATH)/ \
   -v \$ephyr_www_dir/:/var/www/srs/ \
+  -v \$ephyr_dvr_dir/:/var/lib/ephyr/dvr/ \
   --env-file $EPHYR_CONFIG_RUNTIME_ENV \
   --name=$EPHYR_CONTAINER_NAME \
   $EPHYR_IMAGE_NAME:\$EPHYR_IMAGE_TAG \$EPHYR_CLI_ARGS
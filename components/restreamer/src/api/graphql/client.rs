@@ -2,16 +2,19 @@
 //!
 //! [GraphQL]: https://graphql.com
 
-use std::collections::HashSet;
+use std::{collections::HashSet, time::Duration};
 
 use actix_web::http::StatusCode;
 
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
 use ephyr_log::tracing;
 use futures::{stream::BoxStream, StreamExt};
 use futures_signals::signal::SignalExt as _;
 use itertools::Itertools;
-use juniper::{graphql_object, graphql_subscription, GraphQLObject, RootNode};
+use juniper::{
+    graphql_object, graphql_subscription, GraphQLObject, Nullable, RootNode,
+};
 use once_cell::sync::Lazy;
 use rand::Rng as _;
 use tap::Tap;
@@ -20,9 +23,12 @@ use crate::{
     api::graphql,
     dvr, reorder_items, spec,
     state::{
-        Delay, InputEndpointKind, InputId, InputKey, InputSrc, InputSrcUrl,
-        Label, MixinId, MixinSrcUrl, OutputDstUrl, OutputId, PasswordKind,
-        Restream, RestreamId, RestreamKey, Volume,
+        AudioEncoding, Delay, DvrRetention, InputEndpointKind, InputId,
+        InputKey, InputSrc, InputSrcUrl, Label, Loudness, MixinId, MixinSrcUrl,
+        OutputDstUrl, OutputId, PasswordKind, Restream, RestreamId,
+        RestreamKey, RetryPolicy, Schedule, ScheduleId, ScheduleRepeat,
+        ScheduledAction, Sidechain, Snapshot, SnapshotId, SpeakerFilter,
+        SpecChange, SrtListener, TranscodingProfile, Volume,
     },
     Spec,
 };
@@ -33,7 +39,7 @@ use crate::{
         get_video_file_from_gdrive, get_video_list_from_gdrive_folder,
         FileCommand, FileId, FileState, LocalFileInfo,
     },
-    spec::v1::BackupInput,
+    spec::v2::BackupInput,
     state::{Direction, EndpointId, Output, ServerInfo, VolumeLevel},
     types::UNumber,
 };
@@ -84,14 +90,44 @@ impl MutationsRoot {
         restream_id: Option<RestreamId>,
         context: &Context,
     ) -> Result<Option<bool>, graphql::Error> {
-        let spec = serde_json::from_str::<Spec>(&spec)?.into_v1();
+        let _snapshot = context.state().record_snapshot("import");
+
+        let spec = serde_json::from_str::<Spec>(&spec)?.into_v2();
+
+        // SRT ports should stay unique along with the `Restream`s being kept
+        // untouched by the `spec`.
+        let is_kept = |r: &&Restream| {
+            restream_id.map_or_else(
+                || !replace && !spec.restreams.iter().any(|s| s.key == r.key),
+                |id| r.id != id,
+            )
+        };
+        for l in spec.restreams.iter().flat_map(|r| r.input.srt_listeners()) {
+            let is_taken = context
+                .state()
+                .restreams
+                .lock_ref()
+                .iter()
+                .filter(is_kept)
+                .any(|r| r.input.uses_srt_port(l.port));
+            if is_taken {
+                return Err(graphql::Error::new("DUPLICATE_SRT_PORT")
+                    .status(StatusCode::CONFLICT)
+                    .message(&format!(
+                        "SRT port {} is used already",
+                        l.port.0,
+                    )));
+            }
+        }
 
         let notify_list_of_files_changed = || {
             let mut commands = context.state().file_commands.lock_mut();
             commands.push(FileCommand::ListOfFilesChanged);
         };
 
-        Ok(if let Some(id) = restream_id {
+        // Lock of `State::restreams` should be released before the returned
+        // value is built, so recording the `_snapshot` doesn't deadlock.
+        let imported = if let Some(id) = restream_id {
             let mut spec = (spec.restreams.len() == 1)
                 .then(|| spec.restreams.into_iter().next())
                 .flatten()
@@ -144,7 +180,8 @@ impl MutationsRoot {
             context.state().apply(spec, replace);
             notify_list_of_files_changed();
             Some(true)
-        })
+        };
+        Ok(imported)
     }
 
     /// Sets a new `Restream` or updates an existing one (if `id` is specified).
@@ -187,11 +224,60 @@ impl MutationsRoot {
             default = false
         )]
         with_playback_encoding: bool,
+        #[graphql(description = "Optional `SrtListener` of an additional \
+                                 endpoint for accepting a live stream being \
+                                 pushed via SRT.\
+                                 \n\n\
+                                 Allowed only if no `src` is specified. \
+                                 If omitted, then the current one is kept.")]
+        srt: Nullable<spec::v2::SrtListener>,
         #[graphql(description = "ID of the `Restream` to be updated \
                                  rather than creating a new one.")]
         id: Option<RestreamId>,
         context: &Context,
     ) -> Result<Option<RestreamId>, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("setRestream");
+
+        // Omitted `srt` keeps the current SRT endpoint of a pushed live
+        // stream, so clients unaware of it don't remove it on editing.
+        let srt = srt.explicit().unwrap_or_else(|| {
+            let is_pushed =
+                src.is_none() && backup_inputs.is_none() && file_id.is_none();
+            let id = id.filter(|_| is_pushed)?;
+            context
+                .state()
+                .restreams
+                .lock_ref()
+                .iter()
+                .find(|r| r.id == id)?
+                .input
+                .endpoints
+                .iter()
+                .find_map(|e| e.srt.as_ref().map(SrtListener::export))
+        });
+
+        if let Some(srt) = &srt {
+            let invalid = |msg: &str| {
+                graphql::Error::new("INVALID_SRT_LISTENER")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(msg)
+            };
+            if src.is_some() || backup_inputs.is_some() || file_id.is_some() {
+                return Err(invalid(
+                    "SRT endpoint is allowed only for a pushed live stream",
+                ));
+            }
+            SrtListener::validate(srt).map_err(|e| invalid(&e))?;
+            if context.state().is_srt_port_taken(srt.port, id) {
+                return Err(graphql::Error::new("DUPLICATE_SRT_PORT")
+                    .status(StatusCode::CONFLICT)
+                    .message(&format!(
+                        "SRT port {} is used already",
+                        srt.port.0,
+                    )));
+            }
+        }
+
         let backups = match backup_inputs.clone() {
             None => Vec::new(),
             Some(b) => b,
@@ -201,34 +287,44 @@ impl MutationsRoot {
             if backup_inputs.is_some() || file_id.is_some() {
                 (
                     InputKey::playback(),
-                    Some(spec::v1::InputSrc::FailoverInputs(
-                        vec![spec::v1::Input::new_primary(src)]
+                    Some(spec::v2::InputSrc::FailoverInputs(
+                        vec![spec::v2::Input::new_primary(src)]
                             .into_iter()
                             .chain(
                                 backups
                                     .into_iter()
-                                    .map(spec::v1::Input::new_backup),
+                                    .map(spec::v2::Input::new_backup),
                             )
                             .chain(file_id.map_or_else(Vec::new, |id| {
-                                vec![spec::v1::Input::new_file_backup(id)]
+                                vec![spec::v2::Input::new_file_backup(id)]
                             }))
                             .collect(),
                     )),
                 )
             } else {
-                (InputKey::primary(), src.map(spec::v1::InputSrc::RemoteUrl))
+                (InputKey::primary(), src.map(spec::v2::InputSrc::RemoteUrl))
             };
 
-        let mut endpoints = vec![spec::v1::InputEndpoint {
+        let mut endpoints = vec![spec::v2::InputEndpoint {
             kind: InputEndpointKind::Rtmp,
             label: None,
             file_id: None,
+            srt: None,
         }];
         if with_hls {
-            endpoints.push(spec::v1::InputEndpoint {
+            endpoints.push(spec::v2::InputEndpoint {
                 kind: InputEndpointKind::Hls,
                 label: None,
                 file_id: None,
+                srt: None,
+            });
+        }
+        if let Some(srt) = srt {
+            endpoints.push(spec::v2::InputEndpoint {
+                kind: InputEndpointKind::Srt,
+                label: None,
+                file_id: None,
+                srt: Some(srt),
             });
         }
 
@@ -236,20 +332,23 @@ impl MutationsRoot {
         let with_playback_encoding =
             input_key.is_playback() && with_playback_encoding;
 
-        let spec = spec::v1::Restream {
+        let spec = spec::v2::Restream {
             id: None,
             key,
             label,
-            input: spec::v1::Input {
+            input: spec::v2::Input {
                 id: None,
                 key: input_key,
                 endpoints,
                 src: input_src,
                 enabled: true,
+                record: false,
             },
             outputs: vec![],
-            playlist: Some(spec::v1::Playlist { queue: vec![] }),
-            with_playback_encoding: Some(with_playback_encoding),
+            playlist: Some(spec::v2::Playlist { queue: vec![] }),
+            with_playback_encoding,
+            // Existing schedules are retained on editing.
+            schedules: vec![],
         };
 
         let result = if let Some(id) = id {
@@ -278,6 +377,8 @@ impl MutationsRoot {
         file_id: FileId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("downloadFile");
+
         let mut restreams = context.state().restreams.lock_mut();
         restreams.iter_mut().for_each(|restream| {
             if let Some(InputSrc::Failover(fo)) = &restream.input.src {
@@ -312,6 +413,8 @@ impl MutationsRoot {
         id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("removeRestream");
+
         context.state().remove_restream(id)?;
         Some(true)
     }
@@ -329,6 +432,8 @@ impl MutationsRoot {
         id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("enableRestream");
+
         context.state().enable_restream(id)
     }
 
@@ -346,6 +451,8 @@ impl MutationsRoot {
         id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("disableRestream");
+
         context.state().disable_restream(id)
     }
 
@@ -355,6 +462,8 @@ impl MutationsRoot {
         ids: Vec<RestreamId>,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("changeInputsOrder");
+
         let mut restreams = context.state().restreams.lock_mut();
         let reordered = reorder_items(&restreams, &ids, |r: &Restream| r.id);
         *restreams = reordered;
@@ -375,6 +484,8 @@ impl MutationsRoot {
         >,
         context: &Context,
     ) -> Result<bool, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("changeOutputsOrder");
+
         let mut restreams = context.state().restreams.lock_mut();
         let outputs = &mut restreams
             .iter_mut()
@@ -408,6 +519,8 @@ impl MutationsRoot {
         dst_position: UNumber,
         context: &Context,
     ) -> Result<bool, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("moveOutput");
+
         let mut restreams = context.state().restreams.lock_mut();
 
         let output = restreams
@@ -461,6 +574,8 @@ impl MutationsRoot {
         playlist: Vec<FileId>,
         context: &Context,
     ) -> Result<bool, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("setPlaylist");
+
         // Checks whether the list of files contains duplicates and if so
         // reject setting playlist
         if playlist.iter().unique().count() != playlist.len() {
@@ -515,6 +630,9 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot =
+            context.state().record_snapshot("cancelPlaylistDownload");
+
         let restream = context
             .state()
             .restreams
@@ -554,6 +672,9 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot =
+            context.state().record_snapshot("restartPlaylistDownload");
+
         let restream = context
             .state()
             .restreams
@@ -598,6 +719,8 @@ impl MutationsRoot {
         file_id: FileId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("cancelFileDownload");
+
         context.state().files.lock_mut().iter_mut().find_map(|f| {
             (f.file_id == file_id).then(|| {
                 if f.state == FileState::Local {
@@ -617,6 +740,10 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context
+            .state()
+            .record_snapshot("stopPlayingFileFromPlaylist");
+
         context
             .state()
             .restreams
@@ -637,6 +764,8 @@ impl MutationsRoot {
         file_id: FileId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("playFileFromPlaylist");
+
         context
             .state()
             .restreams
@@ -665,6 +794,8 @@ impl MutationsRoot {
         name_prefix: String,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("broadcastPlayFile");
+
         let mut has_found = false;
         context
             .state()
@@ -696,6 +827,9 @@ impl MutationsRoot {
         name_prefix: String,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot =
+            context.state().record_snapshot("broadcastStopPlayingFile");
+
         let mut has_found = false;
         context
             .state()
@@ -742,6 +876,10 @@ impl MutationsRoot {
         let single_file_response =
             get_video_file_from_gdrive(&api_key, &file_or_folder_id).await;
 
+        // Taken after awaiting, so no concurrent mutation is attributed to
+        // this one.
+        let _snapshot =
+            context.state().record_snapshot("getPlaylistFromGdrive");
         let mut restreams = context.state().restreams.lock_mut();
         let restream = restreams
             .iter_mut()
@@ -801,6 +939,8 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("enableInput");
+
         context.state().enable_input(id, restream_id)
     }
 
@@ -822,9 +962,39 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("disableInput");
+
         context.state().disable_input(id, restream_id)
     }
 
+    /// Enables or disables recording of a live stream received by an `Input`
+    /// into DVR files.
+    ///
+    /// Recorded files are listed by `Query.inputDvrRecordings` and are subject
+    /// to the `DvrRetention` policy, same as the ones of `Output`s.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if recording of an `Input` with the given `id` has been
+    /// changed, `false` if it has been set so already, and `null` if it
+    /// doesn't exist.
+    fn set_input_recording(
+        #[graphql(description = "ID of the `Input` to be recorded.")]
+        id: InputId,
+        #[graphql(
+            description = "ID of the `Restream` to record the `Input` in."
+        )]
+        restream_id: RestreamId,
+        #[graphql(description = "Indicator whether the `Input` should be \
+                                 recorded.")]
+        record: bool,
+        context: &Context,
+    ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("setInputRecording");
+
+        context.state().set_input_recording(id, restream_id, record)
+    }
+
     /// Moves this [`Input`] in given direction.
     ///
     /// This may affect the order and priority of endpoints.
@@ -843,6 +1013,8 @@ impl MutationsRoot {
         context: &Context,
         direction: Direction,
     ) -> Result<Option<bool>, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("moveInputInDirection");
+
         context
             .state()
             .move_input_in_direction(id, restream_id, direction)
@@ -864,6 +1036,8 @@ impl MutationsRoot {
         label: Option<Label>,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("setEndpointLabel");
+
         context
             .state()
             .set_endpoint_label(id, restream_id, endpoint_id, label)
@@ -893,24 +1067,154 @@ impl MutationsRoot {
                                At the moment only [RTMP] and [Icecast] are \
                                supported.\
                                \n\n\
+                               Its [Icecast] `password` query parameter is \
+                               stored separately and never exposed. If \
+                               omitted, then the current one of the updated \
+                               `Output` is kept, and if empty, then it's \
+                               cleared.\
+                               \n\n\
                                [Icecast]: https://icecast.org\n\
                                [RTMP]: https://en.wikipedia.org/wiki/\
                                        Real-Time_Messaging_Protocol"
         )]
-        dst: OutputDstUrl,
+        mut dst: OutputDstUrl,
         #[graphql(description = "Optional label to add a new `Output` with.")]
         label: Option<Label>,
         preview_url: Option<Url>,
         #[graphql(
-            description = "Optional `MixinSrcUrl`s to mix into this `Output`.",
+            description = "Optional `MixinSrcUrl`s to mix into this `Output`.\
+                           \n\n\
+                           Their `password` and `channel_password` query \
+                           parameters are stored separately and never \
+                           exposed. If omitted, then the current ones of \
+                           the updated `Mixin` are kept, and if empty, then \
+                           they're cleared.",
             default = Vec::new(),
         )]
         mixins: Vec<MixinSrcUrl>,
+        #[graphql(description = "Optional `TranscodingProfile` to transcode \
+                                 a live stream with before re-streaming it \
+                                 to the destination.\
+                                 \n\n\
+                                 If `null`, then a live stream is \
+                                 re-streamed \"as is\". If omitted, then \
+                                 the current one of the updated `Output` \
+                                 is kept.")]
+        transcoding: Nullable<spec::v2::TranscodingProfile>,
+        #[graphql(description = "Optional `AudioEncoding` to encode audio \
+                                 with before re-streaming it to the \
+                                 audio-only destination ([Icecast] or \
+                                 `.mp3` file).\
+                                 \n\n\
+                                 If `null`, then audio is encoded into \
+                                 64 kbit/s MP3. If omitted, then the \
+                                 current one of the updated `Output` is \
+                                 kept.\
+                                 \n\n\
+                                 [Icecast]: https://icecast.org")]
+        audio_encoding: Nullable<spec::v2::AudioEncoding>,
+        #[graphql(description = "Optional `Loudness` to normalize and limit \
+                                 the mixed audio with.\
+                                 \n\n\
+                                 If `null`, then the mixed audio is \
+                                 re-streamed \"as is\". If omitted, then \
+                                 the current one of the updated `Output` \
+                                 is kept.")]
+        loudness: Nullable<spec::v2::Loudness>,
+        #[graphql(description = "Optional `RetryPolicy` of restarting a \
+                                 failed re-streaming to the destination.\
+                                 \n\n\
+                                 If `null`, then the global one is used. \
+                                 If omitted, then the current one of the \
+                                 updated `Output` is kept.")]
+        retry_policy: Nullable<spec::v2::RetryPolicy>,
         #[graphql(description = "ID of the `Output` to be updated \
                                  rather than creating a new one.")]
         id: Option<OutputId>,
         context: &Context,
     ) -> Result<Option<OutputId>, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("setOutput");
+
+        let existing_output = id.as_ref().and_then(|output_id| {
+            context.state().get_output(restream_id, *output_id)
+        });
+        // Omitted settings are kept as they are, so clients unaware of them
+        // don't reset them.
+        let transcoding = transcoding.explicit().unwrap_or_else(|| {
+            existing_output
+                .as_ref()
+                .and_then(|o| o.transcoding.as_ref())
+                .map(TranscodingProfile::export)
+        });
+        let audio_encoding = audio_encoding.explicit().unwrap_or_else(|| {
+            existing_output
+                .as_ref()
+                .and_then(|o| o.audio_encoding.as_ref())
+                .map(AudioEncoding::export)
+        });
+        let loudness = loudness.explicit().unwrap_or_else(|| {
+            existing_output
+                .as_ref()
+                .and_then(|o| o.loudness.as_ref())
+                .map(Loudness::export)
+        });
+        let retry_policy = retry_policy.explicit().unwrap_or_else(|| {
+            existing_output
+                .as_ref()
+                .and_then(|o| o.retry_policy.as_ref())
+                .map(RetryPolicy::export)
+        });
+
+        if let Some(transcoding) = &transcoding {
+            TranscodingProfile::validate(transcoding).map_err(|e| {
+                graphql::Error::new("INVALID_TRANSCODING_PROFILE")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
+
+        if let Some(policy) = &retry_policy {
+            RetryPolicy::validate(policy).map_err(|e| {
+                graphql::Error::new("INVALID_RETRY_POLICY")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
+
+        if let Some(encoding) = &audio_encoding {
+            AudioEncoding::validate(encoding)
+                .and_then(|()| AudioEncoding::validate_dst(encoding, &dst))
+                .map_err(|e| {
+                    graphql::Error::new("INVALID_AUDIO_ENCODING")
+                        .status(StatusCode::BAD_REQUEST)
+                        .message(&e)
+                })?;
+        }
+
+        if let Some(loudness) = &loudness {
+            Loudness::validate(loudness).map_err(|e| {
+                graphql::Error::new("INVALID_LOUDNESS")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
+
+        // Passwords are stored separately from `Output.dst` and `Mixin.src`,
+        // so are not exposed along with them.
+        let password = dst
+            .take_password()
+            .or_else(|| existing_output.as_ref()?.password.clone())
+            .filter(|p| !p.is_empty());
+        let mixins = mixins
+            .into_iter()
+            .map(|mut src| {
+                let password = src.take_param(MixinSrcUrl::PASSWORD_PARAM);
+                let channel_password =
+                    src.take_param(MixinSrcUrl::CHANNEL_PASSWORD_PARAM);
+                (src, password, channel_password)
+            })
+            .collect::<Vec<_>>();
+
         if mixins.len() > 5 {
             return Err(graphql::Error::new("TOO_MUCH_MIXIN_URLS")
                 .status(StatusCode::BAD_REQUEST)
@@ -918,7 +1222,7 @@ impl MutationsRoot {
         }
         if !mixins.is_empty() {
             let mut unique = HashSet::with_capacity(mixins.len());
-            for m in &mixins {
+            for (m, ..) in &mixins {
                 if let Some(dup) = unique.replace(m) {
                     return Err(graphql::Error::new("DUPLICATE_MIXIN_URL")
                         .status(StatusCode::BAD_REQUEST)
@@ -927,7 +1231,12 @@ impl MutationsRoot {
                         )));
                 }
             }
-            if mixins.iter().filter(|u| u.scheme() == "ts").take(4).count() > 3
+            if mixins
+                .iter()
+                .filter(|(u, ..)| u.scheme() == "ts")
+                .take(4)
+                .count()
+                > 3
             {
                 return Err(graphql::Error::new(
                     "TOO_MUCH_TEAMSPEAK_MIXIN_URLS",
@@ -937,9 +1246,6 @@ impl MutationsRoot {
             }
         }
 
-        let existing_output = id.as_ref().and_then(|output_id| {
-            context.state().get_output(restream_id, *output_id)
-        });
         let mut original_volume = Volume::ORIGIN.export();
         if let Some(output) = existing_output.as_ref() {
             if !mixins.is_empty() {
@@ -947,18 +1253,20 @@ impl MutationsRoot {
             }
         }
 
-        let spec = spec::v1::Output {
+        let spec = spec::v2::Output {
             id: None,
             dst,
+            password,
             label,
             preview_url,
             volume: original_volume,
             mixins: mixins
                 .into_iter()
-                .map(|src| {
+                .map(|(src, mut password, mut channel_password)| {
                     let delay;
                     let volume;
                     let sidechain;
+                    let speakers;
                     if let Some(orig_mixin) =
                         existing_output.as_ref().and_then(|val| {
                             val.mixins.iter().find(|val| val.src == src)
@@ -966,24 +1274,46 @@ impl MutationsRoot {
                     {
                         volume = orig_mixin.volume.export();
                         delay = orig_mixin.delay;
-                        sidechain = orig_mixin.sidechain;
+                        sidechain = orig_mixin
+                            .sidechain
+                            .as_ref()
+                            .map(Sidechain::export);
+                        speakers = orig_mixin.speakers.export();
+                        password =
+                            password.or_else(|| orig_mixin.password.clone());
+                        channel_password = channel_password
+                            .or_else(|| orig_mixin.channel_password.clone());
                     } else {
                         volume = Volume::ORIGIN.export();
-                        delay = (src.scheme() == "ts")
+                        delay = matches!(src.scheme(), "ts" | "mumble")
                             .then(|| Delay::from_millis(3500))
                             .flatten()
                             .unwrap_or_default();
-                        sidechain = false;
+                        sidechain = None;
+                        speakers = spec::v2::SpeakerFilter::default();
                     }
-                    spec::v1::Mixin {
+                    spec::v2::Mixin {
                         src,
                         volume,
                         delay,
                         sidechain,
+                        speakers,
+                        teamspeak_identity: None,
+                        password: password.filter(|p| !p.is_empty()),
+                        channel_password: channel_password
+                            .filter(|p| !p.is_empty()),
                     }
                 })
                 .collect(),
             enabled: false,
+            schedules: existing_output
+                .as_ref()
+                .map(|o| o.schedules.iter().map(Schedule::export).collect())
+                .unwrap_or_default(),
+            transcoding,
+            audio_encoding,
+            loudness,
+            retry_policy,
         };
 
         let result = if let Some(id) = id {
@@ -1014,6 +1344,8 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("removeOutput");
+
         context
             .state()
             .remove_output(id, restream_id)
@@ -1038,6 +1370,8 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("enableOutput");
+
         context.state().enable_output(id, restream_id)
     }
 
@@ -1059,6 +1393,8 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("disableOutput");
+
         context.state().disable_output(id, restream_id)
     }
 
@@ -1079,6 +1415,8 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("enableAllOutputs");
+
         context.state().enable_all_outputs(restream_id)
     }
 
@@ -1099,6 +1437,8 @@ impl MutationsRoot {
         restream_id: RestreamId,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("disableAllOutputs");
+
         context.state().disable_all_outputs(restream_id)
     }
 
@@ -1112,6 +1452,10 @@ impl MutationsRoot {
     /// Returns `true` if at least one `Output` has been disabled, `false` if
     /// all `Output`s have been disabled already or there are no outputs
     fn disable_all_outputs_of_restreams(context: &Context) -> bool {
+        let _snapshot = context
+            .state()
+            .record_snapshot("disableAllOutputsOfRestreams");
+
         context.state().disable_all_outputs_of_restreams()
     }
 
@@ -1125,9 +1469,120 @@ impl MutationsRoot {
     /// Returns `true` if at least one `Output` has been enabled, `false` if all
     /// `Output`s have been enabled already or there are no outputs
     fn enables_all_outputs_of_restreams(context: &Context) -> bool {
+        let _snapshot = context
+            .state()
+            .record_snapshot("enablesAllOutputsOfRestreams");
+
         context.state().enable_all_outputs_of_restreams()
     }
 
+    /// Sets a new `Schedule` of enabling and disabling the specified
+    /// `Restream` (or its `Output`), or updates an existing one (if `id` is
+    /// specified).
+    ///
+    /// ### Result
+    ///
+    /// Returns ID of the set `Schedule`, or `null` if the specified
+    /// `Restream`, `Output` or `Schedule` doesn't exist.
+    fn set_schedule(
+        #[graphql(description = "ID of the `Restream` to be scheduled.")]
+        restream_id: RestreamId,
+        #[graphql(
+            description = "Optional ID of the `Output` to be scheduled. \
+                                 \n\n\
+                                 If not set, then the whole `Restream` is \
+                                 scheduled."
+        )]
+        output_id: Option<OutputId>,
+        #[graphql(description = "Moment when the first window of the \
+                                 `Schedule` starts.")]
+        start: DateTime<Utc>,
+        #[graphql(description = "Moment when the first window of the \
+                                 `Schedule` ends.")]
+        end: DateTime<Utc>,
+        #[graphql(
+            description = "Period of repeating the `Schedule` window.",
+            default = ScheduleRepeat::Once,
+        )]
+        repeat: ScheduleRepeat,
+        #[graphql(description = "ID of the `Schedule` to be updated \
+                                 rather than creating a new one.")]
+        id: Option<ScheduleId>,
+        context: &Context,
+    ) -> Result<Option<ScheduleId>, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("setSchedule");
+
+        Schedule::validate(start, end, repeat).map_err(|e| {
+            graphql::Error::new("INVALID_SCHEDULE")
+                .status(StatusCode::BAD_REQUEST)
+                .message(&e)
+        })?;
+
+        Ok(context.state().set_schedule(
+            restream_id,
+            output_id,
+            id,
+            spec::v2::Schedule { start, end, repeat },
+        ))
+    }
+
+    /// Removes a `Schedule` by its `id` from the specified `Restream` (or its
+    /// `Output`).
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if the specified `Restream`, `Output` or `Schedule`
+    /// doesn't exist, otherwise always returns `true`.
+    fn remove_schedule(
+        #[graphql(description = "ID of the `Schedule` to be removed.")]
+        id: ScheduleId,
+        #[graphql(description = "ID of the `Restream` to remove the \
+                                 `Schedule` from.")]
+        restream_id: RestreamId,
+        #[graphql(description = "Optional ID of the `Output` to remove the \
+                                 `Schedule` from.")]
+        output_id: Option<OutputId>,
+        context: &Context,
+    ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("removeSchedule");
+
+        context
+            .state()
+            .remove_schedule(restream_id, output_id, id)?;
+        Some(true)
+    }
+
+    /// Clears recorded `StreamFailure`s of the specified `Output` or
+    /// `InputEndpoint`.
+    ///
+    /// If neither `outputId`, nor `endpointId` is specified, then clears
+    /// `StreamFailure`s of all `Output`s and `InputEndpoint`s of the
+    /// `Restream`.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if any `StreamFailure`s have been cleared, `false` if
+    /// there were none, or `null` if the specified `Restream`, `Output` or
+    /// `InputEndpoint` doesn't exist.
+    fn clear_failures(
+        #[graphql(description = "ID of the `Restream` to clear \
+                                 `StreamFailure`s in.")]
+        restream_id: RestreamId,
+        #[graphql(description = "Optional ID of the `Output` to clear \
+                                 `StreamFailure`s of.")]
+        output_id: Option<OutputId>,
+        #[graphql(description = "Optional ID of the `InputEndpoint` to \
+                                 clear `StreamFailure`s of.")]
+        endpoint_id: Option<EndpointId>,
+        context: &Context,
+    ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("clearFailures");
+
+        context
+            .state()
+            .clear_failures(restream_id, output_id, endpoint_id)
+    }
+
     /// Tunes a `Volume` rate of the specified `Output` or one of its `Mixin`s.
     ///
     /// ### Result
@@ -1152,11 +1607,89 @@ impl MutationsRoot {
         muted: bool,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("tuneVolume");
+
         context.state().tune_volume(
             restream_id,
             output_id,
             mixin_id,
-            Volume { level, muted },
+            Volume {
+                level,
+                muted,
+                solo: false,
+            },
+        )
+    }
+
+    /// Solos the specified `Output`'s origin audio or one of its `Mixin`s, so
+    /// all the other non-soloed ones are muted while mixing, or unsolos it.
+    ///
+    /// Keeps the configured `Volume` rates untouched.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if a `Volume.solo` flag has been changed, `false` if it
+    /// has the same value already, or `null` if the specified `Output` or
+    /// `Mixin` doesn't exist.
+    fn tune_solo(
+        #[graphql(
+            description = "ID of the `Restream` to tune the `Output` in."
+        )]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the tuned `Output`.")]
+        output_id: OutputId,
+        #[graphql(description = "Optional ID of the tuned `Mixin`.\
+                                \n\n\
+                                If set, then tunes the `Mixin` rather than \
+                                the `Output`.")]
+        mixin_id: Option<MixinId>,
+        solo: bool,
+        context: &Context,
+    ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("tuneSolo");
+
+        context
+            .state()
+            .tune_solo(restream_id, output_id, mixin_id, solo)
+    }
+
+    /// Fades a `Volume` rate of the specified `Output` or one of its `Mixin`s
+    /// to the `target` one smoothly.
+    ///
+    /// A muted `Volume` is unmuted and faded in from `0`. Fading stops once
+    /// the `Volume` is tuned by anything else.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if a fading has been started, `false` if the `Volume`
+    /// has the `target` rate already, or `null` if the specified `Output` or
+    /// `Mixin` doesn't exist.
+    fn fade_volume(
+        #[graphql(
+            description = "ID of the `Restream` to tune the `Output` in."
+        )]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the tuned `Output`.")]
+        output_id: OutputId,
+        #[graphql(description = "Optional ID of the tuned `Mixin`.\
+                                \n\n\
+                                If set, then tunes the `Mixin` rather than \
+                                the `Output`.")]
+        mixin_id: Option<MixinId>,
+        #[graphql(description = "Volume rate in percents to fade to.")]
+        target: VolumeLevel,
+        #[graphql(description = "Duration of fading, in milliseconds.")]
+        duration: UNumber,
+        context: &Context,
+    ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("fadeVolume");
+
+        context.state().fade_volume(
+            restream_id,
+            output_id,
+            mixin_id,
+            target,
+            Duration::from_millis(duration.0.into()),
         )
     }
 
@@ -1181,6 +1714,8 @@ impl MutationsRoot {
         delay: Delay,
         context: &Context,
     ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("tuneDelay");
+
         context
             .state()
             .tune_delay(restream_id, output_id, mixin_id, delay)
@@ -1189,6 +1724,9 @@ impl MutationsRoot {
     /// Tunes a `Sidechain` of the specified `Mixin` before mix it into its
     /// `Output`.
     ///
+    /// Changing `Sidechain` parameters of an already side-chained `Mixin` is
+    /// applied in real-time, without interrupting its `Output`.
+    ///
     /// ### Result
     ///
     /// Returns `true` if a `Sidechain` has been changed, `false` if it has
@@ -1202,15 +1740,74 @@ impl MutationsRoot {
         #[graphql(description = "ID of the `Output` of the tuned `Mixin`.")]
         output_id: OutputId,
         #[graphql(description = "ID of the tuned `Mixin`.")] mixin_id: MixinId,
+        #[graphql(description = "Indicator whether the `Mixin` should be \
+                                 side-chained with its `Output`.")]
         sidechain: bool,
+        #[graphql(description = "Optional `Sidechain` parameters to \
+                                 side-chain the `Mixin` with.\
+                                 \n\n\
+                                 If not set, then the current ones are kept, \
+                                 or the default ones are used.")]
+        params: Option<spec::v2::Sidechain>,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().tune_sidechain(
+    ) -> Result<Option<bool>, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("tuneSidechain");
+
+        if let Some(params) = &params {
+            Sidechain::validate(params).map_err(|e| {
+                graphql::Error::new("INVALID_SIDECHAIN")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
+
+        Ok(context.state().tune_sidechain(
             restream_id,
             output_id,
             mixin_id,
             sidechain,
-        )
+            params.map(Sidechain::new),
+        ))
+    }
+
+    /// Tunes a `SpeakerFilter` of the specified `Mixin`, choosing which
+    /// TeamSpeak channel members are captured by it.
+    ///
+    /// Changing `SpeakerFilter` is applied in real-time, without interrupting
+    /// the `Mixin`'s `Output`.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if a `SpeakerFilter` has been changed, `false` if it has
+    /// the same value already, or `null` if the specified `Output`
+    /// or `Mixin` doesn't exist.
+    fn tune_speakers(
+        #[graphql(
+            description = "ID of the `Restream` to tune the the `Mixin` in."
+        )]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `Output` of the tuned `Mixin`.")]
+        output_id: OutputId,
+        #[graphql(description = "ID of the tuned `Mixin`.")] mixin_id: MixinId,
+        #[graphql(description = "`SpeakerFilter` of TeamSpeak channel \
+                                 members to capture audio of.")]
+        speakers: spec::v2::SpeakerFilter,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("tuneSpeakers");
+
+        SpeakerFilter::validate(&speakers).map_err(|e| {
+            graphql::Error::new("INVALID_SPEAKER_FILTER")
+                .status(StatusCode::BAD_REQUEST)
+                .message(&e)
+        })?;
+
+        Ok(context.state().tune_speakers(
+            restream_id,
+            output_id,
+            mixin_id,
+            SpeakerFilter::new(speakers),
+        ))
     }
 
     /// Removes the specified recorded file.
@@ -1257,6 +1854,8 @@ impl MutationsRoot {
         kind: Option<PasswordKind>,
         context: &Context,
     ) -> Result<bool, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("setPassword");
+
         static HASH_CFG: Lazy<argon2::Config<'static>> =
             Lazy::new(argon2::Config::default);
 
@@ -1329,8 +1928,50 @@ impl MutationsRoot {
         google_api_key: Option<String>,
         #[graphql(description = "Maximum number of files in playlist")]
         max_downloading_files: Option<UNumber>,
+        #[graphql(description = "Global `RetryPolicy` of restarting failed \
+                                 re-streaming processes.\
+                                 \n\n\
+                                 If `null`, then the default one is used. \
+                                 If omitted, then the current one is kept.")]
+        retry_policy: Nullable<spec::v2::RetryPolicy>,
+        #[graphql(description = "`DvrRetention` policy of recorded files.\
+                                 \n\n\
+                                 If `null`, then the default one is used. \
+                                 If omitted, then the current one is kept.")]
+        dvr_retention: Nullable<spec::v2::DvrRetention>,
         context: &Context,
     ) -> Result<bool, graphql::Error> {
+        let _snapshot = context.state().record_snapshot("setSettings");
+
+        // Omitted policies are kept as they are, so clients unaware of them
+        // don't reset them.
+        let (retry_policy, dvr_retention) = {
+            let current = context.state().settings.lock_ref();
+            (
+                retry_policy.explicit().unwrap_or_else(|| {
+                    current.retry_policy.as_ref().map(RetryPolicy::export)
+                }),
+                dvr_retention.explicit().unwrap_or_else(|| {
+                    current.dvr_retention.as_ref().map(DvrRetention::export)
+                }),
+            )
+        };
+
+        if let Some(policy) = &retry_policy {
+            RetryPolicy::validate(policy).map_err(|e| {
+                graphql::Error::new("INVALID_RETRY_POLICY")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
+        if let Some(retention) = &dvr_retention {
+            DvrRetention::validate(retention).map_err(|e| {
+                graphql::Error::new("INVALID_DVR_RETENTION")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
+
         // Validate title
         let value = title.unwrap_or_default();
         if value.len() > 70 {
@@ -1345,8 +1986,33 @@ impl MutationsRoot {
         settings.enable_confirmation = enable_confirmation;
         settings.google_api_key = google_api_key;
         settings.max_downloading_files = max_downloading_files;
+        settings.retry_policy = retry_policy.map(RetryPolicy::new);
+        settings.dvr_retention = dvr_retention.map(DvrRetention::new);
         Ok(true)
     }
+
+    /// Rolls back the whole configuration of this server to the `Snapshot`
+    /// with the given `id`, replacing the current one.
+    ///
+    /// The current configuration is recorded as a new `Snapshot` beforehand,
+    /// so the rollback can be reverted too.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if a `Snapshot` with the given `id` doesn't exist,
+    /// otherwise always returns `true`.
+    fn rollback_to(
+        #[graphql(description = "ID of the `Snapshot` to roll back to.")]
+        snapshot_id: SnapshotId,
+        context: &Context,
+    ) -> Option<bool> {
+        let _snapshot = context.state().record_snapshot("rollbackTo");
+
+        context.state().rollback_to(snapshot_id)?;
+        let mut commands = context.state().file_commands.lock_mut();
+        commands.push(FileCommand::ListOfFilesChanged);
+        Some(true)
+    }
 }
 
 /// Root of all [GraphQL queries][1] in the [`Schema`].
@@ -1369,6 +2035,8 @@ impl QueriesRoot {
             enable_confirmation: settings.enable_confirmation,
             google_api_key: settings.google_api_key,
             max_downloading_files: settings.max_downloading_files,
+            retry_policy: settings.retry_policy.unwrap_or_default(),
+            dvr_retention: settings.dvr_retention.unwrap_or_default(),
         }
     }
 
@@ -1391,27 +2059,92 @@ impl QueriesRoot {
         context.state().restreams.get_cloned()
     }
 
+    /// Returns the nearest upcoming actions of `Schedule`s of all the
+    /// `Restream`s and `Output`s, ordered by their time.
+    fn upcoming_scheduled_actions(
+        #[graphql(
+            description = "Maximum number of actions to return.",
+            default = 20
+        )]
+        limit: i32,
+        context: &Context,
+    ) -> Vec<ScheduledAction> {
+        context
+            .state()
+            .upcoming_scheduled_actions(usize::try_from(limit).unwrap_or(0))
+    }
+
     /// Returns list of recorded files of the specified `Output`.
     ///
     /// If returned list is empty, the there is no recorded files for the
     /// specified `Output`.
     ///
-    /// Each recorded file is represented as a relative path on [SRS] HTTP
-    /// server in `dvr/` directory, so the download link should look like this:
+    /// Each recorded file is represented as a relative path (like
+    /// `<output-id>/<file>`) served by this server's HTTP endpoint requiring
+    /// the same authorization as this GraphQL API, and supporting byte-range
+    /// requests, so the download link should look like this:
     /// ```ignore
-    /// http://my.host:8080/dvr/returned/file/path.flv
-    /// http://my.host:8080/dvr/returned/file/path.wav
-    /// http://my.host:8080/dvr/returned/file/path.mp3
+    /// http://my.host/dvr/returned/file/path.flv
+    /// http://my.host/dvr/returned/file/path.wav
+    /// http://my.host/dvr/returned/file/path.mp3
     /// ```
-    ///
-    /// [SRS]: https://github.com/ossrs/srs
     async fn dvr_files(
         #[graphql(
             description = "ID of the `Output` to return recorded files of."
         )]
         id: OutputId,
     ) -> Vec<String> {
-        dvr::Storage::global().list_files(id).await
+        dvr::Storage::global()
+            .list_files(id.into())
+            .await
+            .into_iter()
+            .flat_map(|r| r.files)
+            .collect()
+    }
+
+    /// Returns recorded files of the specified `Output` grouped into
+    /// recording sessions, ordered by their start time.
+    ///
+    /// Files recorded one right after another (because of rotation or a short
+    /// interruption, for example) belong to the same session.
+    ///
+    /// Each file is represented in the same way as in `Query.dvrFiles`. All the
+    /// files of a session may be downloaded as a single ZIP archive:
+    /// ```ignore
+    /// http://my.host/dvr/<output-id>/recording.zip?startedAt=<started-at>
+    /// ```
+    async fn dvr_recordings(
+        #[graphql(description = "ID of the `Output` to return recordings of.")]
+        id: OutputId,
+    ) -> Vec<dvr::Recording> {
+        dvr::Storage::global().list_files(id.into()).await
+    }
+
+    /// Returns recorded files of the specified `Input` (see
+    /// `Mutation.setInputRecording`) grouped into recording sessions, ordered
+    /// by their start time.
+    ///
+    /// Files and sessions are represented and served in the same way as in
+    /// `Query.dvrRecordings`, using the `Input`'s ID instead of the `Output`'s
+    /// one.
+    async fn input_dvr_recordings(
+        #[graphql(description = "ID of the `Input` to return recordings of.")]
+        id: InputId,
+    ) -> Vec<dvr::Recording> {
+        dvr::Storage::global().list_files(id.into()).await
+    }
+
+    /// Returns current usage of the DVR storage by every `Output` and `Input`
+    /// having any recorded files.
+    async fn dvr_usage(
+        context: &Context,
+    ) -> Result<Vec<dvr::Usage>, graphql::Error> {
+        let restreams = context.state().restreams.get_cloned();
+        dvr::Storage::global().usage(&restreams).await.map_err(|e| {
+            graphql::Error::new("DVR_USAGE_FAILED")
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .message(&format!("Failed to read DVR files: {e}"))
+        })
     }
 
     /// Returns `Restream`s happening on this server and identifiable by the
@@ -1427,6 +2160,14 @@ impl QueriesRoot {
             default = Vec::new(),
         )]
         ids: Vec<RestreamId>,
+        #[graphql(
+            description = "Indicator whether secrets (like identities of \
+                           TeamSpeak clients) should be exported too, so \
+                           the imported `Restream`s are recognized as the \
+                           same ones.",
+            default = false
+        )]
+        with_secrets: bool,
         context: &Context,
     ) -> Result<Option<String>, graphql::Error> {
         let settings = context.state().settings.get_cloned().export();
@@ -1436,12 +2177,18 @@ impl QueriesRoot {
             .get_cloned()
             .into_iter()
             .filter_map(|r| {
-                (ids.is_empty() || ids.contains(&r.id)).then(|| r.export())
+                (ids.is_empty() || ids.contains(&r.id)).then(|| {
+                    if with_secrets {
+                        r.export_with_secrets()
+                    } else {
+                        r.export()
+                    }
+                })
             })
             .collect::<Vec<_>>();
         (!restreams.is_empty())
             .then(|| {
-                let spec: Spec = spec::v1::Spec {
+                let spec: Spec = spec::v2::Spec {
                     settings: Some(settings),
                     restreams,
                 }
@@ -1452,6 +2199,35 @@ impl QueriesRoot {
             })
             .transpose()
     }
+
+    /// Returns the history of configuration `Snapshot`s of this server, taken
+    /// before each mutation, ordered from the newest to the oldest one.
+    fn snapshots(context: &Context) -> Vec<Snapshot> {
+        let mut snapshots = context.state().history.get_cloned();
+        snapshots.reverse();
+        snapshots
+    }
+
+    /// Returns changes between the configuration `Snapshot`s with the given
+    /// IDs.
+    ///
+    /// If `to` is not specified, then the current configuration is compared
+    /// with.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if any of the specified `Snapshot`s doesn't exist.
+    fn diff_snapshots(
+        #[graphql(description = "ID of the `Snapshot` to compare from.")]
+        from: SnapshotId,
+        #[graphql(description = "ID of the `Snapshot` to compare to. \
+                                 If not specified, then the current \
+                                 configuration is used.")]
+        to: Option<SnapshotId>,
+        context: &Context,
+    ) -> Option<Vec<SpecChange>> {
+        context.state().diff_snapshots(from, to)
+    }
 }
 
 /// Root of all [GraphQL subscriptions][1] in the [`Schema`].
@@ -1479,6 +2255,8 @@ impl SubscriptionsRoot {
                 enable_confirmation: h.enable_confirmation,
                 google_api_key: h.google_api_key,
                 max_downloading_files: h.max_downloading_files,
+                retry_policy: h.retry_policy.unwrap_or_default(),
+                dvr_retention: h.dvr_retention.unwrap_or_default(),
             })
             .to_stream()
             .boxed()
@@ -1646,6 +2424,12 @@ pub struct Info {
     /// This value can be overwritten by the similar setting
     /// on a particular [Restream]
     pub max_downloading_files: Option<UNumber>,
+
+    /// Global `RetryPolicy` of restarting failed re-streaming processes
+    pub retry_policy: RetryPolicy,
+
+    /// `DvrRetention` policy of recorded files
+    pub dvr_retention: DvrRetention,
 }
 
 /// Restream with its source output if it has any
@@ -2,12 +2,23 @@
 //!
 //! [GraphQL]: https://graphql.com
 
+use std::time::Duration;
+
 use futures::{stream::BoxStream, StreamExt};
 use futures_signals::signal::SignalExt as _;
 use juniper::{graphql_object, graphql_subscription, RootNode};
 
-use crate::state::{
-    Delay, MixinId, Output, OutputId, RestreamId, Volume, VolumeLevel,
+use actix_web::http::StatusCode;
+
+use crate::{
+    api::graphql,
+    spec,
+    state::{
+        Delay, MixinId, Output, OutputId, RestreamId, Sidechain, SpeakerFilter,
+        TalkingSpeakers, Volume, VolumeLevel,
+    },
+    stream_statistics::AudioLevel,
+    types::UNumber,
 };
 
 use super::Context;
@@ -44,7 +55,44 @@ impl MutationsRoot {
             restream_id,
             output_id,
             mixin_id,
-            Volume { level, muted },
+            Volume {
+                level,
+                muted,
+                solo: false,
+            },
+        )
+    }
+
+    /// Solos the specified `Output`'s origin audio or one of its `Mixin`s, so
+    /// all the other non-soloed ones are muted, or unsolos it.
+    fn tune_solo(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: Option<MixinId>,
+        solo: bool,
+        context: &Context,
+    ) -> Option<bool> {
+        context
+            .state()
+            .tune_solo(restream_id, output_id, mixin_id, solo)
+    }
+
+    /// Fades a `Volume` rate of the specified `Output` or one of its `Mixin`s
+    /// to the `target` one smoothly, during the `duration` in milliseconds.
+    fn fade_volume(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: Option<MixinId>,
+        target: VolumeLevel,
+        duration: UNumber,
+        context: &Context,
+    ) -> Option<bool> {
+        context.state().fade_volume(
+            restream_id,
+            output_id,
+            mixin_id,
+            target,
+            Duration::from_millis(duration.0.into()),
         )
     }
 
@@ -62,19 +110,56 @@ impl MutationsRoot {
     }
 
     /// Tunes a the specified [`Mixin.sidechain`] in this [`State`]
+    ///
+    /// Changing `Sidechain` parameters of an already side-chained `Mixin` is
+    /// applied in real-time.
     fn tune_sidechain(
         restream_id: RestreamId,
         output_id: OutputId,
         mixin_id: MixinId,
         sidechain: bool,
+        params: Option<spec::v2::Sidechain>,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().tune_sidechain(
+    ) -> Result<Option<bool>, graphql::Error> {
+        if let Some(params) = &params {
+            Sidechain::validate(params).map_err(|e| {
+                graphql::Error::new("INVALID_SIDECHAIN")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
+
+        Ok(context.state().tune_sidechain(
             restream_id,
             output_id,
             mixin_id,
             sidechain,
-        )
+            params.map(Sidechain::new),
+        ))
+    }
+
+    /// Tunes a the specified [`Mixin.speakers`] in this [`State`]
+    ///
+    /// Changing `SpeakerFilter` of a `Mixin` is applied in real-time.
+    fn tune_speakers(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: MixinId,
+        speakers: spec::v2::SpeakerFilter,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        SpeakerFilter::validate(&speakers).map_err(|e| {
+            graphql::Error::new("INVALID_SPEAKER_FILTER")
+                .status(StatusCode::BAD_REQUEST)
+                .message(&e)
+        })?;
+
+        Ok(context.state().tune_speakers(
+            restream_id,
+            output_id,
+            mixin_id,
+            SpeakerFilter::new(speakers),
+        ))
     }
 }
 
@@ -126,4 +211,44 @@ impl SubscriptionsRoot {
             .to_stream()
             .boxed()
     }
+
+    /// Subscribes to real-time `AudioLevel`s of the specified `Output`'s
+    /// origin audio and each of its `Mixin`s, updated a few times per second.
+    ///
+    /// Empty while the `Output` is not being mixed.
+    async fn audio_levels(
+        output_id: OutputId,
+        context: &Context,
+    ) -> BoxStream<'static, Vec<AudioLevel>> {
+        context
+            .state()
+            .audio_levels
+            .signal_ref(move |levels| {
+                levels.get(&output_id).cloned().unwrap_or_default()
+            })
+            .dedupe_cloned()
+            .to_stream()
+            .boxed()
+    }
+
+    /// Subscribes to [TeamSpeak] channel members talking at the moment in the
+    /// channels of the specified `Output`'s `Mixin`s.
+    ///
+    /// Empty while the `Output` is not being mixed.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    async fn talking_speakers(
+        output_id: OutputId,
+        context: &Context,
+    ) -> BoxStream<'static, Vec<TalkingSpeakers>> {
+        context
+            .state()
+            .talking_speakers
+            .signal_ref(move |talking| {
+                talking.get(&output_id).cloned().unwrap_or_default()
+            })
+            .dedupe_cloned()
+            .to_stream()
+            .boxed()
+    }
 }
@@ -66,6 +66,18 @@ pub struct Opts {
     )]
     pub state_path: PathBuf,
 
+    /// Number of timestamped backups of the state file to keep.
+    #[arg(
+        long,
+        env = "EPHYR_RESTREAMER_STATE_BACKUPS",
+        default_value = "5",
+        help = "Number of state file backups to keep",
+        long_help = "Number of timestamped backups of the state file to keep \
+                     next to it. The newest valid backup is used if the state \
+                     file is corrupted."
+    )]
+    pub state_backups: usize,
+
     /// Path to [SRS] installation directory.
     ///
     /// [SRS]: https://github.com/ossrs/srs
@@ -98,6 +110,25 @@ pub struct Opts {
     )]
     pub srs_http_dir: PathBuf,
 
+    /// Path to the directory where [DVR] files are recorded into.
+    ///
+    /// Should be located outside of [`Opts::srs_http_dir`], so the recorded
+    /// files are not served publicly by [SRS].
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    /// [SRS]: https://github.com/ossrs/srs
+    #[arg(
+        long,
+        env = "EPHYR_RESTREAMER_DVR_DIR",
+        default_value = "/var/lib/ephyr/dvr",
+        help = "Path to recorded DVR files",
+        long_help = "Path to the directory where DVR files are recorded into.\
+                     \n\n\
+                     Should be located outside of --srs-http-dir, so the \
+                     recorded files are not served publicly by SRS."
+    )]
+    pub dvr_dir: PathBuf,
+
     /// Path to [FFmpeg] binary.
     ///
     /// [FFmpeg]: https://ffmpeg.org
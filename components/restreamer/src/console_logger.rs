@@ -24,6 +24,8 @@ pub enum ConsoleMessageSource {
     Client,
     /// Message came from statistics subsystem
     Statistics,
+    /// Message came from DVR subsystem
+    Dvr,
 }
 
 /// Message for console
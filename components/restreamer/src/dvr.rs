@@ -2,19 +2,25 @@
 //!
 //! [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
 
+mod zip;
+
 use std::{
+    collections::{HashMap, HashSet},
     ffi::OsString,
     io,
     path::{Path, PathBuf},
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
 use ephyr_log::{tracing, tracing::instrument};
 use futures::{future, stream::TryStreamExt};
+use juniper::GraphQLObject;
 use once_cell::sync::OnceCell;
+use systemstat::{Platform as _, System};
 use tokio::fs;
-use tokio_stream::wrappers::ReadDirStream;
+use tokio_stream::wrappers::{ReadDirStream, ReceiverStream};
 use url::Url;
 use uuid::Uuid;
 
@@ -72,16 +78,34 @@ impl Storage {
         let mut full = self.root_path.clone();
         full.push(output.id.to_string());
         full.push(output.dst.path().trim_start_matches('/'));
+        let mut url = Url::from_file_path(full)
+            .map_err(|e| anyhow!("Failed convert path to URL: {:?}", e))?;
+        // Preserve the `Rotation` parameters.
+        url.set_query(output.dst.query());
+        Ok(url)
+    }
+
+    /// Forms a correct [`Url`] pointing to the file for recording a live stream
+    /// received by the given [`state::Input`].
+    ///
+    /// # Errors
+    /// If failed to convert path to [`Url`]
+    pub fn input_file_url(&self, input: &state::Input) -> anyhow::Result<Url> {
+        let mut full = self.root_path.clone();
+        full.push(input.id.to_string());
+        full.push(format!("{}.flv", input.key));
         Url::from_file_path(full)
             .map_err(|e| anyhow!("Failed convert path to URL: {:?}", e))
     }
 
-    /// Lists stored [DVR] files of the given [`state::Output`].
+    /// Lists stored [DVR] files of the [`state::Output`] or [`state::Input`]
+    /// with the given `id`, grouped into [`Recording`] sessions.
     ///
-    /// Returns them as relative paths to this [`Storage::root_path`].
+    /// Files are represented as relative paths to this
+    /// [`Storage::root_path`].
     ///
     /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
-    pub async fn list_files(&self, id: state::OutputId) -> Vec<String> {
+    pub async fn list_files(&self, id: Uuid) -> Vec<Recording> {
         let dir = &self.root_path;
 
         let mut output_dir = dir.clone();
@@ -90,18 +114,17 @@ impl Storage {
         if let Ok(read_dir) = fs::read_dir(output_dir).await {
             return ReadDirStream::new(read_dir)
                 .try_filter_map(|i| async move {
-                    Ok(i.file_type()
-                        .await?
-                        .is_file()
-                        .then(|| i.path())
-                        .and_then(|p| {
-                            Some(
-                                p.strip_prefix(dir).ok()?.display().to_string(),
-                            )
-                        }))
+                    let meta = i.metadata().await?;
+                    Ok(meta.is_file().then(|| i.path()).and_then(|p| {
+                        RecordedFile::new(
+                            p.strip_prefix(dir).ok()?,
+                            meta.modified().ok()?,
+                        )
+                    }))
                 })
                 .try_collect()
                 .await
+                .map(Recording::group)
                 .unwrap_or_else(|e| {
                     if e.kind() != io::ErrorKind::NotFound {
                         tracing::error!(
@@ -116,6 +139,60 @@ impl Storage {
         vec![]
     }
 
+    /// Returns the absolute path of a [DVR] file with the given `name`,
+    /// recorded by the [`state::Output`] or [`state::Input`] with the given
+    /// `id`.
+    ///
+    /// Returns [`None`] if the `name` is not a valid file name.
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    #[must_use]
+    pub fn file_path(&self, id: Uuid, name: &str) -> Option<PathBuf> {
+        if name.is_empty() || name.starts_with('.') || name.contains('/') {
+            return None;
+        }
+        let mut full = self.root_path.clone();
+        full.push(id.to_string());
+        full.push(name);
+        Some(full)
+    }
+
+    /// Streams a [ZIP] archive of all the files of the [`Recording`] session
+    /// started at the given moment by the [`state::Output`] or
+    /// [`state::Input`] with the given `id`.
+    ///
+    /// The moment may differ from the [`Recording::started_at`] up to a
+    /// second, so it may be passed with a lower precision.
+    ///
+    /// Returns [`None`] if there is no such [`Recording`].
+    ///
+    /// [ZIP]: https://en.wikipedia.org/wiki/ZIP_(file_format)
+    pub async fn zip_recording(
+        &self,
+        id: Uuid,
+        started_at: DateTime<Utc>,
+    ) -> Option<ReceiverStream<io::Result<Vec<u8>>>> {
+        let recording = self
+            .list_files(id)
+            .await
+            .into_iter()
+            .filter(|r| (r.started_at - started_at).num_seconds().abs() < 1)
+            .min_by_key(|r| (r.started_at - started_at).abs())?;
+        let files = recording
+            .files
+            .into_iter()
+            .map(|f| {
+                let path = self.root_path.join(&f);
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or(f);
+                (name, path)
+            })
+            .collect();
+        Some(zip::stream(files))
+    }
+
     /// Removes a [DVR] file from this [`Storage`] identified by its relative
     /// `path` to this [`Storage::root_path`].
     ///
@@ -141,13 +218,194 @@ impl Storage {
         true
     }
 
+    /// Returns the current [`Usage`] of this [`Storage`] by every
+    /// [`state::Output`] and [`state::Input`] of the given [`state::Restream`]s
+    /// having any recorded files.
+    ///
+    /// # Errors
+    ///
+    /// If fails to read the stored files.
+    #[allow(clippy::cast_precision_loss)] // not an issue for file sizes
+    pub async fn usage(
+        &self,
+        restreams: &[state::Restream],
+    ) -> io::Result<Vec<Usage>> {
+        let outputs: HashSet<Uuid> = restreams
+            .iter()
+            .flat_map(|r| r.outputs.iter().map(|o| o.id.into()))
+            .collect();
+        let mut usage = HashMap::<Uuid, Usage>::new();
+        for f in self.stored_files().await? {
+            let u = usage.entry(f.owner_id).or_insert_with(|| {
+                let is_output = outputs.contains(&f.owner_id);
+                Usage {
+                    output_id: is_output.then(|| f.owner_id.into()),
+                    input_id: (!is_output).then(|| f.owner_id.into()),
+                    files: 0,
+                    size: 0.0,
+                }
+            });
+            u.files += 1;
+            u.size += f.size as f64;
+        }
+        Ok(usage.into_values().collect())
+    }
+
+    /// Returns free space (in bytes) of the disk this [`Storage`] is located
+    /// on, if it can be detected.
+    #[must_use]
+    pub fn free_space(&self) -> Option<u64> {
+        System::new()
+            .mounts()
+            .map_err(|e| {
+                tracing::error!("Failed to detect free space of DVR disk: {e}");
+            })
+            .ok()?
+            .into_iter()
+            .filter(|m| self.root_path.starts_with(&m.fs_mounted_on))
+            .max_by_key(|m| m.fs_mounted_on.len())
+            .map(|m| m.avail.as_u64())
+    }
+
+    /// Removes the oldest [DVR] files of this [`Storage`] violating the given
+    /// [`state::DvrRetention`] policy.
+    ///
+    /// The latest file of every [`state::Output`] and [`state::Input`] is
+    /// never removed because of size limits, as it may be being recorded at
+    /// the moment.
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    #[instrument(skip_all, name = "dvr::enforce_retention")]
+    pub async fn enforce_retention(&self, retention: &state::DvrRetention) {
+        let mut files = match self.stored_files().await {
+            Ok(files) => files,
+            Err(e) => {
+                tracing::error!("Failed to list DVR files: {e}");
+                return;
+            }
+        };
+        files.sort_by_key(|f| f.modified_at);
+
+        let mut latest = HashMap::new();
+        for (n, f) in files.iter().enumerate() {
+            _ = latest.insert(f.owner_id, n);
+        }
+        let is_latest = |n: usize, f: &StoredFile| latest[&f.owner_id] == n;
+
+        let mut expired = vec![false; files.len()];
+        if let Some(max_age) = retention.max_age_secs() {
+            let now = SystemTime::now();
+            for (n, f) in files.iter().enumerate() {
+                expired[n] = now
+                    .duration_since(f.modified_at)
+                    .map_or(false, |age| age.as_secs() > max_age);
+            }
+        }
+
+        if let Some(max) = retention.max_output_bytes() {
+            let mut sizes = HashMap::<Uuid, u64>::new();
+            for (_, f) in files.iter().enumerate().filter(|(n, _)| !expired[*n])
+            {
+                *sizes.entry(f.owner_id).or_default() += f.size;
+            }
+            for (n, f) in files.iter().enumerate() {
+                let size = sizes.entry(f.owner_id).or_default();
+                if *size > max && !expired[n] && !is_latest(n, f) {
+                    expired[n] = true;
+                    *size -= f.size;
+                }
+            }
+        }
+
+        if let Some(max) = retention.max_total_bytes() {
+            let mut size: u64 = files
+                .iter()
+                .enumerate()
+                .filter(|(n, _)| !expired[*n])
+                .map(|(_, f)| f.size)
+                .sum();
+            for (n, f) in files.iter().enumerate() {
+                if size > max && !expired[n] && !is_latest(n, f) {
+                    expired[n] = true;
+                    size -= f.size;
+                }
+            }
+        }
+
+        for (f, _) in files.iter().zip(expired).filter(|(_, e)| *e) {
+            tracing::info!("Deleting expired DVR file: {}", f.path.display());
+            if let Err(e) = fs::remove_file(&f.path).await {
+                if e.kind() != io::ErrorKind::NotFound {
+                    tracing::error!(
+                        "Failed to remove {} DVR file: {e}",
+                        f.path.display(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Lists all the [DVR] files stored in this [`Storage`] by
+    /// [`state::Output`]s and [`state::Input`]s.
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    async fn stored_files(&self) -> io::Result<Vec<StoredFile>> {
+        let mut files = vec![];
+        let mut dirs = match fs::read_dir(&self.root_path).await {
+            Ok(dirs) => dirs,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(files),
+            Err(e) => return Err(e),
+        };
+        while let Some(dir) = dirs.next_entry().await? {
+            let Some(owner_id) = dir
+                .file_name()
+                .to_str()
+                .and_then(|n| Uuid::parse_str(n).ok())
+            else {
+                continue;
+            };
+            if !dir.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut entries = fs::read_dir(dir.path()).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let meta = entry.metadata().await?;
+                if meta.is_file() {
+                    files.push(StoredFile {
+                        owner_id,
+                        path: entry.path(),
+                        size: meta.len(),
+                        modified_at: meta.modified()?,
+                    });
+                }
+            }
+        }
+        Ok(files)
+    }
+
     /// Cleans up any [DVR] files of this [`Storage`] not being associated with
-    /// [`state::Output`]s of the given renewed [`state::Restream`]s.
+    /// [`state::Output`]s or [`state::Input`]s of the given renewed
+    /// [`state::Restream`]s.
     ///
     /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
     #[instrument(skip_all, name = "dvr::cleanup")]
     pub async fn cleanup(&self, restreams: &[state::Restream]) {
+        fn collect_inputs(input: &state::Input, ids: &mut HashSet<Uuid>) {
+            _ = ids.insert(input.id.into());
+            if let Some(state::InputSrc::Failover(s)) = &input.src {
+                for i in &s.inputs {
+                    collect_inputs(i, ids);
+                }
+            }
+        }
+
         // TODO: Consider only `file:///` outputs?
+        let mut ids = HashSet::new();
+        for r in restreams {
+            ids.extend(r.outputs.iter().map(|o| Uuid::from(o.id)));
+            collect_inputs(&r.input, &mut ids);
+        }
+
         if let Ok(read_dir) = fs::read_dir(&self.root_path).await {
             ReadDirStream::new(read_dir)
                 .try_filter(|i| {
@@ -155,12 +413,7 @@ impl Storage {
                         i.file_name()
                             .to_str()
                             .and_then(|n| Uuid::parse_str(n).ok())
-                            .map_or(true, |id| {
-                                let id = id.into();
-                                !restreams.iter().any(|r| {
-                                    r.outputs.iter().any(|o| o.id == id)
-                                })
-                            }),
+                            .map_or(true, |id| !ids.contains(&id)),
                     )
                 })
                 .try_for_each_concurrent(4, |i| async move {
@@ -186,18 +439,220 @@ impl Storage {
     }
 }
 
+/// Session of a [DVR] recording, consisting of one or more files recorded one
+/// right after another.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+#[graphql(name = "DvrRecording")]
+pub struct Recording {
+    /// Time when this [`Recording`] session has started.
+    pub started_at: DateTime<Utc>,
+
+    /// Time when the last file of this [`Recording`] session has been
+    /// modified.
+    pub updated_at: DateTime<Utc>,
+
+    /// Relative paths of the recorded files, in the order of recording.
+    pub files: Vec<String>,
+}
+
+impl Recording {
+    /// Maximum gap between the end of a recorded file and the start of the
+    /// next one, so they're still considered as a single [`Recording`]
+    /// session.
+    ///
+    /// Covers rotations and short interruptions (re-connections of a flapping
+    /// input, for example).
+    pub const MAX_GAP: Duration = Duration::from_secs(60);
+
+    /// Groups the given [`RecordedFile`]s into [`Recording`] sessions, ordered
+    /// by their start time.
+    fn group(mut files: Vec<RecordedFile>) -> Vec<Self> {
+        files.sort_by(|a, b| {
+            a.started_at.cmp(&b.started_at).then(a.part.cmp(&b.part))
+        });
+
+        let mut recordings: Vec<Self> = vec![];
+        for file in files {
+            let started_at = DateTime::<Utc>::from(file.started_at);
+            let updated_at = DateTime::<Utc>::from(file.modified_at);
+            match recordings.last_mut() {
+                Some(last)
+                    if (started_at - last.updated_at)
+                        .to_std()
+                        .map_or(true, |gap| gap <= Self::MAX_GAP) =>
+                {
+                    last.updated_at = last.updated_at.max(updated_at);
+                    last.files.push(file.path);
+                }
+                _ => recordings.push(Self {
+                    started_at,
+                    updated_at,
+                    files: vec![file.path],
+                }),
+            }
+        }
+        recordings
+    }
+}
+
+/// Usage of a DVR [`Storage`] by a single `Output` or `Input`.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+#[graphql(name = "DvrUsage")]
+pub struct Usage {
+    /// ID of the `Output` recorded the files, if they were recorded by an
+    /// `Output`.
+    pub output_id: Option<state::OutputId>,
+
+    /// ID of the `Input` recorded the files, if they were not recorded by an
+    /// `Output`.
+    pub input_id: Option<state::InputId>,
+
+    /// Number of the recorded files.
+    pub files: i32,
+
+    /// Total size of the recorded files, in bytes.
+    pub size: f64,
+}
+
+/// [DVR] file stored in a [`Storage`] by a [`state::Output`] or a
+/// [`state::Input`].
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+#[derive(Clone, Debug)]
+struct StoredFile {
+    /// ID of the [`state::Output`] or [`state::Input`] recorded this file.
+    owner_id: Uuid,
+
+    /// Absolute path of this file.
+    path: PathBuf,
+
+    /// Size of this file, in bytes.
+    size: u64,
+
+    /// Time when this file has been modified last time.
+    modified_at: SystemTime,
+}
+
+/// Single stored [DVR] file.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+#[derive(Clone, Debug)]
+struct RecordedFile {
+    /// Relative path of this file to the [`Storage::root_path`].
+    path: String,
+
+    /// Time when recording of this file has started.
+    ///
+    /// Parsed from the timestamp in the file name (see [`new_file_path()`]),
+    /// falling back to the modification time.
+    started_at: SystemTime,
+
+    /// Time when this file has been modified last time.
+    modified_at: SystemTime,
+
+    /// Number of this file among the segments of a single [FFmpeg] run, if it
+    /// was recorded with a [`Rotation`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    part: Option<u32>,
+}
+
+impl RecordedFile {
+    /// Creates a new [`RecordedFile`] out of its relative `path` and
+    /// modification time.
+    fn new(path: &Path, modified_at: SystemTime) -> Option<Self> {
+        let stem = path.file_stem()?.to_str()?;
+        let mut parts = stem.rsplit('_');
+        let (micros, part) = match (parts.next(), parts.next()) {
+            (Some(part), Some(ts)) if part.len() == 3 => {
+                (ts.parse::<u64>().ok(), part.parse::<u32>().ok())
+            }
+            (Some(ts), _) => (ts.parse::<u64>().ok(), None),
+            _ => (None, None),
+        };
+        let started_at = micros
+            .map(|m| SystemTime::UNIX_EPOCH + Duration::from_micros(m))
+            .unwrap_or(modified_at);
+        Some(Self {
+            path: path.display().to_string(),
+            started_at,
+            modified_at,
+            part: micros.and(part),
+        })
+    }
+}
+
+/// Policy of rotating files of a [DVR] recording, so a long recording is
+/// split into multiple files.
+///
+/// It's specified via query parameters of a `file:///` [`Url`], at most one
+/// of them may be used:
+/// - `rotate_minutes`: starts a new file every N minutes, without
+///   interrupting the recording;
+/// - `rotate_mb`: starts a new file once the current one grows up to N
+///   megabytes, restarting the recording (so a small gap may occur).
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Rotation {
+    /// Duration of a single file, in minutes.
+    pub minutes: Option<u16>,
+
+    /// Maximum size of a single file, in megabytes.
+    pub megabytes: Option<u32>,
+}
+
+impl Rotation {
+    /// Name of the query parameter specifying [`Rotation::minutes`].
+    pub const MINUTES_PARAM: &'static str = "rotate_minutes";
+
+    /// Name of the query parameter specifying [`Rotation::megabytes`].
+    pub const MEGABYTES_PARAM: &'static str = "rotate_mb";
+
+    /// Parses a [`Rotation`] out of the query parameters of the given
+    /// `file:///` [`Url`].
+    ///
+    /// Returns [`None`] if the parameters are invalid.
+    #[must_use]
+    pub fn parse(url: &Url) -> Option<Self> {
+        let mut rotation = Self::default();
+        for (k, v) in url.query_pairs() {
+            match k.as_ref() {
+                Self::MINUTES_PARAM => {
+                    rotation.minutes =
+                        Some(v.parse::<u16>().ok().filter(|m| *m > 0)?);
+                }
+                Self::MEGABYTES_PARAM => {
+                    rotation.megabytes =
+                        Some(v.parse::<u32>().ok().filter(|m| *m > 0)?);
+                }
+                _ => return None,
+            }
+        }
+        (rotation.minutes.is_none() || rotation.megabytes.is_none())
+            .then_some(rotation)
+    }
+}
+
 /// Creates a new recording file path from the given DVR file [`Url`] (formed by
 /// [`Storage::file_url()`]) appended with the current timestamp in microseconds
 /// to ensure its uniqueness.
 ///
+/// If `segmented` is `true`, then the file name is additionally appended with
+/// a `%03d` pattern, to be substituted with a segment number by [FFmpeg].
+///
 /// Also, ensures that the appropriate parent directory for the file exists.
 ///
 /// # Errors
 ///
 /// If cannot create a file path from the given [`Url`], or fails to create its
 /// parent directory.
+///
+/// [FFmpeg]: https://ffmpeg.org
 #[allow(clippy::missing_panics_doc)]
-pub async fn new_file_path(url: &Url) -> io::Result<PathBuf> {
+pub async fn new_file_path(url: &Url, segmented: bool) -> io::Result<PathBuf> {
     let mut path = url.to_file_path().map_err(|()| {
         io::Error::new(io::ErrorKind::Other, "File URL contains bad file path")
     })?;
@@ -214,7 +669,11 @@ pub async fn new_file_path(url: &Url) -> io::Result<PathBuf> {
     if let Some(name) = path.file_stem() {
         file_name.push(name);
     }
-    file_name.push(format!("_{}.", now.as_micros()));
+    file_name.push(format!("_{}", now.as_micros()));
+    if segmented {
+        file_name.push("_%03d");
+    }
+    file_name.push(".");
     if let Some(ext) = path.extension() {
         file_name.push(ext);
     }
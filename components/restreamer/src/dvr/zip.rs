@@ -0,0 +1,321 @@
+//! Streaming of [ZIP] archives bundling recorded [DVR] files.
+//!
+//! Files are stored without compression (as they're compressed already), and
+//! [ZIP64] format is always used, so files of any size are supported.
+//!
+//! [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+//! [ZIP]: https://en.wikipedia.org/wiki/ZIP_(file_format)
+//! [ZIP64]: https://en.wikipedia.org/wiki/ZIP_(file_format)#ZIP64
+
+use std::{io, path::PathBuf, time::SystemTime};
+
+use chrono::{DateTime, Datelike as _, Timelike as _, Utc};
+use tokio::{fs, io::AsyncReadExt as _, sync::mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Size of a single chunk of a streamed file, in bytes.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Version of the [ZIP] specification required to extract [ZIP64] archives.
+///
+/// [ZIP]: https://en.wikipedia.org/wiki/ZIP_(file_format)
+/// [ZIP64]: https://en.wikipedia.org/wiki/ZIP_(file_format)#ZIP64
+const VERSION: u16 = 45;
+
+/// General purpose flags of archived files: sizes and CRC-32 follow the data
+/// in a descriptor, and names are UTF-8 encoded.
+const FLAGS: u16 = 0x0008 | 0x0800;
+
+/// Streams a [ZIP] archive of the given files, each one being a pair of its
+/// name in the archive and its path on the disk.
+///
+/// Archive is written in a background task, which stops once the returned
+/// stream is dropped.
+///
+/// [ZIP]: https://en.wikipedia.org/wiki/ZIP_(file_format)
+pub(crate) fn stream(
+    files: Vec<(String, PathBuf)>,
+) -> ReceiverStream<io::Result<Vec<u8>>> {
+    let (tx, rx) = mpsc::channel(4);
+    drop(tokio::spawn(async move {
+        if let Err(e) = write(files, &tx).await {
+            _ = tx.send(Err(e)).await;
+        }
+    }));
+    ReceiverStream::new(rx)
+}
+
+/// Already written file of a [ZIP] archive.
+///
+/// [ZIP]: https://en.wikipedia.org/wiki/ZIP_(file_format)
+struct Entry {
+    /// Name of the file in the archive.
+    name: String,
+
+    /// [DOS] time and date of the file's last modification.
+    ///
+    /// [DOS]: https://learn.microsoft.com/windows/win32/sysinfo/ms-dos-date-and-time
+    modified_at: (u16, u16),
+
+    /// CRC-32 checksum of the file's data.
+    crc: u32,
+
+    /// Size of the file's data, in bytes.
+    size: u64,
+
+    /// Offset of the file's local header in the archive.
+    offset: u64,
+}
+
+/// Writes a [ZIP] archive of the given `files` into the given channel.
+///
+/// [ZIP]: https://en.wikipedia.org/wiki/ZIP_(file_format)
+async fn write(
+    files: Vec<(String, PathBuf)>,
+    tx: &mpsc::Sender<io::Result<Vec<u8>>>,
+) -> io::Result<()> {
+    let mut offset = 0;
+    let mut entries = Vec::with_capacity(files.len());
+    for (name, path) in files {
+        let mut file = fs::File::open(&path).await?;
+        let modified_at = dos_date_time(file.metadata().await?.modified()?);
+
+        let header_offset = offset;
+        offset += send(tx, local_header(&name, modified_at)).await?;
+
+        let mut crc = crc32fast::Hasher::new();
+        let mut size = 0;
+        loop {
+            let mut chunk = vec![0; CHUNK_SIZE];
+            let n = file.read(&mut chunk).await?;
+            if n == 0 {
+                break;
+            }
+            chunk.truncate(n);
+            crc.update(&chunk);
+            size += n as u64;
+            offset += send(tx, chunk).await?;
+        }
+        let crc = crc.finalize();
+        offset += send(tx, data_descriptor(crc, size)).await?;
+
+        entries.push(Entry {
+            name,
+            modified_at,
+            crc,
+            size,
+            offset: header_offset,
+        });
+    }
+
+    let mut directory = vec![];
+    for e in &entries {
+        directory.extend(central_header(e));
+    }
+    let directory_size = directory.len() as u64;
+    directory.extend(end_of_directory(
+        entries.len() as u64,
+        directory_size,
+        offset,
+    ));
+    _ = send(tx, directory).await?;
+    Ok(())
+}
+
+/// Sends the given `chunk` of a [ZIP] archive into the given channel,
+/// returning its size.
+///
+/// # Errors
+///
+/// If the receiving side of the channel has been dropped.
+///
+/// [ZIP]: https://en.wikipedia.org/wiki/ZIP_(file_format)
+async fn send(
+    tx: &mpsc::Sender<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+) -> io::Result<u64> {
+    let len = chunk.len() as u64;
+    tx.send(Ok(chunk)).await.map_err(|_| {
+        io::Error::new(io::ErrorKind::BrokenPipe, "ZIP stream is dropped")
+    })?;
+    Ok(len)
+}
+
+/// Forms a local file header preceding the file's data.
+fn local_header(name: &str, (time, date): (u16, u16)) -> Vec<u8> {
+    [
+        &0x0403_4b50_u32.to_le_bytes()[..],
+        &VERSION.to_le_bytes(),
+        &FLAGS.to_le_bytes(),
+        &0_u16.to_le_bytes(), // stored without compression
+        &time.to_le_bytes(),
+        &date.to_le_bytes(),
+        &0_u32.to_le_bytes(), // CRC-32 follows in the data descriptor
+        &u32::MAX.to_le_bytes(), // sizes follow in the data descriptor
+        &u32::MAX.to_le_bytes(),
+        &name_len(name).to_le_bytes(),
+        &20_u16.to_le_bytes(),
+        name.as_bytes(),
+        // ZIP64 extended information.
+        &0x0001_u16.to_le_bytes(),
+        &16_u16.to_le_bytes(),
+        &0_u64.to_le_bytes(),
+        &0_u64.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Forms a data descriptor following the file's data.
+fn data_descriptor(crc: u32, size: u64) -> Vec<u8> {
+    [
+        &0x0807_4b50_u32.to_le_bytes()[..],
+        &crc.to_le_bytes(),
+        &size.to_le_bytes(),
+        &size.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Forms a central directory header of the given [`Entry`].
+fn central_header(e: &Entry) -> Vec<u8> {
+    let (time, date) = e.modified_at;
+    [
+        &0x0201_4b50_u32.to_le_bytes()[..],
+        &VERSION.to_le_bytes(),
+        &VERSION.to_le_bytes(),
+        &FLAGS.to_le_bytes(),
+        &0_u16.to_le_bytes(),
+        &time.to_le_bytes(),
+        &date.to_le_bytes(),
+        &e.crc.to_le_bytes(),
+        &u32::MAX.to_le_bytes(),
+        &u32::MAX.to_le_bytes(),
+        &name_len(&e.name).to_le_bytes(),
+        &28_u16.to_le_bytes(),
+        &0_u16.to_le_bytes(), // comment length
+        &0_u16.to_le_bytes(), // disk number
+        &0_u16.to_le_bytes(), // internal attributes
+        &0_u32.to_le_bytes(), // external attributes
+        &u32::MAX.to_le_bytes(),
+        e.name.as_bytes(),
+        // ZIP64 extended information.
+        &0x0001_u16.to_le_bytes(),
+        &24_u16.to_le_bytes(),
+        &e.size.to_le_bytes(),
+        &e.size.to_le_bytes(),
+        &e.offset.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Forms the records ending an archive with the central directory of the
+/// given size located at the given offset.
+fn end_of_directory(entries: u64, size: u64, offset: u64) -> Vec<u8> {
+    [
+        // ZIP64 end of central directory record.
+        &0x0606_4b50_u32.to_le_bytes()[..],
+        &44_u64.to_le_bytes(),
+        &VERSION.to_le_bytes(),
+        &VERSION.to_le_bytes(),
+        &0_u32.to_le_bytes(),
+        &0_u32.to_le_bytes(),
+        &entries.to_le_bytes(),
+        &entries.to_le_bytes(),
+        &size.to_le_bytes(),
+        &offset.to_le_bytes(),
+        // ZIP64 end of central directory locator.
+        &0x0706_4b50_u32.to_le_bytes(),
+        &0_u32.to_le_bytes(),
+        &(offset + size).to_le_bytes(),
+        &1_u32.to_le_bytes(),
+        // End of central directory record.
+        &0x0605_4b50_u32.to_le_bytes(),
+        &0_u16.to_le_bytes(),
+        &0_u16.to_le_bytes(),
+        &u16::MAX.to_le_bytes(),
+        &u16::MAX.to_le_bytes(),
+        &u32::MAX.to_le_bytes(),
+        &u32::MAX.to_le_bytes(),
+        &0_u16.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Returns length of the given file `name`, truncating it to [`u16`].
+fn name_len(name: &str) -> u16 {
+    u16::try_from(name.len()).unwrap_or(u16::MAX)
+}
+
+/// Converts the given time into [DOS] time and date.
+///
+/// [DOS]: https://learn.microsoft.com/windows/win32/sysinfo/ms-dos-date-and-time
+fn dos_date_time(time: SystemTime) -> (u16, u16) {
+    let t = DateTime::<Utc>::from(time);
+    let year = u16::try_from(t.year().clamp(1980, 2107) - 1980).unwrap_or(0);
+    #[allow(clippy::cast_possible_truncation)] // all fit into `u16`
+    let (month, day, hour, minute, second) = (
+        t.month() as u16,
+        t.day() as u16,
+        t.hour() as u16,
+        t.minute() as u16,
+        t.second() as u16,
+    );
+    (
+        (hour << 11) | (minute << 5) | (second / 2),
+        (year << 9) | (month << 5) | day,
+    )
+}
+
+#[cfg(test)]
+mod zip_spec {
+    use std::io::{Cursor, Read as _};
+
+    use tokio::fs;
+    use tokio_stream::StreamExt as _;
+    use uuid::Uuid;
+
+    use super::{stream, CHUNK_SIZE};
+
+    #[tokio::test]
+    async fn is_unpacked_into_original_files() {
+        let dir = std::env::temp_dir()
+            .join(format!("ephyr-dvr-zip-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).await.unwrap();
+        // Spans multiple chunks to be streamed.
+        let large: Vec<u8> = (0..CHUNK_SIZE * 2 + 10)
+            .map(|n| u8::try_from(n % 251).unwrap())
+            .collect();
+        let files = [
+            ("1700000000000000.flv", large),
+            ("1700000000000000_001.flv", b"second part".to_vec()),
+            ("empty.flv", vec![]),
+        ];
+        for (name, data) in &files {
+            fs::write(dir.join(name), data).await.unwrap();
+        }
+
+        let archive: Vec<u8> = stream(
+            files
+                .iter()
+                .map(|(name, _)| ((*name).to_owned(), dir.join(name)))
+                .collect(),
+        )
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await
+        .concat();
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        assert_eq!(zip.len(), files.len());
+        for (i, (name, data)) in files.iter().enumerate() {
+            let mut file = zip.by_index(i).unwrap();
+            assert_eq!(file.name(), *name);
+
+            let mut unpacked = vec![];
+            _ = file.read_to_end(&mut unpacked).unwrap();
+            assert_eq!(&unpacked, data, "mismatched `{name}` contents");
+        }
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+}
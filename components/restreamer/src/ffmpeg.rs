@@ -4,7 +4,9 @@
 
 mod copy_restreamer;
 mod file_restreamer;
+mod metering;
 mod mixing_restreamer;
+mod progress;
 mod restreamer;
 mod restreamer_kind;
 mod restreamers_pool;
@@ -10,7 +10,10 @@ use tokio::{io, process::Command};
 use url::Url;
 use uuid::Uuid;
 
-use crate::dvr;
+use crate::{
+    dvr::{self, Rotation},
+    state::{AudioEncoding, HlsPush, Icecast, InputSrcUrl, RtspTransport},
+};
 
 /// Kind of a [FFmpeg] re-streaming process that re-streams a live stream from
 /// one URL endpoint to another one "as is", without performing any live stream
@@ -30,6 +33,10 @@ pub struct CopyRestreamer {
 
     /// [`Url`] to publish the pulled live stream onto.
     pub to_url: Url,
+
+    /// [`AudioEncoding`] of the published live stream, if the
+    /// [`CopyRestreamer::to_url`] is an audio-only one.
+    pub audio_encoding: AudioEncoding,
 }
 
 impl CopyRestreamer {
@@ -39,7 +46,9 @@ impl CopyRestreamer {
     #[inline]
     #[must_use]
     pub fn needs_restart(&self, actual: &Self) -> bool {
-        self.from_url != actual.from_url || self.to_url != actual.to_url
+        self.from_url != actual.from_url
+            || self.to_url != actual.to_url
+            || self.audio_encoding != actual.audio_encoding
     }
 
     /// Properly setups the given [FFmpeg] [`Command`] for this
@@ -54,48 +63,47 @@ impl CopyRestreamer {
         &self,
         cmd: &mut Command,
     ) -> io::Result<()> {
-        _ = match self.from_url.scheme() {
+        let from_url = match self.from_url.scheme() {
             "http" | "https"
                 if Path::new(self.from_url.path()).extension()
                     == Some("m3u8".as_ref()) =>
             {
-                cmd.arg("-re")
+                _ = cmd.arg("-re");
+                self.from_url.clone()
             }
 
-            "rtmp" | "rtmps" => cmd,
-            "file" => cmd.arg("-re").args(["-stream_loop", "-1"]),
+            "rtmp" | "rtmps" => self.from_url.clone(),
+            "rtsp" | "rtsps" | "srt" => setup_pull_input(cmd, &self.from_url),
+            "file" => {
+                _ = cmd.arg("-re").args(["-stream_loop", "-1"]);
+                self.from_url.clone()
+            }
             _ => unimplemented!(),
-        }
-        .args(["-i", self.from_url.as_str()]);
+        };
+        _ = cmd.args(["-i", from_url.as_str()]);
 
         _ = match self.to_url.scheme() {
-            "file" => match Path::new(self.to_url.path())
-                .extension()
-                .and_then(OsStr::to_str)
-            {
-                Some("flv") => cmd
-                    .args(["-c", "copy"])
-                    .arg(dvr::new_file_path(&self.to_url).await?),
-                Some("wav") => cmd
-                    .arg("-vn")
-                    .args(["-acodec", "pcm_s16le"])
-                    .args(["-ar", "48000"])
-                    .args(["-ac", "2"])
-                    .arg(dvr::new_file_path(&self.to_url).await?),
-                Some("mp3") => cmd
-                    .arg("-vn")
-                    .args(["-acodec", "libmp3lame"])
-                    .args(["-b:a", "64k"])
-                    .args(["-ar", "48000"])
-                    .args(["-ac", "2"])
-                    .arg(dvr::new_file_path(&self.to_url).await?),
-                _ => unimplemented!(),
-            },
-            "icecast" => cmd
-                .arg("-vn")
-                .args(["-acodec", "libmp3lame", "-b:a", "64k"])
-                .args(["-f", "mp3", "-content_type", "audio/mpeg"])
-                .arg(self.to_url.as_str()),
+            "file" => {
+                _ = match Path::new(self.to_url.path())
+                    .extension()
+                    .and_then(OsStr::to_str)
+                {
+                    Some("flv" | "mkv" | "mp4") => cmd.args(["-c", "copy"]),
+                    Some("wav") => cmd
+                        .arg("-vn")
+                        .args(["-acodec", "pcm_s16le"])
+                        .args(["-ar", "48000"])
+                        .args(["-ac", "2"]),
+                    Some("mp3") => {
+                        setup_audio_encoding(cmd, self.audio_encoding)
+                    }
+                    _ => unimplemented!(),
+                };
+                setup_dvr_output(cmd, &self.to_url).await?
+            }
+            "icecast" => {
+                setup_icecast_push(cmd, &self.to_url, self.audio_encoding)
+            }
 
             "rtmp" | "rtmps" => cmd
                 .args(["-c", "copy"])
@@ -107,8 +115,173 @@ impl CopyRestreamer {
                 .args(["-strict", "-2", "-y", "-f", "mpegts"])
                 .arg(self.to_url.as_str()),
 
+            "hls+http" | "hls+https" => {
+                setup_hls_push(cmd.args(["-c", "copy"]), &self.to_url)
+            }
+
             _ => unimplemented!(),
         };
         Ok(())
     }
 }
+
+/// Setups the given [FFmpeg] [`Command`] for pulling a live stream from the
+/// given [RTSP] or [SRT] `url`.
+///
+/// Returns the [`Url`] to be used as the [FFmpeg] input, with the options
+/// understood by [`InputSrcUrl`] being translated into the ones understood by
+/// [FFmpeg].
+///
+/// [FFmpeg]: https://ffmpeg.org
+/// [RTSP]: https://en.wikipedia.org/wiki/Real_Time_Streaming_Protocol
+/// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+pub(crate) fn setup_pull_input(cmd: &mut Command, url: &Url) -> Url {
+    let mut input = url.clone();
+    match url.scheme() {
+        "rtsp" | "rtsps" => {
+            let (transport, query): (Vec<_>, Vec<_>) = url
+                .query_pairs()
+                .into_owned()
+                .partition(|(k, _)| k == InputSrcUrl::RTSP_TRANSPORT_PARAM);
+            if let Some(t) = transport
+                .iter()
+                .find_map(|(_, v)| RtspTransport::from_param(v))
+            {
+                _ = cmd.args(["-rtsp_transport", &t.to_string()]);
+            }
+            input.set_query(None);
+            if !query.is_empty() {
+                _ = input.query_pairs_mut().extend_pairs(query);
+            }
+        }
+        "srt" => {
+            // SRT listeners specify their mode explicitly, while pulled
+            // sources are always called.
+            if !url.query_pairs().any(|(k, _)| k == "mode") {
+                _ = input.query_pairs_mut().append_pair("mode", "caller");
+            }
+        }
+        _ => {}
+    }
+    input
+}
+
+/// Setups the given [FFmpeg] [`Command`] for pushing [HLS] segments to the
+/// given `hls+http(s)://` `url`.
+///
+/// Failed uploads don't stop the [FFmpeg] process, but are reported in its
+/// logs, making its [`Status`] unstable.
+///
+/// # Panics
+///
+/// If the given `url` is not a valid [`HlsPush`] destination.
+///
+/// [FFmpeg]: https://ffmpeg.org
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [`Status`]: crate::state::Status
+pub(crate) fn setup_hls_push<'c>(
+    cmd: &'c mut Command,
+    url: &Url,
+) -> &'c mut Command {
+    let hls = HlsPush::parse(url).expect("Invalid HLS push URL");
+    cmd.args(["-f", "hls"])
+        .args(["-hls_time", &hls.segment_duration.to_string()])
+        .args(["-hls_list_size", &hls.playlist_size.to_string()])
+        .args(["-hls_segment_filename", &hls.segment_url])
+        .args(["-method", &hls.method.to_string()])
+        .args(["-http_persistent", "1"])
+        .args(["-ignore_io_errors", "1"])
+        .arg(hls.playlist_url.as_str())
+}
+
+/// Setups the given [FFmpeg] [`Command`] for encoding audio-only output
+/// according to the given [`AudioEncoding`].
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub(crate) fn setup_audio_encoding<'c>(
+    cmd: &'c mut Command,
+    encoding: AudioEncoding,
+) -> &'c mut Command {
+    cmd.arg("-vn")
+        .args(["-acodec", encoding.format.encoder()])
+        .args(["-b:a", &format!("{}k", encoding.bitrate.0)])
+        .args(["-ar", "48000"])
+        .args(["-ac", "2"])
+}
+
+/// Setups the given [FFmpeg] [`Command`] for publishing audio encoded
+/// according to the given [`AudioEncoding`] onto the given `icecast://` `url`,
+/// advertising its station metadata.
+///
+/// # Panics
+///
+/// If the given `url` is not a valid [`Icecast`] destination.
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub(crate) fn setup_icecast_push<'c>(
+    cmd: &'c mut Command,
+    url: &Url,
+    encoding: AudioEncoding,
+) -> &'c mut Command {
+    let icecast = Icecast::parse(url).expect("Invalid Icecast URL");
+    _ = setup_audio_encoding(cmd, encoding)
+        .args(["-f", encoding.format.muxer()])
+        .args(["-content_type", encoding.format.content_type()]);
+    for (opt, val) in [
+        ("-ice_name", &icecast.name),
+        ("-ice_genre", &icecast.genre),
+        ("-ice_description", &icecast.description),
+        ("-password", &icecast.password),
+    ] {
+        if let Some(val) = val {
+            _ = cmd.args([opt, val.as_str()]);
+        }
+    }
+    cmd.arg(icecast.mount_url.as_str())
+}
+
+/// Setups the given [FFmpeg] [`Command`] for recording into a new [DVR] file
+/// of the given `file:///` `url`, rotating it according to its [`Rotation`].
+///
+/// # Errors
+///
+/// If the new [DVR] file path fails to be created.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+/// [FFmpeg]: https://ffmpeg.org
+pub(crate) async fn setup_dvr_output<'c>(
+    cmd: &'c mut Command,
+    url: &Url,
+) -> io::Result<&'c mut Command> {
+    let format = match Path::new(url.path()).extension().and_then(OsStr::to_str)
+    {
+        Some("mkv") => "matroska",
+        Some(ext @ ("flv" | "mp3" | "mp4" | "wav")) => ext,
+        _ => unimplemented!(),
+    };
+    // Fragmented MP4 remains playable even if its recording is interrupted.
+    let mp4_flags = "+frag_keyframe+empty_moov+default_base_moof";
+
+    let rotation = Rotation::parse(url).unwrap_or_default();
+    if let Some(minutes) = rotation.minutes {
+        _ = cmd
+            .args(["-f", "segment", "-segment_format", format])
+            .args(["-segment_time", &(u32::from(minutes) * 60).to_string()])
+            .args(["-reset_timestamps", "1"]);
+        if format == "mp4" {
+            _ = cmd.args([
+                "-segment_format_options",
+                &format!("movflags={mp4_flags}"),
+            ]);
+        }
+    } else {
+        if let Some(mb) = rotation.megabytes {
+            _ = cmd.args(["-fs", &(u64::from(mb) << 20).to_string()]);
+        }
+        _ = cmd.args(["-f", format]);
+        if format == "mp4" {
+            _ = cmd.args(["-movflags", mp4_flags]);
+        }
+    }
+    Ok(cmd.arg(dvr::new_file_path(url, rotation.minutes.is_some()).await?))
+}
@@ -4,7 +4,9 @@ use tokio::{io, process::Command};
 use url::Url;
 use uuid::Uuid;
 
-use crate::dvr;
+use crate::{
+    dvr, ffmpeg::copy_restreamer::setup_icecast_push, state::AudioEncoding,
+};
 
 /// Kind of a [FFmpeg] re-streaming process that streams a local file to input
 /// endpoint "as is", without performing any live stream modifications.
@@ -67,13 +69,12 @@ impl FileRestreamer {
                     == Some("flv".as_ref()) =>
             {
                 cmd.args(["-c", "copy"])
-                    .arg(dvr::new_file_path(&self.to_url).await?)
+                    .arg(dvr::new_file_path(&self.to_url, false).await?)
             }
 
-            "icecast" => cmd
-                .args(["-c:a", "libmp3lame", "-b:a", "64k"])
-                .args(["-f", "mp3", "-content_type", "audio/mpeg"])
-                .arg(self.to_url.as_str()),
+            "icecast" => {
+                setup_icecast_push(cmd, &self.to_url, AudioEncoding::default())
+            }
 
             "rtmp" | "rtmps" => cmd
                 .args(["-c", "copy"])
@@ -0,0 +1,184 @@
+//! Parser of audio levels printed by [FFmpeg] `ametadata` filter.
+//!
+//! Metering filters (`ebur128` and `astats`) attach their measurements to
+//! audio frames as metadata, which is printed then as a block of lines:
+//! ```text
+//! frame:12   pts:192000  pts_time:4
+//! lavfi.r128.M=-23.120
+//! lavfi.r128.S=-24.005
+//! lavfi.astats.Overall.Peak_level=-6.021
+//! ephyr.track=8b2a7d8e-0f3c-4bd7-9d5e-6a0c1f5b1e2a
+//! ```
+//! where the `ephyr.track` key is added last and identifies the measured
+//! audio track.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::mem;
+
+use uuid::Uuid;
+
+use crate::stream_statistics::AudioLevel;
+
+/// Metadata key identifying the audio track measured by a metering filter.
+pub(crate) const TRACK_KEY: &str = "ephyr.track";
+
+/// Result of parsing a single line of [FFmpeg] output with a
+/// [`MeteringParser`].
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum MeteringLine {
+    /// Line is a part of a not yet finished block of frame metadata.
+    Partial,
+
+    /// Line finishes a block of frame metadata, so the [`AudioLevel`] of the
+    /// audio track with the given ID is available.
+    ///
+    /// [`AudioLevel::mixin_id`] is left unset, as the parser has no idea
+    /// whether the track is a `Mixin` or not.
+    Report(Uuid, AudioLevel),
+}
+
+/// Parser accumulating frame metadata lines printed by [FFmpeg] metering
+/// filters into [`AudioLevel`]s.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug)]
+pub(crate) struct MeteringParser {
+    /// [`AudioLevel`] of the block being currently accumulated.
+    current: AudioLevel,
+}
+
+impl Default for MeteringParser {
+    fn default() -> Self {
+        Self { current: silence() }
+    }
+}
+
+impl MeteringParser {
+    /// Parses the given `line` of [FFmpeg] output.
+    ///
+    /// Returns [`None`] if the `line` is not a part of frame metadata (a
+    /// regular log line or a progress report, for example).
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) fn parse_line(&mut self, line: &str) -> Option<MeteringLine> {
+        let line = line.trim();
+        if line.starts_with("frame:") {
+            self.current = silence();
+            return Some(MeteringLine::Partial);
+        }
+        let (key, value) = line.split_once('=')?;
+        let cur = &mut self.current;
+        match key {
+            "lavfi.r128.M" => cur.momentary = parse_level(value),
+            "lavfi.r128.S" => cur.short_term = parse_level(value),
+            "lavfi.astats.Overall.Peak_level" => cur.peak = parse_level(value),
+            TRACK_KEY => {
+                let level = mem::replace(cur, silence());
+                return value
+                    .trim()
+                    .parse()
+                    .ok()
+                    .map(|track| MeteringLine::Report(track, level));
+            }
+            k if k.starts_with("lavfi.r128.")
+                || k.starts_with("lavfi.astats.") => {}
+            _ => return None,
+        }
+        Some(MeteringLine::Partial)
+    }
+}
+
+/// Returns an [`AudioLevel`] of a silent audio track.
+fn silence() -> AudioLevel {
+    AudioLevel {
+        mixin_id: None,
+        momentary: AudioLevel::FLOOR,
+        short_term: AudioLevel::FLOOR,
+        peak: AudioLevel::FLOOR,
+    }
+}
+
+/// Parses the given level `value` of frame metadata, in dB.
+///
+/// Unknown or non-finite values (`-inf` of silence, for example), and the
+/// ones below the [`AudioLevel::FLOOR`] are parsed as the
+/// [`AudioLevel::FLOOR`].
+fn parse_level(value: &str) -> f64 {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .map_or(AudioLevel::FLOOR, |v| v.max(AudioLevel::FLOOR))
+}
+
+#[cfg(test)]
+mod metering_parser_spec {
+    use uuid::Uuid;
+
+    use super::{AudioLevel, MeteringLine, MeteringParser};
+
+    const TRACK: &str = "8b2a7d8e-0f3c-4bd7-9d5e-6a0c1f5b1e2a";
+
+    fn feed(parser: &mut MeteringParser, block: &str) -> Option<MeteringLine> {
+        block.lines().map(|l| parser.parse_line(l)).last().flatten()
+    }
+
+    #[test]
+    fn parses_levels() {
+        let mut parser = MeteringParser::default();
+
+        assert_eq!(parser.parse_line("frame=250"), None);
+        assert_eq!(parser.parse_line("[info] Stream mapping:"), None);
+        assert_eq!(
+            feed(
+                &mut parser,
+                &format!(
+                    "frame:12   pts:192000  pts_time:4\n\
+                     lavfi.r128.M=-23.120\nlavfi.r128.S=-24.005\n\
+                     lavfi.r128.I=-25.000\nlavfi.r128.LRA=3.100\n\
+                     lavfi.astats.Overall.Peak_level=-6.021\n\
+                     ephyr.track={TRACK}",
+                ),
+            ),
+            Some(MeteringLine::Report(
+                Uuid::parse_str(TRACK).unwrap(),
+                AudioLevel {
+                    mixin_id: None,
+                    momentary: -23.12,
+                    short_term: -24.005,
+                    peak: -6.021,
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn floors_silence() {
+        let mut parser = MeteringParser::default();
+
+        assert_eq!(
+            feed(
+                &mut parser,
+                &format!(
+                    "frame:0    pts:0       pts_time:0\n\
+                     lavfi.r128.M=-inf\nlavfi.r128.S=-1000.000\n\
+                     lavfi.astats.Overall.Peak_level=-inf\n\
+                     ephyr.track={TRACK}",
+                ),
+            ),
+            Some(MeteringLine::Report(
+                Uuid::parse_str(TRACK).unwrap(),
+                AudioLevel {
+                    mixin_id: None,
+                    momentary: AudioLevel::FLOOR,
+                    short_term: AudioLevel::FLOOR,
+                    peak: AudioLevel::FLOOR,
+                },
+            )),
+        );
+    }
+}
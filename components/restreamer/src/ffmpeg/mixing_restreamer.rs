@@ -8,10 +8,13 @@ use std::{
     borrow::Cow,
     collections::HashMap,
     ffi::OsStr,
-    fmt::Write as _,
+    fmt::{Display, Write as _},
     panic::AssertUnwindSafe,
     path::{Path, PathBuf},
+    pin::Pin,
     sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
 };
 
 use ephyr_log::{
@@ -22,9 +25,12 @@ use futures::{FutureExt as _, TryFutureExt as _};
 use interprocess::os::unix::fifo_file::create_fifo;
 use tokio::{
     fs::File,
-    io, pin,
+    io::{self, AsyncRead},
+    pin,
     process::Command,
     sync::{watch, Mutex},
+    task::JoinHandle,
+    time,
 };
 use tsclientlib::Identity;
 use url::Url;
@@ -32,12 +38,35 @@ use uuid::Uuid;
 use zeromq::ZmqMessage;
 
 use crate::{
-    display_panic, dvr,
-    ffmpeg::{restreamer::RestreamerStatus, RestreamerKind},
-    state::{self, Delay, MixinId, MixinSrcUrl, State, Volume},
+    display_panic,
+    ffmpeg::{
+        copy_restreamer::{
+            setup_audio_encoding, setup_dvr_output, setup_hls_push,
+            setup_icecast_push, setup_pull_input,
+        },
+        metering::TRACK_KEY,
+        restreamer::RestreamerStatus,
+        RestreamerKind,
+    },
+    mumble,
+    state::{
+        self, AudioEncoding, Delay, Loudness, MixinId, MixinSrcUrl, Sidechain,
+        State, TalkingSpeakers, Volume,
+    },
     teamspeak,
 };
 
+/// Number of audio samples (at 48 kHz) to measure audio levels of a mixed
+/// track over, so they're reported 3 times per second.
+const METERING_SAMPLES: u32 = 16000;
+
+/// Interval to publish [`TalkingSpeakers`] and failures of [TeamSpeak] and
+/// [Mumble] [`Mixin`]s with.
+///
+/// [Mumble]: https://www.mumble.info
+/// [TeamSpeak]: https://teamspeak.com
+const VOICE_TRACKING_INTERVAL: Duration = Duration::from_millis(250);
+
 /// Kind of a [FFmpeg] re-streaming process that mixes a live stream from one
 /// URL endpoint with some additional live streams and re-streams the result to
 /// another endpoint.
@@ -65,6 +94,13 @@ pub struct MixingRestreamer {
     /// [ZeroMQ]: https://zeromq.org
     pub orig_zmq_port: u16,
 
+    /// [`Loudness`] to normalize and limit the mixed audio with, if any.
+    pub loudness: Option<Loudness>,
+
+    /// [`AudioEncoding`] of the mixed audio, if the
+    /// [`MixingRestreamer::to_url`] is an audio-only one.
+    pub audio_encoding: AudioEncoding,
+
     /// Additional live streams to be mixed with the original one before being
     /// re-streamed to the [`MixingRestreamer::to_url`].
     pub mixins: Vec<Mixin>,
@@ -75,6 +111,8 @@ impl MixingRestreamer {
     ///
     /// `prev` value may be specified to consume already initialized resources,
     /// which are unwanted to be re-created.
+    ///
+    /// `file_root` is the directory of local files to be mixed from.
     #[must_use]
     #[instrument(skip_all, name="MixinRestreamer::new", fields(
         src=%from_url, dst=%output.dst)
@@ -83,6 +121,7 @@ impl MixingRestreamer {
         output: &state::Output,
         from_url: &Url,
         mut prev: Option<&RestreamerKind>,
+        file_root: &Path,
     ) -> Self {
         let prev = prev.as_mut().and_then(|kind| {
             if let RestreamerKind::Mixing(r) = kind {
@@ -97,6 +136,8 @@ impl MixingRestreamer {
             to_url: RestreamerKind::dst_url(output),
             orig_volume: output.volume.clone(),
             orig_zmq_port: new_unique_zmq_port(),
+            loudness: output.loudness,
+            audio_encoding: output.audio_encoding.unwrap_or_default(),
             mixins: output
                 .mixins
                 .iter()
@@ -105,6 +146,7 @@ impl MixingRestreamer {
                         m,
                         output.label.as_ref(),
                         prev.and_then(|p| p.iter().find(|p| p.id == m.id)),
+                        file_root,
                     )
                 })
                 .collect(),
@@ -119,6 +161,8 @@ impl MixingRestreamer {
     pub fn needs_restart(&mut self, actual: &Self) -> bool {
         if self.from_url != actual.from_url
             || self.to_url != actual.to_url
+            || self.loudness != actual.loudness
+            || self.audio_encoding != actual.audio_encoding
             || self.mixins.len() != actual.mixins.len()
         {
             return true;
@@ -130,24 +174,49 @@ impl MixingRestreamer {
             }
         }
 
-        if self.orig_volume != actual.orig_volume {
-            self.orig_volume = actual.orig_volume.clone();
-            tune_volume(self.id, self.orig_zmq_port, self.orig_volume.clone());
+        // Soloing any track affects the effective volumes of all the others.
+        let (was_soloing, is_soloing) =
+            (self.is_soloing(), actual.is_soloing());
+        let volume = actual.orig_volume.effective(is_soloing);
+        if self.orig_volume.effective(was_soloing) != volume {
+            tune_volume(self.id, self.orig_zmq_port, volume);
         }
+        self.orig_volume = actual.orig_volume.clone();
         for (curr, actual) in self.mixins.iter_mut().zip(actual.mixins.iter()) {
-            if curr.volume != actual.volume {
-                curr.volume = actual.volume.clone();
-                tune_volume(curr.id.into(), curr.zmq_port, curr.volume.clone());
+            let volume = actual.volume.effective(is_soloing);
+            if curr.volume.effective(was_soloing) != volume {
+                tune_volume(curr.id.into(), curr.zmq_port, volume);
             }
+            curr.volume = actual.volume.clone();
             if curr.delay != actual.delay {
                 curr.delay = actual.delay;
                 tune_delay(curr.id.into(), curr.zmq_port, curr.delay);
             }
+            if let (Some(sc), Some(actual_sc)) =
+                (curr.sidechain.as_mut(), actual.sidechain.as_ref())
+            {
+                if sc != actual_sc {
+                    tune_sidechain(
+                        curr.id.into(),
+                        curr.zmq_port,
+                        sc,
+                        actual_sc,
+                    );
+                    *sc = *actual_sc;
+                }
+            }
         }
 
         false
     }
 
+    /// Indicates whether any audio track of this [`MixingRestreamer`] is
+    /// soloed, so all the non-soloed ones are muted.
+    #[must_use]
+    pub fn is_soloing(&self) -> bool {
+        self.orig_volume.solo || self.mixins.iter().any(|m| m.volume.solo)
+    }
+
     /// Properly setups the given [FFmpeg] [`Command`] for this
     /// [`MixingRestreamer`] before running it.
     ///
@@ -178,26 +247,33 @@ impl MixingRestreamer {
         let orig_volume = output
             .as_ref()
             .map_or(self.orig_volume.clone(), |o| o.volume.clone());
+        let is_soloing = output.as_ref().map_or_else(
+            || self.is_soloing(),
+            |o| o.volume.solo || o.mixins.iter().any(|m| m.volume.solo),
+        );
 
         // WARNING: The filters order matters here!
-        let mut filter_complex = Vec::with_capacity(self.mixins.len() + 1);
+        let mut filter_complex =
+            Vec::with_capacity(2 * (self.mixins.len() + 1));
         filter_complex.push(format!(
             "[0:a]\
                volume@{orig_id}={volume},\
                aresample=48000,\
-               azmq=bind_address=tcp\\\\\\://127.0.0.1\\\\\\:{port}\
-             [{orig_id}]",
+               azmq=bind_address=tcp\\\\\\://127.0.0.1\\\\\\:{port},\
+               asplit=2\
+             [{orig_id}][{orig_id}_meter]",
             orig_id = self.id,
-            volume = orig_volume.display_as_fraction(),
+            volume = orig_volume.effective(is_soloing).display_as_fraction(),
             port = self.orig_zmq_port,
         ));
+        filter_complex.push(metering_filter(self.id));
         _ = cmd.args(["-i", self.from_url.as_str()]);
 
         for (n, mixin) in self.mixins.iter().enumerate() {
             let mut extra_filters = String::new();
 
             _ = match mixin.url.scheme() {
-                "ts" => {
+                "ts" | "mumble" => {
                     extra_filters.push_str("aresample=async=1,");
                     cmd.args(["-thread_queue_size", "512"])
                         .args(["-f", "f32be"])
@@ -208,12 +284,34 @@ impl MixingRestreamer {
                         .arg(mixin.get_fifo_path())
                 }
 
-                "http" | "https"
-                    if Path::new(mixin.url.path()).extension()
-                        == Some("mp3".as_ref()) =>
-                {
+                "http" | "https" => {
+                    extra_filters.push_str("aresample=48000,");
+                    cmd.arg("-vn").args(["-i", mixin.url.as_str()])
+                }
+
+                "rtmp" | "rtmps" | "srt" => {
+                    extra_filters.push_str("aresample=48000:async=1,");
+                    let url = setup_pull_input(cmd, &mixin.url);
+                    cmd.arg("-vn").args(["-i", url.as_str()])
+                }
+
+                "file" => {
+                    let file = mixin.file.as_ref().ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "Not a local file Mixin.src: {}",
+                                mixin.url
+                            ),
+                        )
+                    })?;
                     extra_filters.push_str("aresample=48000,");
-                    cmd.args(["-i", mixin.url.as_str()])
+                    if mixin.url.is_looped() {
+                        _ = cmd.args(["-stream_loop", "-1"]);
+                    }
+                    // Local file should be played in real time, rather than
+                    // being read as fast as possible.
+                    cmd.arg("-re").arg("-vn").arg("-i").arg(file)
                 }
 
                 _ => unimplemented!(),
@@ -242,14 +340,16 @@ impl MixingRestreamer {
                 "[{num}:a]\
                    volume@{mixin_id}={volume},\
                    {extra_filters}\
-                   azmq=bind_address=tcp\\\\\\://127.0.0.1\\\\\\:{port}\
-                 [{mixin_id}]",
+                   azmq=bind_address=tcp\\\\\\://127.0.0.1\\\\\\:{port},\
+                   asplit=2\
+                 [{mixin_id}][{mixin_id}_meter]",
                 num = n + 1,
                 mixin_id = mixin.id,
-                volume = volume.display_as_fraction(),
+                volume = volume.effective(is_soloing).display_as_fraction(),
                 extra_filters = extra_filters,
                 port = mixin.zmq_port,
             ));
+            filter_complex.push(metering_filter(mixin.id));
         }
 
         let mut orig_id = self.id.to_string();
@@ -260,19 +360,35 @@ impl MixingRestreamer {
             .collect::<Vec<_>>();
 
         // Activate `sidechain` filter if required
-        if let Some(sidechain_mixin) = self.mixins.iter().find(|m| m.sidechain)
+        if let Some((sidechain_mixin, sidechain)) = self
+            .mixins
+            .iter()
+            .find_map(|m| m.sidechain.as_ref().map(|s| (m, s)))
         {
             let sidechain_mixin_id = sidechain_mixin.id.to_string();
+            // Up-to-date parameters are taken right from the `State`, as they
+            // won't be updated in a closured `self` value.
+            let sidechain = output
+                .as_ref()
+                .and_then(|o| {
+                    o.mixins.iter().find_map(|m| {
+                        (m.id == sidechain_mixin.id)
+                            .then_some(m.sidechain)
+                            .flatten()
+                    })
+                })
+                .unwrap_or(*sidechain);
+            let options = sidechain
+                .ffmpeg_options()
+                .into_iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join(":");
             // Sidechain is mixing Origin Audio and selected Mixin Audio
             filter_complex.push(format!(
                 "[{sidechain_mixin_id}]asplit=2[sc][mix];\
-                 [{orig_id}][sc]sidechaincompress=\
-                                    level_in=2\
-                                    :threshold=0.05\
-                                    :ratio=10\
-                                    :attack=10\
-                                    :knee=4\
-                                    :release=1500[compr]"
+                 [{orig_id}][sc]sidechaincompress@{sidechain_mixin_id}=\
+                                    level_in=2:{options}[compr]"
             ));
             // Replace Mixin Id for sidechain with `mix` value
             if let Some(elem) =
@@ -285,8 +401,15 @@ impl MixingRestreamer {
             orig_id = "compr".to_string();
         };
 
+        // Loudness is normalized and limited right after mixing, as clipping
+        // mostly happens once all the audio tracks sound together.
+        let loudness = self
+            .loudness
+            .map(|l| format!(",{}", l.ffmpeg_filters()))
+            .unwrap_or_default();
         filter_complex.push(format!(
-            "[{orig_id}][{mixin_ids}]amix=inputs={count}:duration=longest[out]",
+            "[{orig_id}][{mixin_ids}]amix=inputs={count}:duration=longest\
+             {loudness}[out]",
             orig_id = orig_id,
             mixin_ids = mixin_ids.join("]["),
             count = self.mixins.len() + 1,
@@ -302,35 +425,36 @@ impl MixingRestreamer {
             .args(["-max_muxing_queue_size", "50000000"]);
 
         _ = match self.to_url.scheme() {
-            "file" => match Path::new(self.to_url.path())
-                .extension()
-                .and_then(OsStr::to_str)
-            {
-                Some("flv") => cmd
-                    .args(["-map", "0:v"])
-                    .args(["-c:a", "libfdk_aac", "-c:v", "copy", "-shortest"])
-                    .arg(dvr::new_file_path(&self.to_url).await?),
-                Some("wav") => cmd
-                    .arg("-vn")
-                    .args(["-acodec", "pcm_s16le"])
-                    .args(["-ar", "48000"])
-                    .args(["-ac", "2"])
-                    .arg(dvr::new_file_path(&self.to_url).await?),
-                Some("mp3") => cmd
-                    .arg("-vn")
-                    .args(["-acodec", "libmp3lame"])
-                    .args(["-b:a", "64k"])
-                    .args(["-ar", "48000"])
-                    .args(["-ac", "2"])
-                    .arg(dvr::new_file_path(&self.to_url).await?),
-                _ => unimplemented!(),
-            },
+            "file" => {
+                _ = match Path::new(self.to_url.path())
+                    .extension()
+                    .and_then(OsStr::to_str)
+                {
+                    Some("flv" | "mkv" | "mp4") => {
+                        cmd.args(["-map", "0:v"]).args([
+                            "-c:a",
+                            "libfdk_aac",
+                            "-c:v",
+                            "copy",
+                            "-shortest",
+                        ])
+                    }
+                    Some("wav") => cmd
+                        .arg("-vn")
+                        .args(["-acodec", "pcm_s16le"])
+                        .args(["-ar", "48000"])
+                        .args(["-ac", "2"]),
+                    Some("mp3") => {
+                        setup_audio_encoding(cmd, self.audio_encoding)
+                    }
+                    _ => unimplemented!(),
+                };
+                setup_dvr_output(cmd, &self.to_url).await?
+            }
 
-            "icecast" => cmd
-                .arg("-vn")
-                .args(["-c:a", "libmp3lame", "-b:a", "64k"])
-                .args(["-f", "mp3", "-content_type", "audio/mpeg"])
-                .arg(self.to_url.as_str()),
+            "icecast" => {
+                setup_icecast_push(cmd, &self.to_url, self.audio_encoding)
+            }
 
             "rtmp" | "rtmps" => cmd
                 .args(["-map", "0:v"])
@@ -344,6 +468,17 @@ impl MixingRestreamer {
                 .args(["-strict", "-2", "-y", "-f", "mpegts"])
                 .arg(self.to_url.as_str()),
 
+            "hls+http" | "hls+https" => {
+                _ = cmd.args(["-map", "0:v"]).args([
+                    "-c:a",
+                    "libfdk_aac",
+                    "-c:v",
+                    "copy",
+                    "-shortest",
+                ]);
+                setup_hls_push(cmd, &self.to_url)
+            }
+
             _ => unimplemented!(),
         };
         Ok(())
@@ -368,7 +503,7 @@ impl MixingRestreamer {
     ) {
         #[instrument(skip(input))]
         async fn run_copy_and_stop_on_signal(
-            input: Arc<Mutex<teamspeak::Input>>,
+            input: Arc<Mutex<VoiceInput>>,
             fifo_path: PathBuf,
             mut kill_rx: watch::Receiver<RestreamerStatus>,
         ) -> io::Result<()> {
@@ -424,6 +559,74 @@ impl MixingRestreamer {
             }
         }
     }
+
+    /// Spawns a task publishing [`TalkingSpeakers`] and unrecoverable
+    /// failures of this [`MixingRestreamer`]'s [TeamSpeak] and [Mumble]
+    /// [`Mixin`]s into the given `actual` [`State`] each
+    /// [`VOICE_TRACKING_INTERVAL`], until it's aborted.
+    ///
+    /// [`TalkingSpeakers`] are tracked for [TeamSpeak] [`Mixin`]s only.
+    ///
+    /// Returns [`None`] if there are no [TeamSpeak] or [Mumble] [`Mixin`]s to
+    /// track.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    #[must_use]
+    pub(crate) fn spawn_voice_tracking(
+        &self,
+        actual: &State,
+    ) -> Option<JoinHandle<()>> {
+        let tracked = self
+            .mixins
+            .iter()
+            .filter_map(|m| {
+                Some((
+                    m.id,
+                    m.speakers.clone(),
+                    Arc::clone(m.failure.as_ref()?),
+                ))
+            })
+            .collect::<Vec<_>>();
+        if tracked.is_empty() {
+            return None;
+        }
+
+        let output_id = state::OutputId::from(self.id);
+        let state = actual.clone();
+        Some(tokio::spawn(
+            async move {
+                let mut ticker = time::interval(VOICE_TRACKING_INTERVAL);
+                loop {
+                    _ = ticker.tick().await;
+                    let talking = tracked
+                        .iter()
+                        .filter_map(|(mixin_id, s, _)| {
+                            Some(TalkingSpeakers {
+                                mixin_id: *mixin_id,
+                                speakers: s.as_ref()?.lock().unwrap().talking(),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    // Avoid notifying subscribers if nothing has changed.
+                    if state.talking_speakers.lock_ref().get(&output_id)
+                        != Some(&talking)
+                    {
+                        _ = state
+                            .talking_speakers
+                            .lock_mut()
+                            .insert(output_id, talking);
+                    }
+
+                    for (mixin_id, _, failure) in &tracked {
+                        let error = failure.lock().unwrap().clone();
+                        state.renew_mixin_error(output_id, *mixin_id, error);
+                    }
+                }
+            }
+            .in_current_span(),
+        ))
+    }
 }
 
 /// Additional live stream for mixing in a [`MixingRestreamer`].
@@ -435,16 +638,24 @@ pub struct Mixin {
     /// [`Url`] to pull an additional live stream from for mixing.
     pub url: MixinSrcUrl,
 
+    /// Server and channel passwords to capture audio from the [TeamSpeak] or
+    /// [Mumble] server with.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    passwords: (Option<String>, Option<String>),
+
     /// [`Delay`] to mix this [`Mixin`]'s live stream with.
     pub delay: Delay,
 
     /// [`Volume`] rate to mix an audio of this [`Mixin`]'s live stream with.
     pub volume: Volume,
 
-    /// Apply [sidechain] audio filter of this [`Mixin`]'s with live stream.
+    /// Parameters of [sidechain] audio filter of this [`Mixin`]'s with live
+    /// stream, if it should be applied.
     ///
     /// [sidechain]: https://ffmpeg.org/ffmpeg-filters.html#sidechaincompress
-    pub sidechain: bool,
+    pub sidechain: Option<Sidechain>,
 
     /// [ZeroMQ] port of a spawned [FFmpeg] process listening to a real-time
     /// filter updates of this [`Mixin`]'s live stream during mixing process.
@@ -453,13 +664,31 @@ pub struct Mixin {
     /// [ZeroMQ]: https://zeromq.org
     pub zmq_port: u16,
 
-    /// Actual live audio stream captured from the [TeamSpeak] server.
+    /// Actual live audio stream captured from the [TeamSpeak] or [Mumble]
+    /// server.
     ///
     /// If present, it should be fed into [FIFO].
     ///
-    /// [TeamSpeak]: https://teamspeak.com
     /// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
-    stdin: Option<Arc<Mutex<teamspeak::Input>>>,
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    stdin: Option<Arc<Mutex<VoiceInput>>>,
+
+    /// [`teamspeak::Speakers`] of the [`Mixin::stdin`], allowing to update
+    /// their [`SpeakerFilter`] in real-time and to track the talking ones.
+    ///
+    /// Present for [TeamSpeak] [`Mixin`]s only.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    /// [`SpeakerFilter`]: state::SpeakerFilter
+    speakers: Option<Arc<std::sync::Mutex<teamspeak::Speakers>>>,
+
+    /// Unrecoverable failure of the [`Mixin::stdin`], if any.
+    failure: Option<Arc<std::sync::Mutex<Option<String>>>>,
+
+    /// Absolute path of the local file to be mixed, if this [`Mixin`] is
+    /// sourced from a `file:///` URL.
+    file: Option<PathBuf>,
 }
 
 impl Mixin {
@@ -469,10 +698,17 @@ impl Mixin {
     /// which are unwanted to be re-created.
     ///
     /// Optional `label` may be used to identify this [`Mixin`] in a [TeamSpeak]
-    /// channel.
+    /// or [Mumble] channel.
+    ///
+    /// `file_root` is the directory to resolve a `file:///` URL of the given
+    /// [`state::Mixin`] against.
     ///
+    /// [`state::Mixin::speakers`] filter is applied right away, even if the
+    /// `prev` [TeamSpeak] connection is reused.
+    ///
+    /// [Mumble]: https://www.mumble.info
     /// [TeamSpeak]: https://teamspeak.com
-    #[allow(clippy::non_ascii_literal)]
+    #[allow(clippy::missing_panics_doc, clippy::non_ascii_literal)]
     #[must_use]
     #[instrument(skip_all, name="Mixin::new", fields(
         label=label.map(ToString::to_string), src=%state.src)
@@ -481,57 +717,73 @@ impl Mixin {
         state: &state::Mixin,
         label: Option<&state::Label>,
         prev: Option<&Mixin>,
+        file_root: &Path,
     ) -> Self {
-        let stdin = (state.src.scheme() == "ts")
-            .then(|| {
-                prev.and_then(|m| m.stdin.clone()).or_else(|| {
-                    let mut host = Cow::Borrowed(state.src.host_str()?);
-                    if let Some(port) = state.src.port() {
-                        host = Cow::Owned(format!("{host}:{port}"));
+        let passwords =
+            (state.password.clone(), state.channel_password.clone());
+        // Connection to a server of another kind, or authenticated with other
+        // passwords, cannot be reused.
+        let prev_voice = prev
+            .filter(|m| {
+                m.url.scheme() == state.src.scheme() && m.passwords == passwords
+            })
+            .and_then(|m| {
+                Some((m.stdin.clone()?, m.speakers.clone(), m.failure.clone()?))
+            });
+        let voice = state.src.voice_channel().and_then(|channel| {
+            prev_voice.or_else(|| {
+                let query: HashMap<String, String> =
+                    state.src.query_pairs().into_owned().collect();
+                let name = query
+                    .get("name")
+                    .cloned()
+                    .or_else(|| label.map(|l| format!("🤖 {l}")))
+                    .unwrap_or_else(|| format!("🤖 {}", state.id));
+
+                Some(match state.src.scheme() {
+                    "ts" => {
+                        let input =
+                            new_teamspeak_input(state, channel, name, &query)?;
+                        let speakers = Arc::clone(&input.speakers);
+                        let failure = Arc::clone(&input.failure);
+                        (
+                            Arc::new(Mutex::new(VoiceInput::TeamSpeak(input))),
+                            Some(speakers),
+                            failure,
+                        )
+                    }
+                    _ => {
+                        let input = new_mumble_input(state, channel, name)?;
+                        let failure = Arc::clone(&input.failure);
+                        (
+                            Arc::new(Mutex::new(VoiceInput::Mumble(input))),
+                            None,
+                            failure,
+                        )
                     }
-
-                    let channel = state.src.path().trim_start_matches('/');
-
-                    let query: HashMap<String, String> =
-                        state.src.query_pairs().into_owned().collect();
-                    let name = query
-                        .get("name")
-                        .cloned()
-                        .or_else(|| label.map(|l| format!("🤖 {l}")))
-                        .unwrap_or_else(|| format!("🤖 {}", state.id));
-                    let identity = query.get("identity").map_or_else(
-                        Identity::create,
-                        |v| {
-                            Identity::new_from_str(v).unwrap_or_else(|e| {
-                                tracing::error!(
-                                    "Failed to create identity `{}`\
-                                    \n\t with error: {}",
-                                    &v,
-                                    &e
-                                );
-                                Identity::create()
-                            })
-                        },
-                    );
-
-                    Some(Arc::new(Mutex::new(teamspeak::Input::new(
-                        teamspeak::Connection::build(host.into_owned())
-                            .channel(channel.to_owned())
-                            .name(name)
-                            .identity(identity),
-                    ))))
                 })
             })
-            .flatten();
+        });
+        let (stdin, speakers, failure) = match voice {
+            Some((i, s, f)) => (Some(i), s, Some(f)),
+            None => (None, None, None),
+        };
+        if let Some(speakers) = &speakers {
+            speakers.lock().unwrap().filter = state.speakers.clone();
+        }
 
         Self {
             id: state.id,
             url: state.src.clone(),
+            passwords,
             delay: state.delay,
             sidechain: state.sidechain,
             volume: state.volume.clone(),
             zmq_port: new_unique_zmq_port(),
             stdin,
+            speakers,
+            failure,
+            file: state.src.local_path().map(|p| file_root.join(p)),
         }
     }
 
@@ -543,14 +795,18 @@ impl Mixin {
     #[inline]
     #[must_use]
     pub fn needs_restart(&self, actual: &Self) -> bool {
-        self.url != actual.url || self.sidechain != actual.sidechain
+        self.url != actual.url
+            || self.passwords != actual.passwords
+            || self.sidechain.is_some() != actual.sidechain.is_some()
     }
 
-    /// [FIFO] path where stream captures from the [TeamSpeak] server.
+    /// [FIFO] path where stream captures from the [TeamSpeak] or [Mumble]
+    /// server.
     ///
     /// Should be fed into [FFmpeg]'s as file input.
     ///
     /// [FFmpeg]: https://ffmpeg.org
+    /// [Mumble]: https://www.mumble.info
     /// [TeamSpeak]: https://teamspeak.com
     /// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
     #[inline]
@@ -560,6 +816,128 @@ impl Mixin {
     }
 }
 
+/// Creates a new [`teamspeak::Input`] joining the given `channel` of the
+/// [TeamSpeak] server specified by the `ts://` [`state::Mixin::src`].
+///
+/// Returns [`None`] if the [`state::Mixin::src`] has no host.
+///
+/// [TeamSpeak]: https://teamspeak.com
+fn new_teamspeak_input(
+    state: &state::Mixin,
+    channel: String,
+    name: String,
+    query: &HashMap<String, String>,
+) -> Option<teamspeak::Input> {
+    let mut host = Cow::Borrowed(state.src.host_str()?);
+    if let Some(port) = state.src.port() {
+        host = Cow::Owned(format!("{host}:{port}"));
+    }
+
+    // Identity specified in URL explicitly has priority over the one persisted
+    // for the `Mixin`.
+    let identity = query
+        .get("identity")
+        .or(state.teamspeak_identity.as_ref())
+        .map_or_else(Identity::create, |v| {
+            Identity::new_from_str(v).unwrap_or_else(|e| {
+                tracing::error!(
+                    "Failed to create identity `{}`\
+                    \n\t with error: {}",
+                    &v,
+                    &e
+                );
+                Identity::create()
+            })
+        });
+
+    let mut cfg = teamspeak::Connection::build(host.into_owned())
+        .channel(channel)
+        .name(name)
+        .identity(identity);
+    if let Some(pass) = state.password.clone() {
+        cfg = cfg.password(pass);
+    }
+    if let Some(pass) = state.channel_password.clone() {
+        cfg = cfg.channel_password(pass);
+    }
+    Some(teamspeak::Input::new(cfg))
+}
+
+/// Creates a new [`mumble::Input`] joining the given `channel` of the [Mumble]
+/// server specified by the `mumble://` [`state::Mixin::src`].
+///
+/// Returns [`None`] if the [`state::Mixin::src`] has no host.
+///
+/// [Mumble]: https://www.mumble.info
+fn new_mumble_input(
+    state: &state::Mixin,
+    channel: String,
+    name: String,
+) -> Option<mumble::Input> {
+    Some(mumble::Input::new(mumble::Config {
+        host: state.src.host_str()?.to_owned(),
+        port: state.src.port().unwrap_or(mumble::DEFAULT_PORT),
+        channel,
+        name,
+        password: state.password.clone(),
+        channel_password: state.channel_password.clone(),
+    }))
+}
+
+/// Live audio stream captured from a [TeamSpeak] or [Mumble] server for a
+/// [`Mixin`].
+///
+/// [Mumble]: https://www.mumble.info
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Debug)]
+enum VoiceInput {
+    /// Audio captured from a [TeamSpeak] server.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    TeamSpeak(teamspeak::Input),
+
+    /// Audio captured from a [Mumble] server.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    Mumble(mumble::Input),
+}
+
+impl AsyncRead for VoiceInput {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::TeamSpeak(i) => Pin::new(i).poll_read(cx, buf),
+            Self::Mumble(i) => Pin::new(i).poll_read(cx, buf),
+        }
+    }
+}
+
+/// Forms a [FFmpeg] filter chain measuring audio levels of the specified
+/// `track` (taken from its `[{track}_meter]` output) and printing them into
+/// STDERR, so they can be parsed by a [`MeteringParser`].
+///
+/// Audio is measured in chunks of [`METERING_SAMPLES`], so the levels are
+/// reported a few times per second.
+///
+/// [`MeteringParser`]: crate::ffmpeg::metering::MeteringParser
+/// [FFmpeg]: https://ffmpeg.org
+fn metering_filter(track: impl Display) -> String {
+    format!(
+        "[{track}_meter]\
+           asetnsamples=n={METERING_SAMPLES}:p=0,\
+           ebur128=metadata=1,\
+           astats=metadata=1:reset=1:measure_perchannel=none\
+                 :measure_overall=Peak_level,\
+           ametadata=mode=add:key={TRACK_KEY}:value={track},\
+           ametadata=mode=print:file=pipe\\\\\\:2:direct=1,\
+           anullsink",
+    )
+}
+
 /// Generates a new port for a [ZeroMQ] listener, which is highly unlikely to be
 /// used already.
 ///
@@ -605,6 +983,25 @@ fn tune_delay(track: Uuid, port: u16, delay: Delay) {
     );
 }
 
+/// Tunes [`Sidechain`] parameters of the specified [FFmpeg] `track` by
+/// updating the changed options of its `sidechaincompress` [FFmpeg] filter in
+/// real-time via [ZeroMQ] protocol.
+///
+/// [FFmpeg]: https://ffmpeg.org
+/// [ZeroMQ]: https://zeromq.org
+fn tune_sidechain(track: Uuid, port: u16, curr: &Sidechain, new: &Sidechain) {
+    for ((name, old), (_, value)) in
+        curr.ffmpeg_options().into_iter().zip(new.ffmpeg_options())
+    {
+        if old != value {
+            tune_with_zmq(
+                port,
+                format!("sidechaincompress@{track} {name} {value}").into(),
+            );
+        }
+    }
+}
+
 /// Send [`ZmqMessage`] to specified localhost and specified port
 ///
 /// Used for apply [FFmpeg] filter in real-time via [ZeroMQ] protocol.
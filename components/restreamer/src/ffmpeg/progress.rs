@@ -0,0 +1,276 @@
+//! Parser of [FFmpeg] `-progress` reports.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::mem;
+
+use crate::{state::Status, stream_statistics::StreamProgress};
+
+/// Result of parsing a single line of [FFmpeg] output with a
+/// [`ProgressParser`].
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ProgressLine {
+    /// Line is a part of a not yet finished progress report.
+    Partial,
+
+    /// Line finishes a progress report, so the whole [`StreamProgress`] and
+    /// the [`Status`] derived from it are available.
+    Report(StreamProgress, Status),
+}
+
+/// Parser accumulating `key=value` lines of [FFmpeg] `-progress` reports into
+/// [`StreamProgress`] snapshots.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ProgressParser {
+    /// [`StreamProgress`] of the report being currently accumulated.
+    current: StreamProgress,
+
+    /// [`StreamProgress`] of the previous finished report, if any.
+    prev: Option<StreamProgress>,
+
+    /// Indicator whether any I/O errors (failed HLS uploads, for example) were
+    /// logged since the previous finished report.
+    has_io_errors: bool,
+}
+
+impl ProgressParser {
+    /// Parses the given `line` of [FFmpeg] output.
+    ///
+    /// Returns [`None`] if the `line` is not a part of a progress report (a
+    /// regular log line, for example).
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) fn parse_line(&mut self, line: &str) -> Option<ProgressLine> {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            return self.skip_line(line);
+        };
+        let value = value.trim();
+        let cur = &mut self.current;
+        match key {
+            "frame" => cur.frame = parse_int(value),
+            "fps" => cur.fps = parse_float(value).unwrap_or_default(),
+            "bitrate" => {
+                cur.bitrate = parse_float(value.trim_end_matches("kbits/s"));
+            }
+            "total_size" => cur.total_size = parse_float(value),
+            "out_time_us" => {
+                cur.out_time = parse_float(value).map(|us| us / 1_000_000.0);
+            }
+            "drop_frames" => cur.drop_frames = parse_int(value),
+            "dup_frames" => cur.dup_frames = parse_int(value),
+            "speed" => cur.speed = parse_float(value.trim_end_matches('x')),
+            "progress" => {
+                let progress = mem::take(&mut self.current);
+                let status = if mem::take(&mut self.has_io_errors) {
+                    Status::Unstable
+                } else {
+                    Self::status(&progress, self.prev.as_ref())
+                };
+                self.prev = Some(progress.clone());
+                return Some(ProgressLine::Report(progress, status));
+            }
+            k if k.starts_with("out_time") || k.starts_with("stream_") => {}
+            _ => return self.skip_line(line),
+        }
+        Some(ProgressLine::Partial)
+    }
+
+    /// Skips the given non-progress `line` of [FFmpeg] output, remembering
+    /// whether it reports an I/O error.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    fn skip_line(&mut self, line: &str) -> Option<ProgressLine> {
+        let line = line.to_lowercase();
+        if ["http error", "failed to open", "failed to upload"]
+            .iter()
+            .any(|e| line.contains(e))
+        {
+            self.has_io_errors = true;
+        }
+        None
+    }
+
+    /// Derives [`Status`] of a re-streaming out of its `current`
+    /// [`StreamProgress`] and the `prev`ious one.
+    ///
+    /// Re-streaming is considered [`Status::Unstable`] if it stopped producing
+    /// any output, or its video got stuck at zero FPS.
+    ///
+    /// Unknown values (the total size of HLS output, for example) are not
+    /// considered, so the remaining ones are relied on.
+    fn status(
+        current: &StreamProgress,
+        prev: Option<&StreamProgress>,
+    ) -> Status {
+        let not_grown = |cur: Option<f64>, prev: Option<f64>| {
+            cur.zip(prev).map_or(false, |(c, p)| c <= p)
+        };
+        let is_stalled = prev.map_or(false, |p| {
+            not_grown(current.total_size, p.total_size)
+                || not_grown(current.out_time, p.out_time)
+                || (p.frame > 0 && current.frame <= p.frame)
+        });
+        if is_stalled || (current.frame > 0 && current.fps <= 0.0) {
+            Status::Unstable
+        } else {
+            Status::Online
+        }
+    }
+}
+
+/// Parses the given integer `value` of a progress report, saturating it into
+/// an [`i32`].
+///
+/// Unknown values (`N/A`, for example) are parsed as `0`.
+fn parse_int(value: &str) -> i32 {
+    value
+        .parse::<u64>()
+        .map_or(0, |v| i32::try_from(v).unwrap_or(i32::MAX))
+}
+
+/// Parses the given floating point `value` of a progress report.
+///
+/// Returns [`None`] for unknown (`N/A`, for example) or non-finite values.
+fn parse_float(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+#[cfg(test)]
+mod progress_parser_spec {
+    use super::{ProgressLine, ProgressParser, Status, StreamProgress};
+
+    fn feed(parser: &mut ProgressParser, report: &str) -> Option<ProgressLine> {
+        report
+            .lines()
+            .map(|l| parser.parse_line(l))
+            .last()
+            .flatten()
+    }
+
+    #[test]
+    fn parses_report() {
+        let mut parser = ProgressParser::default();
+
+        assert_eq!(parser.parse_line("[info] Stream mapping:"), None);
+        assert_eq!(
+            feed(
+                &mut parser,
+                "frame=250\nfps=25.00\nstream_0_0_q=-1.0\n\
+                 bitrate=2500.5kbits/s\ntotal_size=3125000\n\
+                 out_time_us=10000000\nout_time=00:00:10.000000\n\
+                 dup_frames=1\ndrop_frames=2\nspeed=1.01x\n\
+                 progress=continue",
+            ),
+            Some(ProgressLine::Report(
+                StreamProgress {
+                    bitrate: Some(2500.5),
+                    fps: 25.0,
+                    speed: Some(1.01),
+                    drop_frames: 2,
+                    dup_frames: 1,
+                    total_size: Some(3_125_000.0),
+                    out_time: Some(10.0),
+                    frame: 250,
+                },
+                Status::Online,
+            )),
+        );
+    }
+
+    #[test]
+    fn detects_stalled_output() {
+        let mut parser = ProgressParser::default();
+        let report = "frame=250\nfps=25.00\nbitrate=N/A\n\
+                      total_size=3125000\nspeed=N/A\nprogress=continue";
+
+        assert!(matches!(
+            feed(&mut parser, report),
+            Some(ProgressLine::Report(_, Status::Online)),
+        ));
+        assert!(matches!(
+            feed(&mut parser, report),
+            Some(ProgressLine::Report(_, Status::Unstable)),
+        ));
+    }
+
+    #[test]
+    fn ignores_unknown_total_size() {
+        let mut parser = ProgressParser::default();
+        let report = |frame: u32, us: u32| {
+            format!(
+                "frame={frame}\nfps=25.00\nbitrate=N/A\ntotal_size=N/A\n\
+                 out_time_us={us}\nspeed=1x\nprogress=continue",
+            )
+        };
+
+        let Some(ProgressLine::Report(progress, status)) =
+            feed(&mut parser, &report(250, 10_000_000))
+        else {
+            panic!("expected a finished report");
+        };
+        assert_eq!(progress.total_size, None);
+        assert_eq!(status, Status::Online);
+        assert!(matches!(
+            feed(&mut parser, &report(300, 12_000_000)),
+            Some(ProgressLine::Report(_, Status::Online)),
+        ));
+        assert!(matches!(
+            feed(&mut parser, &report(300, 12_000_000)),
+            Some(ProgressLine::Report(_, Status::Unstable)),
+        ));
+    }
+
+    #[test]
+    fn detects_stalled_audio_only_output() {
+        let mut parser = ProgressParser::default();
+        let report = |us: u32| {
+            format!(
+                "frame=0\nfps=0.00\ntotal_size=N/A\nout_time_us={us}\n\
+                 progress=continue",
+            )
+        };
+
+        assert!(matches!(
+            feed(&mut parser, &report(10_000_000)),
+            Some(ProgressLine::Report(_, Status::Online)),
+        ));
+        assert!(matches!(
+            feed(&mut parser, &report(12_000_000)),
+            Some(ProgressLine::Report(_, Status::Online)),
+        ));
+        assert!(matches!(
+            feed(&mut parser, &report(12_000_000)),
+            Some(ProgressLine::Report(_, Status::Unstable)),
+        ));
+    }
+
+    #[test]
+    fn detects_io_errors() {
+        let mut parser = ProgressParser::default();
+
+        assert_eq!(
+            parser.parse_line("[http @ 0x55d0] HTTP error 403 Forbidden",),
+            None,
+        );
+        assert!(matches!(
+            feed(
+                &mut parser,
+                "frame=250\nfps=25.00\ntotal_size=3125000\n\
+                 progress=continue",
+            ),
+            Some(ProgressLine::Report(_, Status::Unstable)),
+        ));
+        assert!(matches!(
+            feed(
+                &mut parser,
+                "frame=300\nfps=25.00\ntotal_size=3750000\n\
+                 progress=continue",
+            ),
+            Some(ProgressLine::Report(_, Status::Online)),
+        ));
+    }
+}
@@ -7,15 +7,17 @@ use crate::{
     ffmpeg::restreamer_kind::RestreamerKind,
     state::{State, Status},
 };
-use chrono::{DateTime, Utc};
 use ephyr_log::{
     tracing,
     tracing::{instrument, Span},
     Instrument,
 };
-use futures::{future, pin_mut, FutureExt as _, TryFutureExt as _};
+use futures::{future, FutureExt as _};
 use std::{
-    panic::AssertUnwindSafe, path::Path, process::Stdio, time::Duration,
+    panic::AssertUnwindSafe,
+    path::Path,
+    process::Stdio,
+    time::{Duration, Instant},
 };
 use tokio::{process::Command, sync::watch, time};
 use uuid::Uuid;
@@ -79,7 +81,7 @@ impl Restreamer {
     ) -> Self {
         let (kind_for_abort, state_for_abort) = (kind.clone(), state.clone());
         let kind_for_spawn = kind.clone();
-        let mut time_of_fail: Option<DateTime<Utc>> = None;
+        let mut failures: u32 = 0;
         let (kill_tx, kill_rx) = watch::channel(RestreamerStatus::Started);
 
         let (spawner, abort_if_hanged) = future::abortable(
@@ -89,11 +91,12 @@ impl Restreamer {
                     let (kind, state) = (&kind_for_spawn, &state);
                     let mut cmd = Command::new(ffmpeg_path.as_ref());
                     let kill_rx_for_ffmpeg = kill_rx.clone();
+                    let started_at = Instant::now();
 
-                    _ = AssertUnwindSafe(
+                    let result = AssertUnwindSafe(
                         async move {
                             Self::change_status(
-                                time_of_fail,
+                                failures,
                                 kind,
                                 state,
                                 Status::Initializing,
@@ -106,62 +109,46 @@ impl Restreamer {
                                     .stderr(Stdio::piped()),
                                 state,
                             )
+                            .await
                             .map_err(|e| {
-                                tracing::error!(
-                                    "Failed to setup FFmpeg re-streamer: {}",
-                                    e,
-                                );
-                            })
-                            .await?;
-
-                            let running = kind
-                                .run_ffmpeg(cmd, kill_rx_for_ffmpeg)
-                                .in_current_span();
-                            pin_mut!(running);
-
-                            let set_online = async move {
-                                // If ffmpeg process does not fail within 10 sec
-                                // than set `Online` status.
-                                time::sleep(Duration::from_secs(10)).await;
-                                kind.renew_status(Status::Online, state);
-
-                                future::pending::<()>().await;
-                                Ok(())
-                            }
-                            .in_current_span();
-                            pin_mut!(set_online);
-
-                            future::try_select(running, set_online)
+                                format!(
+                                    "Failed to setup FFmpeg re-streamer: {e}",
+                                )
+                            })?;
+
+                            // `Status::Online` is set once FFmpeg reports
+                            // its progress.
+                            kind.run_ffmpeg(cmd, kill_rx_for_ffmpeg, state)
+                                .in_current_span()
                                 .await
                                 .map_err(|e| {
-                                    tracing::error!(
-                                        "Failed to run FFmpeg re-streamer: {}",
-                                        e.factor_first().0,
-                                    );
+                                    format!(
+                                        "Failed to run FFmpeg re-streamer: {e}",
+                                    )
                                 })
-                                .map(|r| r.factor_first().0)
                         }
-                        .in_current_span()
-                        .unwrap_or_else(|()| {
-                            Self::change_status(
-                                time_of_fail,
-                                kind,
-                                state,
-                                Status::Offline,
-                            );
-                            time_of_fail = Some(Utc::now());
-                        }),
+                        .in_current_span(),
                     )
                     .catch_unwind()
                     .await
-                    .map_err(|p| {
-                        tracing::error!(
+                    .unwrap_or_else(|p| {
+                        Err(format!(
                             "Panicked while spawning/observing FFmpeg \
-                         re-streamer: {}",
+                             re-streamer: {}",
                             display_panic(&p),
-                        );
+                        ))
                     });
 
+                    if let Err(e) = &result {
+                        tracing::error!("{e}");
+                        Self::change_status(
+                            failures,
+                            kind,
+                            state,
+                            Status::Offline,
+                        );
+                    }
+
                     if *kill_rx_for_loop.borrow() == RestreamerStatus::Finished
                     {
                         break;
@@ -177,7 +164,44 @@ impl Restreamer {
                         break;
                     };
 
-                    time::sleep(Duration::from_secs(2)).await;
+                    // Recording into a new size-rotated DVR file should
+                    // start right away, as it's not a failure.
+                    if result.is_ok() && kind.rotates_dvr_by_size() {
+                        failures = 0;
+                        continue;
+                    }
+
+                    let policy = state.retry_policy(kind.id());
+                    if let Err(e) = result {
+                        // Long enough run means the previous failures have
+                        // been recovered from.
+                        if started_at.elapsed() >= Self::STABLE_RUN {
+                            failures = 0;
+                        }
+                        failures += 1;
+
+                        if policy.is_exhausted(failures)
+                            && state.auto_disable_output(
+                                kind.id(),
+                                format!(
+                                    "Failed {failures} times in a row: \
+                                     {}",
+                                    // Details are kept in `StreamFailure`s.
+                                    e.lines().next().unwrap_or_default(),
+                                ),
+                            )
+                        {
+                            tracing::warn!(
+                                "Disabled Output after {failures} \
+                                 consecutive failures",
+                            );
+                            break;
+                        }
+                    } else {
+                        failures = 0;
+                    }
+
+                    time::sleep(policy.delay(failures)).await;
                 }
             }
             .in_current_span(),
@@ -196,32 +220,28 @@ impl Restreamer {
         }
     }
 
-    /// Check if the last time of fail was less that 15 sec. ago than [FFmpeg]
-    /// process is unstable.
-    /// In other case set new `[Status]` to `[RestreamerKind]`
+    /// Duration of running [FFmpeg] process, after which its previous
+    /// failures are not considered consecutive anymore.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    const STABLE_RUN: Duration = Duration::from_secs(30);
+
+    /// Sets the new [`Status`] of the given [`RestreamerKind`], unless the
+    /// [FFmpeg] process has failed recently, making it [`Status::Unstable`].
     ///
     /// [FFmpeg]: https://ffmpeg.org
     fn change_status(
-        time_of_fail: Option<DateTime<Utc>>,
+        failures: u32,
         kind: &RestreamerKind,
         state: &State,
         new_status: Status,
     ) {
-        match time_of_fail {
-            Some(dt) => {
-                let seconds =
-                    Utc::now().signed_duration_since(dt).num_seconds();
-                let status = if seconds < 15 {
-                    Status::Unstable
-                } else {
-                    new_status
-                };
-                kind.renew_status(status, state);
-            }
-            None => {
-                kind.renew_status(new_status, state);
-            }
-        }
+        let status = if failures > 0 {
+            Status::Unstable
+        } else {
+            new_status
+        };
+        kind.renew_status(status, state);
     }
 }
 
@@ -3,18 +3,25 @@
 //!
 //! [FFmpeg]: https://ffmpeg.org
 
+use chrono::Utc;
 use derive_more::From;
 use ephyr_log::{
     tracing,
     tracing::{instrument, Instrument},
     ChildCapture, ParsedMsg, Span,
 };
+use itertools::Itertools as _;
 use lazy_static::lazy_static;
 use libc::pid_t;
 use regex::Regex;
 use std::{
-    convert::TryInto, fmt::Display, os::unix::process::ExitStatusExt,
-    path::Path, time::Duration,
+    collections::VecDeque,
+    convert::TryInto,
+    fmt::Display,
+    os::unix::process::ExitStatusExt,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 use tokio::{io, process::Command, sync::watch};
 use url::Url;
@@ -25,13 +32,17 @@ use crate::{
     ffmpeg::{
         copy_restreamer::CopyRestreamer,
         file_restreamer::FileRestreamer,
+        metering::{MeteringLine, MeteringParser},
         mixing_restreamer::MixingRestreamer,
+        progress::{ProgressLine, ProgressParser},
         restreamer::RestreamerStatus,
         transcoding_restreamer::{TranscodingOptions, TranscodingRestreamer},
     },
     file_manager::LocalFileInfo,
     proc::kill_process,
-    state::{self, RestreamKey, State, Status},
+    server::srs_callback::update_stream_info,
+    state::{self, AudioEncoding, RestreamKey, State, Status},
+    stream_statistics::{AudioLevel, StreamFailure, StreamProgress},
 };
 
 /// Parse [FFmpeg] log line.
@@ -126,6 +137,20 @@ impl RestreamerKind {
         }
     }
 
+    /// Indicates whether this [FFmpeg] re-streaming process records into
+    /// [DVR] files rotated by their size, so its successful finish means the
+    /// current file is full.
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn rotates_dvr_by_size(&self) -> bool {
+        let url = self.to_url();
+        url.scheme() == "file"
+            && dvr::Rotation::parse(&url)
+                .map_or(false, |r| r.megabytes.is_some())
+    }
+
     /// Returns source url of [FFmpeg].
     ///
     /// [FFmpeg]: https://ffmpeg.org
@@ -187,6 +212,7 @@ impl RestreamerKind {
                         id,
                         from_url,
                         to_url,
+                        audio_encoding: AudioEncoding::default(),
                     }
                     .into()
                 }
@@ -206,12 +232,61 @@ impl RestreamerKind {
                 .into()
             }
 
+            state::InputEndpointKind::Srt => {
+                if is_playing_playlist || input.src.is_some() {
+                    return None;
+                }
+                CopyRestreamer {
+                    id: endpoint.id.into(),
+                    from_url: endpoint.srt.as_ref()?.listen_url(),
+                    to_url: state::InputEndpointKind::Rtmp
+                        .rtmp_url(key, &input.key),
+                    audio_encoding: AudioEncoding::default(),
+                }
+                .into()
+            }
+
             state::InputEndpointKind::File => {
                 return None;
             }
         })
     }
 
+    /// Creates a new [FFmpeg] process recording a live stream received by the
+    /// given [`state::Input`] into DVR files.
+    ///
+    /// Returns [`None`] if the given [`state::Input`] is not enabled, not
+    /// required to be recorded, or doesn't receive a live stream at the moment.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    #[instrument(skip_all, fields(restream.key=%key, input.key=%input.key))]
+    pub fn from_input_recording(
+        input: &state::Input,
+        key: &RestreamKey,
+    ) -> Option<Self> {
+        if !input.enabled || !input.record || !input.is_ready_to_serve() {
+            return None;
+        }
+
+        let to_url = dvr::Storage::global()
+            .input_file_url(input)
+            .map_err(|e| {
+                tracing::error!("Failed to form DVR file URL: {e}");
+            })
+            .ok()?;
+        Some(
+            CopyRestreamer {
+                id: input.id.into(),
+                from_url: state::InputEndpointKind::Rtmp
+                    .rtmp_url(key, &input.key),
+                to_url,
+                audio_encoding: AudioEncoding::default(),
+            }
+            .into(),
+        )
+    }
+
     /// Creates a new [FFmpeg] process streaming a file from playlist to
     /// [`state::Input`] endpoint.
     ///
@@ -269,7 +344,8 @@ impl RestreamerKind {
     /// [`state::Restream::input`] to the given [`state::Output::dst`] endpoint.
     ///
     /// `prev` value may be specified to consume already initialized resources,
-    /// which are unwanted to be re-created.
+    /// which are unwanted to be re-created, and `file_root` is the directory
+    /// of local files to be mixed from.
     ///
     /// Returns [`None`] if a [FFmpeg] re-streaming process cannot not be
     /// created for the given [`state::Output`].
@@ -283,20 +359,36 @@ impl RestreamerKind {
         output: &state::Output,
         from_url: &Url,
         prev: Option<&RestreamerKind>,
+        file_root: &Path,
     ) -> Option<Self> {
         if !output.enabled {
             return None;
         }
 
-        Some(if output.mixins.is_empty() {
-            CopyRestreamer {
+        if !output.mixins.is_empty() {
+            return Some(
+                MixingRestreamer::new(output, from_url, prev, file_root).into(),
+            );
+        }
+
+        let to_url = Self::dst_url(output);
+        Some(match output.transcoding.as_ref() {
+            Some(profile) if TranscodingRestreamer::supports_dst(&to_url) => {
+                Box::new(TranscodingRestreamer {
+                    id: output.id.into(),
+                    from_url: from_url.clone(),
+                    to_url,
+                    options: profile.into(),
+                })
+                .into()
+            }
+            _ => CopyRestreamer {
                 id: output.id.into(),
                 from_url: from_url.clone(),
-                to_url: Self::dst_url(output),
+                to_url,
+                audio_encoding: output.audio_encoding.unwrap_or_default(),
             }
-            .into()
-        } else {
-            MixingRestreamer::new(output, from_url, prev).into()
+            .into(),
         })
     }
 
@@ -307,9 +399,17 @@ impl RestreamerKind {
     #[inline]
     #[must_use]
     pub(crate) fn dst_url(output: &state::Output) -> Url {
-        (output.dst.scheme() == "file")
+        let mut url: Url = (output.dst.scheme() == "file")
             .then(|| dvr::Storage::global().file_url(output).unwrap())
-            .unwrap_or_else(|| output.dst.clone().into())
+            .unwrap_or_else(|| output.dst.clone().into());
+        if url.scheme() == "icecast" {
+            if let Some(password) = &output.password {
+                _ = url
+                    .query_pairs_mut()
+                    .append_pair(state::Icecast::PASSWORD_PARAM, password);
+            }
+        }
+        url
     }
 
     /// Checks whether this [`Restreamer`] must be restarted, as cannot apply
@@ -351,6 +451,15 @@ impl RestreamerKind {
             _ => cmd,
         };
     }
+
+    /// Makes [FFmpeg] periodically report its progress into STDOUT, so it can
+    /// be parsed by a [`ProgressParser`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    fn setup_progress(cmd: &mut Command) {
+        _ = cmd.args(["-nostats", "-stats_period", "2", "-progress", "pipe:1"]);
+    }
+
     /// Properly setups the given [FFmpeg] [`Command`] before running it.
     ///
     /// The specified [`State`] may be used to retrieve up-to-date parameters,
@@ -369,9 +478,10 @@ impl RestreamerKind {
         state: &State,
     ) -> io::Result<()> {
         Self::setup_logger(cmd);
+        Self::setup_progress(cmd);
         match self {
             Self::Copy(c) => c.setup_ffmpeg(cmd).await?,
-            Self::Transcoding(c) => c.setup_ffmpeg(cmd),
+            Self::Transcoding(c) => c.setup_ffmpeg(cmd).await?,
             Self::Mixing(m) => m.setup_ffmpeg(cmd, state).await?,
             Self::File(m) => m.setup_ffmpeg(cmd, false).await?,
         };
@@ -383,6 +493,14 @@ impl RestreamerKind {
     /// Returns [`Ok`] if the [`kill_rx`] was sent and the ffmpeg process
     /// was stopped properly or if the entire input file was played to the end.
     ///
+    /// While running, [`StreamProgress`] reported by the [FFmpeg] process is
+    /// tracked in the `actual` [`State`] along with the [`Status`] derived
+    /// from it. Unsuccessful exit of the [FFmpeg] process is recorded there as
+    /// a [`StreamFailure`]. [`AudioLevel`]s measured by a mixing [FFmpeg]
+    /// process are tracked there too, while it's running, along with the
+    /// [TeamSpeak] channel members talking into its [`Mixin`]s, and
+    /// unrecoverable errors of its [TeamSpeak] and [Mumble] [`Mixin`]s.
+    ///
     /// In case of [`Self::Mixin`] before starting [`Command`]
     /// the FIFO files are created. For each pair of [`Mixin`] and FIFO the
     /// new task are created and transfer data from [`Mixin.stdin`] to FIFO.
@@ -393,18 +511,46 @@ impl RestreamerKind {
     /// [FFmpeg] process was stopped.
     ///
     /// [FFmpeg]: https://ffmpeg.org
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
     #[inline]
     #[instrument(skip_all, fields(?cmd))]
     pub(crate) async fn run_ffmpeg(
         &self,
         cmd: Command,
         kill_rx: watch::Receiver<RestreamerStatus>,
+        actual: &State,
     ) -> io::Result<()> {
-        if let Self::Mixing(m) = self {
+        let voice_tracking = if let Self::Mixing(m) = self {
             m.start_fed_mixins_fifo(&kill_rx);
-        }
+            m.spawn_voice_tracking(actual)
+        } else {
+            None
+        };
         tracing::debug!("Starting ffmpeg process {cmd:?}");
-        Self::run_ffmpeg_(cmd, kill_rx).await
+        let (kind, state) = (self.clone(), actual.clone());
+        let (level_kind, level_state) = (self.clone(), actual.clone());
+        let res = Self::run_ffmpeg_(
+            cmd,
+            kill_rx,
+            move |progress, status| {
+                kind.renew_progress(progress, status, &state);
+            },
+            move |track, level| {
+                level_kind.renew_audio_level(track, level, &level_state);
+            },
+            |failure| self.record_failure(failure, actual),
+        )
+        .await;
+        if let Some(tracking) = voice_tracking {
+            tracking.abort();
+        }
+        if let Self::Mixing(m) = self {
+            let output_id = state::OutputId::from(m.id);
+            _ = actual.audio_levels.lock_mut().remove(&output_id);
+            _ = actual.talking_speakers.lock_mut().remove(&output_id);
+        }
+        res
     }
 
     /// Properly runs the given [FFmpeg] [`Command`] awaiting its completion.
@@ -418,10 +564,18 @@ impl RestreamerKind {
     /// [FFmpeg] process was stopped.
     ///
     /// [FFmpeg]: https://ffmpeg.org
-    async fn run_ffmpeg_(
+    async fn run_ffmpeg_<P, L, F>(
         mut cmd: Command,
         mut kill_rx: watch::Receiver<RestreamerStatus>,
-    ) -> io::Result<()> {
+        on_progress: P,
+        on_level: L,
+        on_failure: F,
+    ) -> io::Result<()>
+    where
+        P: Fn(StreamProgress, Status) + Send + 'static,
+        L: Fn(Uuid, AudioLevel) + Send + 'static,
+        F: FnOnce(StreamFailure),
+    {
         let process = cmd.spawn()?;
 
         // To avoid instant resolve on await for `kill_rx`
@@ -447,10 +601,53 @@ impl RestreamerKind {
             .in_current_span(),
         );
 
+        let parser = Mutex::new(ProgressParser::default());
+        let metering = Mutex::new(MeteringParser::default());
+        let logs_tail = Arc::new(Mutex::new(VecDeque::with_capacity(
+            StreamFailure::STDERR_LINES,
+        )));
+        let logs = Arc::clone(&logs_tail);
         let out = process
             .capture_logs_and_wait_for_output(
                 tracing::info_span!(parent: Span::current(), "ffmpeg_proc"),
-                parse_ffmpeg_log_line,
+                move |line| {
+                    // Audio levels are printed too often to be logged.
+                    let metered = metering
+                        .lock()
+                        .ok()
+                        .and_then(|mut p| p.parse_line(line));
+                    if let Some(metered) = metered {
+                        if let MeteringLine::Report(track, level) = metered {
+                            on_level(track, level);
+                        }
+                        return ParsedMsg {
+                            message: line,
+                            level: "trace",
+                        };
+                    }
+
+                    let parsed =
+                        parser.lock().ok().and_then(|mut p| p.parse_line(line));
+                    match parsed {
+                        Some(ProgressLine::Report(progress, status)) => {
+                            on_progress(progress, status);
+                        }
+                        Some(ProgressLine::Partial) => {}
+                        None => {
+                            if let Ok(mut logs) = logs.lock() {
+                                if logs.len() >= StreamFailure::STDERR_LINES {
+                                    _ = logs.pop_front();
+                                }
+                                logs.push_back(line.to_owned());
+                            }
+                            return parse_ffmpeg_log_line(line);
+                        }
+                    }
+                    ParsedMsg {
+                        message: line,
+                        level: "trace",
+                    }
+                },
             )
             .await?;
         kill_task.abort();
@@ -470,21 +667,33 @@ impl RestreamerKind {
             );
             Ok(())
         } else {
-            Err(io::Error::new(
+            let stderr = logs_tail
+                .lock()
+                .map(|logs| logs.iter().join("\n"))
+                .unwrap_or_default();
+            let err = io::Error::new(
                 io::ErrorKind::Other,
                 format!(
                     "FFmpeg re-streamer unsuccessfully stopped \
                     with exit code: {}\n{}",
-                    out.status,
-                    String::from_utf8_lossy(&out.stderr),
+                    out.status, stderr,
                 ),
-            ))
+            );
+            on_failure(StreamFailure {
+                happened_at: Utc::now(),
+                exit_status: out.status.to_string(),
+                stderr,
+            });
+            Err(err)
         }
     }
 
     /// Renews [`Status`] of this [FFmpeg] re-streaming process in the `actual`
     /// [`State`].
     ///
+    /// Once the process goes [`Status::Offline`], its [`StreamProgress`] is
+    /// discarded.
+    ///
     /// [FFmpeg]: https://ffmpeg.org
     pub fn renew_status(&self, status: Status, actual: &State) {
         for restream in actual.restreams.lock_mut().iter_mut() {
@@ -493,6 +702,9 @@ impl RestreamerKind {
                 for o in &mut restream.outputs {
                     if o.id == my_id {
                         o.status = status;
+                        if status == Status::Offline {
+                            o.progress = None;
+                        }
                         return;
                     }
                 }
@@ -503,9 +715,108 @@ impl RestreamerKind {
                 if let Some(endpoint) = restream.input.find_endpoint(self.id())
                 {
                     endpoint.status = status;
+                    if status == Status::Offline {
+                        endpoint.progress = None;
+                    }
                     return;
                 }
             }
         }
     }
+
+    /// Records the given [`StreamFailure`] of this [FFmpeg] re-streaming
+    /// process in the `actual` [`State`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn record_failure(&self, failure: StreamFailure, actual: &State) {
+        let my_id = self.id();
+        for restream in actual.restreams.lock_mut().iter_mut() {
+            if let Some(o) = restream.outputs.iter_mut().find(|o| o.id == my_id)
+            {
+                StreamFailure::push(&mut o.failures, failure);
+                return;
+            }
+
+            if let Some(endpoint) = restream.input.find_endpoint(self.id()) {
+                StreamFailure::push(&mut endpoint.failures, failure);
+                return;
+            }
+        }
+    }
+
+    /// Renews [`AudioLevel`] of the specified `track` (the origin audio or a
+    /// `Mixin`) measured by this mixing [FFmpeg] re-streaming process in the
+    /// `actual` [`State`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn renew_audio_level(
+        &self,
+        track: Uuid,
+        mut level: AudioLevel,
+        actual: &State,
+    ) {
+        let my_id: Uuid = self.id();
+        level.mixin_id = (track != my_id).then(|| track.into());
+
+        let mut levels = actual.audio_levels.lock_mut();
+        let levels = levels.entry(self.id()).or_default();
+        if let Some(l) =
+            levels.iter_mut().find(|l| l.mixin_id == level.mixin_id)
+        {
+            *l = level;
+        } else {
+            levels.push(level);
+        }
+    }
+
+    /// Renews [`StreamProgress`] of this [FFmpeg] re-streaming process in the
+    /// `actual` [`State`], along with the [`Status`] derived from it.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn renew_progress(
+        &self,
+        progress: StreamProgress,
+        status: Status,
+        actual: &State,
+    ) {
+        let my_id = self.id();
+        for restream in actual.restreams.lock_mut().iter_mut() {
+            if let Some(o) = restream.outputs.iter_mut().find(|o| o.id == my_id)
+            {
+                o.status = status;
+                o.progress = Some(progress);
+                return;
+            }
+
+            if let Some(endpoint) = restream.input.find_endpoint(self.id()) {
+                // `Status::Online` for `state::Input` is set by SRS HTTP
+                // Callback, so is only restored here once published. SRT
+                // endpoint is not known to SRS, so its remuxing process
+                // reporting progress means the stream is being pushed.
+                if endpoint.is_srt()
+                    && status == Status::Online
+                    && endpoint.status != Status::Online
+                {
+                    endpoint.stream_stat = None;
+                    update_stream_info(
+                        endpoint.id,
+                        self.to_url().to_string(),
+                        actual.clone(),
+                    );
+                    tracing::info!(
+                        actor = %endpoint.id,
+                        "Publishing via SRT started"
+                    );
+                }
+                if status != Status::Online
+                    || endpoint.srs_publisher_id.is_some()
+                    || endpoint.is_srt()
+                {
+                    endpoint.status = status;
+                }
+                endpoint.progress = Some(progress);
+                return;
+            }
+        }
+    }
 }
@@ -175,6 +175,13 @@ impl RestreamersPool {
                 self.apply_new_kind(id, new_kind, new_pool);
             }
         }
+
+        if let Some(new_kind) = RestreamerKind::from_input_recording(input, key)
+            .filter(|_| !self.state.dvr_space_low.get())
+        {
+            tracing::debug!(actor=%input.id, "Input recording aka Restreamer");
+            self.apply_new_kind(input.id.into(), new_kind, new_pool);
+        }
     }
 
     /// Inspects the given [`state::Output`] filling the `new_pool` with a
@@ -191,7 +198,10 @@ impl RestreamersPool {
         output: &state::Output,
         new_pool: &mut HashMap<Uuid, Restreamer>,
     ) -> Option<()> {
-        if !output.enabled {
+        // Recording is suspended while free space of DVR disk is low.
+        if !output.enabled
+            || (output.dst.scheme() == "file" && self.state.dvr_space_low.get())
+        {
             return None;
         }
 
@@ -201,6 +211,7 @@ impl RestreamersPool {
             output,
             from_url,
             self.pool.get(&id).map(|p| &p.kind),
+            &self.files_root,
         )?;
 
         self.apply_new_kind(id, new_kind, new_pool);
@@ -4,12 +4,19 @@
 //!
 //! [FFmpeg]: https://ffmpeg.org
 
-use std::borrow::Cow;
+use std::{borrow::Cow, ffi::OsStr, path::Path};
 
-use tokio::process::Command;
+use tokio::{io, process::Command};
 use url::Url;
 use uuid::Uuid;
 
+use crate::{
+    ffmpeg::copy_restreamer::{
+        setup_dvr_output, setup_hls_push, setup_pull_input,
+    },
+    state,
+};
+
 /// Options for transcoding video and audio streams.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TranscodingOptions {
@@ -51,6 +58,21 @@ pub struct TranscodingOptions {
     ///
     /// [1]: https://trac.ffmpeg.org/wiki/Encode/H.264#Tune
     pub tune: Option<Cow<'static, str>>,
+
+    /// Target bitrate for the output video stream.
+    pub vbitrate: Option<Cow<'static, str>>,
+
+    /// [Scale][1] of the output video stream in `width:height` format.
+    ///
+    /// [1]: https://trac.ffmpeg.org/wiki/Scaling
+    pub scale: Option<Cow<'static, str>>,
+
+    /// Size of the GOP (distance between keyframes) for the output video
+    /// stream.
+    pub gop: Option<Cow<'static, str>>,
+
+    /// Target bitrate for the output audio stream.
+    pub abitrate: Option<Cow<'static, str>>,
 }
 
 impl Default for TranscodingOptions {
@@ -66,10 +88,48 @@ impl Default for TranscodingOptions {
                 Some("aac".into())
             },
             maxrate: Some("8M".into()),
-            bufsize: Some("16M".into()),
+            // Playback and HLS re-streams have always been buffered as much as
+            // their `maxrate` allows, so keep it for them.
+            bufsize: Some("8M".into()),
             ar: Some("48000".into()),
             fps: Some("25".into()),
             tune: Some("zerolatency".into()),
+            vbitrate: None,
+            scale: None,
+            gop: None,
+            abitrate: None,
+        }
+    }
+}
+
+impl From<&state::TranscodingProfile> for TranscodingOptions {
+    fn from(profile: &state::TranscodingProfile) -> Self {
+        let vbitrate = profile.video_bitrate.map(|b| b.0);
+        let scale = match (profile.width, profile.height) {
+            (None, None) => None,
+            // `-2` keeps the aspect ratio and the value divisible by 2, as
+            // required by most encoders.
+            (w, h) => Some(
+                format!(
+                    "{}:{}",
+                    w.map_or(-2, |w| i32::from(w.0)),
+                    h.map_or(-2, |h| i32::from(h.0)),
+                )
+                .into(),
+            ),
+        };
+        Self {
+            vcodec: Some(profile.video_codec.encoder().into()),
+            vprofile: None,
+            maxrate: vbitrate.map(|b| format!("{b}k").into()),
+            bufsize: vbitrate.map(|b| format!("{}k", u32::from(b) * 2).into()),
+            fps: profile.fps.map(|f| f.0.to_string().into()),
+            vbitrate: vbitrate.map(|b| format!("{b}k").into()),
+            scale,
+            gop: profile.gop.map(|g| g.0.to_string().into()),
+            acodec: Some(profile.audio_codec.encoder().into()),
+            abitrate: profile.audio_bitrate.map(|b| format!("{}k", b.0).into()),
+            ..Self::default()
         }
     }
 }
@@ -112,17 +172,26 @@ impl TranscodingRestreamer {
     /// Properly setups the given [FFmpeg] [`Command`] for this
     /// [`TranscodingRestreamer`] before running it.
     ///
+    /// # Errors
+    ///
+    /// If the given [FFmpeg] [`Command`] fails to be setup.
+    ///
     /// [FFmpeg]: https://ffmpeg.org
-    pub(crate) fn setup_ffmpeg(&self, cmd: &mut Command) {
-        match self.from_url.scheme() {
-            "http" | "https" | "rtmp" | "rtmps" => (),
+    pub(crate) async fn setup_ffmpeg(
+        &self,
+        cmd: &mut Command,
+    ) -> io::Result<()> {
+        let from_url = match self.from_url.scheme() {
+            "http" | "https" | "rtmp" | "rtmps" => self.from_url.clone(),
+            "rtsp" | "rtsps" | "srt" => setup_pull_input(cmd, &self.from_url),
             "file" => {
                 _ = cmd.arg("-re").args(["-stream_loop", "-1"]);
+                self.from_url.clone()
             }
             _ => unimplemented!(),
-        }
+        };
         // Setup input
-        _ = cmd.args(["-i", self.from_url.as_str()]);
+        _ = cmd.args(["-i", from_url.as_str()]);
         let opts = &self.options;
         // Video options
         if let Some(val) = opts.vcodec.as_ref() {
@@ -137,6 +206,15 @@ impl TranscodingRestreamer {
         if let Some(val) = opts.vprofile.as_ref() {
             _ = cmd.args(["-profile:v", val]);
         }
+        if let Some(val) = opts.vbitrate.as_ref() {
+            _ = cmd.args(["-b:v", val]);
+        }
+        if let Some(val) = opts.scale.as_ref() {
+            _ = cmd.args(["-vf", &format!("scale={val}")]);
+        }
+        if let Some(val) = opts.gop.as_ref() {
+            _ = cmd.args(["-g", val]);
+        }
 
         // Audio options
         if let Some(val) = opts.acodec.as_ref() {
@@ -145,12 +223,15 @@ impl TranscodingRestreamer {
         if let Some(val) = opts.ar.as_ref() {
             let _ = cmd.args(["-ar", val]);
         }
+        if let Some(val) = opts.abitrate.as_ref() {
+            _ = cmd.args(["-b:a", val]);
+        }
         if let Some(val) = opts.maxrate.as_ref() {
             let _ = cmd.args(["-maxrate", val]);
         }
 
         // Output options
-        if let Some(val) = opts.maxrate.as_ref() {
+        if let Some(val) = opts.bufsize.as_ref() {
             _ = cmd.args(["-bufsize", val]);
         }
         if let Some(val) = opts.fps.as_ref() {
@@ -158,9 +239,41 @@ impl TranscodingRestreamer {
         }
 
         _ = match self.to_url.scheme() {
-            "rtmp" | "rtmps" => cmd.args(["-f", "flv"]),
+            "file"
+                if matches!(
+                    Path::new(self.to_url.path())
+                        .extension()
+                        .and_then(OsStr::to_str),
+                    Some("flv" | "mkv" | "mp4"),
+                ) =>
+            {
+                setup_dvr_output(cmd, &self.to_url).await?
+            }
+            "rtmp" | "rtmps" => {
+                cmd.args(["-f", "flv"]).arg(self.to_url.as_str())
+            }
+            "srt" => cmd
+                .args(["-strict", "-2", "-y", "-f", "mpegts"])
+                .arg(self.to_url.as_str()),
+            "hls+http" | "hls+https" => setup_hls_push(cmd, &self.to_url),
             _ => unimplemented!(),
+        };
+        Ok(())
+    }
+
+    /// Indicates whether the given destination [`Url`] may be transcoded into
+    /// by a [`TranscodingRestreamer`].
+    ///
+    /// Audio-only destinations are not, as there is no video to transcode.
+    #[must_use]
+    pub fn supports_dst(url: &Url) -> bool {
+        match url.scheme() {
+            "rtmp" | "rtmps" | "srt" | "hls+http" | "hls+https" => true,
+            "file" => matches!(
+                Path::new(url.path()).extension().and_then(OsStr::to_str),
+                Some("flv" | "mkv" | "mp4"),
+            ),
+            _ => false,
         }
-        .arg(self.to_url.as_str());
     }
 }
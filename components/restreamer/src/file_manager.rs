@@ -549,9 +549,9 @@ pub struct PlaylistFileInfo {
     pub was_played: bool,
 }
 
-impl From<DriveFileInfo> for spec::v1::PlaylistFileInfo {
+impl From<DriveFileInfo> for spec::v2::PlaylistFileInfo {
     fn from(file_response: DriveFileInfo) -> Self {
-        spec::v1::PlaylistFileInfo {
+        spec::v2::PlaylistFileInfo {
             file_id: FileId::from(file_response.id),
             name: file_response.name,
         }
@@ -641,7 +641,7 @@ impl NetworkByteSize {
 pub async fn get_video_file_from_gdrive(
     api_key: &str,
     file_id: &str,
-) -> Result<spec::v1::PlaylistFileInfo, String> {
+) -> Result<spec::v2::PlaylistFileInfo, String> {
     let file_info = GoogleDriveApi::new(api_key)
         .files()
         .get_file_info(file_id)
@@ -663,7 +663,7 @@ pub async fn get_video_file_from_gdrive(
 pub async fn get_video_list_from_gdrive_folder(
     api_key: &str,
     folder_id: &str,
-) -> Result<Vec<spec::v1::PlaylistFileInfo>, String> {
+) -> Result<Vec<spec::v2::PlaylistFileInfo>, String> {
     let response = GoogleDriveApi::new(api_key)
         .files()
         .get_dir_videos(folder_id)
@@ -34,6 +34,7 @@ pub mod console_logger;
 pub mod dvr;
 pub mod ffmpeg;
 pub mod file_manager;
+pub mod mumble;
 mod proc;
 pub mod server;
 pub mod spec;
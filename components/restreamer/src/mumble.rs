@@ -0,0 +1,1388 @@
+//! [Mumble] audio capture definitions.
+//!
+//! [Mumble]: https://www.mumble.info
+
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use audiopus::{coder::Decoder, Channels, SampleRate};
+use backoff::{future::retry_notify, ExponentialBackoff};
+use byteorder::{BigEndian, ByteOrder as _};
+use derive_more::{Display, Error};
+use ephyr_log::tracing::{self, instrument, Span};
+use futures::{
+    future, ready, stream, FutureExt as _, StreamExt as _, TryFutureExt as _,
+};
+use prost::Message as _;
+use tokio::{
+    io::{self, AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _},
+    net::TcpStream,
+    pin,
+    task::JoinHandle,
+    time,
+};
+use tokio_native_tls::{native_tls, TlsConnector};
+
+/// Default port of [Mumble] servers.
+///
+/// [Mumble]: https://www.mumble.info
+pub const DEFAULT_PORT: u16 = 64738;
+
+const CONNECTION_RETRY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Interval to ping [Mumble] server with, keeping the connection alive.
+///
+/// [Mumble] server disconnects clients not pinging it for 30 seconds.
+///
+/// [Mumble]: https://www.mumble.info
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum size (in bytes) of a control message accepted from [Mumble] server.
+///
+/// [Mumble]: https://www.mumble.info
+const MAX_MESSAGE_SIZE: usize = 8 * 1024 * 1024;
+
+/// Type of [Mumble] server user session ID.
+///
+/// [Mumble]: https://www.mumble.info
+type Session = u32;
+
+/// Parameters of connecting to a [Mumble] server.
+///
+/// [Mumble]: https://www.mumble.info
+#[derive(Clone)]
+pub struct Config {
+    /// Host of the [Mumble] server.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    pub host: String,
+
+    /// Port of the [Mumble] server.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    pub port: u16,
+
+    /// Path of the (possibly nested) channel to join, with its nested channels
+    /// separated by `/`.
+    ///
+    /// If empty, then the root channel is stayed in.
+    pub channel: String,
+
+    /// Name to join the [Mumble] server with.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    pub name: String,
+
+    /// Password of the [Mumble] server, if it requires one.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    pub password: Option<String>,
+
+    /// Password of the channel, if it requires one.
+    ///
+    /// [Mumble] channel passwords are access tokens, provided on connection.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    pub channel_password: Option<String>,
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hidden = |p: &Option<String>| p.as_ref().map(|_| "*****");
+        f.debug_struct("Config")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("channel", &self.channel)
+            .field("name", &self.name)
+            .field("password", &hidden(&self.password))
+            .field("channel_password", &hidden(&self.channel_password))
+            .finish()
+    }
+}
+
+/// Audio input captured from [Mumble] server.
+///
+/// It produces [PCM 32-bit floating-point big-endian][1] encoded
+/// [`Input::CHANNELS`]-stereo audio samples (`f32be` format in [FFmpeg]'s
+/// [notation][2]) with a constant [`Input::SAMPLE_RATE`], exactly as a
+/// [`teamspeak::Input`] does.
+///
+/// [`teamspeak::Input`]: crate::teamspeak::Input
+/// [FFmpeg]: https://ffmpeg.org
+/// [Mumble]: https://www.mumble.info
+/// [1]: https://wiki.multimedia.cx/index.php/PCM
+/// [2]: https://trac.ffmpeg.org/wiki/audio%20types
+pub struct Input {
+    /// [`Config`] for establishing new connections with.
+    cfg: Config,
+
+    /// Ticker that fires each [`Input::FREQUENCY_MILLIS`] and is used
+    /// to determine when samples should be emitted.
+    ticker: time::Interval,
+
+    /// Audio frame (samples sequence of [`Input::FRAME_SIZE`]) being emitted
+    /// on each [`Input::ticker`] tick.
+    frame: Vec<f32>,
+
+    /// Cursor indicating the position in [`Input::frame`] to start reading it
+    /// from.
+    cursor: usize,
+
+    /// Mixer of audio of all [Mumble] channel members, for this [`Input`].
+    ///
+    /// [Mumble]: https://www.mumble.info
+    pub audio: Arc<Mutex<AudioMixer>>,
+
+    /// Abort handle and waiter of the spawned [`AudioCapture`], which receives
+    /// audio packets from [Mumble] server and feeds them into the
+    /// [`Input::audio`] mixer.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    conn: Option<(future::AbortHandle, JoinHandle<()>)>,
+
+    /// Description of the last error the spawned [`AudioCapture`] is unable to
+    /// recover from (like a wrong [Mumble] server or channel password), so
+    /// this [`Input`] should return an error too.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    pub failure: Arc<Mutex<Option<String>>>,
+
+    span: Span,
+}
+
+impl Input {
+    /// Sample rate that [`Input`] emits audio samples with.
+    pub const SAMPLE_RATE: usize = 48000;
+
+    /// Number of channels in stereo audio produced by [`Input`].
+    pub const CHANNELS: usize = 2;
+
+    /// Frequency (in milliseconds) that [`Input`] emits audio samples with.
+    pub const FREQUENCY_MILLIS: usize = 20;
+
+    /// Size (in samples) of a single frame emitted by [`Input`] each
+    /// [`Input::FREQUENCY_MILLIS`].
+    pub const FRAME_SIZE: usize =
+        Self::SAMPLE_RATE / 1000 * Self::FREQUENCY_MILLIS * Self::CHANNELS;
+
+    /// Creates a new [`Input`] with the provided [`Config`].
+    #[must_use]
+    pub fn new(cfg: Config) -> Self {
+        Self {
+            cfg,
+            ticker: time::interval(Duration::from_millis(
+                Self::FREQUENCY_MILLIS as u64,
+            )),
+            frame: vec![0.0; Self::FRAME_SIZE],
+            cursor: 0,
+            audio: Arc::new(Mutex::new(AudioMixer::default())),
+            conn: None,
+            failure: Arc::new(Mutex::new(None)),
+            span: tracing::info_span!("mumble::Input"),
+        }
+    }
+
+    /// Spawns an [`AudioCapture`] associated with this [`Input`], retrying it
+    /// endlessly with an [`ExponentialBackoff`] if it fails in a recoverable
+    /// way.
+    #[instrument(skip_all, parent=&self.span)]
+    fn spawn_audio_capturing(&mut self) {
+        let cfg = self.cfg.clone();
+        let audio = self.audio.clone();
+        let failure = self.failure.clone();
+
+        let capturing = retry_notify(
+            ExponentialBackoff {
+                max_elapsed_time: Some(CONNECTION_RETRY_TIMEOUT),
+                ..ExponentialBackoff::default()
+            },
+            move || {
+                AudioCapture::run(cfg.clone(), audio.clone())
+                    .map_err(AudioCaptureError::into_backoff)
+            },
+            |err, dur| {
+                tracing::error!(
+                    "Backoff Mumble server audio capturing for {} due to \
+                     error: {err}",
+                    humantime::format_duration(dur),
+                );
+            },
+        )
+        .map_err(move |e| {
+            tracing::error!("Cannot capture audio from Mumble server: {e}");
+            *failure.lock().unwrap() = Some(e.to_string());
+        });
+
+        let (abort, on_abort) = future::AbortHandle::new_pair();
+        let waiter = tokio::spawn(
+            future::Abortable::new(capturing, on_abort).map(|_| ()),
+        );
+
+        self.conn = Some((abort, waiter));
+    }
+}
+
+impl AsyncRead for Input {
+    /// Emits audio frame of [`Input::FRAME_SIZE`] each
+    /// [`Input::FREQUENCY_MILLIS`]. The frame contains mixed audio of all
+    /// [Mumble] channel members talking at the moment. If there is no
+    /// talking members, the just a silence is emitted.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[instrument(skip_all, parent=&self.span)]
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.conn.is_none() {
+            self.spawn_audio_capturing();
+        }
+        if self.failure.lock().unwrap().is_some() {
+            return Poll::Ready(Err(InputError::NoData.into()));
+        }
+
+        if self.cursor >= self.frame.len() {
+            // `time::Interval` stream never returns `None`, so we can omit
+            // checking it to be finished.
+            _ = ready!(Pin::new(&mut self.ticker).poll_tick(cx));
+
+            self.cursor = 0;
+            self.frame.fill(0.0);
+            self.audio
+                .clone()
+                .lock()
+                .unwrap()
+                .fill_buffer(&mut self.frame);
+        }
+
+        let cursor = self.cursor;
+
+        // Detect how much samples we can mix and write into `dst`.
+        let src_size = self.frame.len() - cursor;
+
+        // `f32` takes 4 bytes in big endian, so we should fit in there.
+        if buf.remaining() <= 3 {
+            return Poll::Ready(Err(InputError::TooSmallBuffer.into()));
+        }
+
+        let size = src_size.min(buf.remaining() / 4);
+        let unfilled = buf.initialize_unfilled();
+        let size_in_bytes = size * 4;
+
+        BigEndian::write_f32_into(
+            &self.frame[cursor..(cursor + size)],
+            &mut unfilled[0..(size_in_bytes)],
+        );
+
+        buf.advance(size_in_bytes);
+        self.cursor += size;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Input")
+            .field("cfg", &self.cfg)
+            .field("ticker", &self.ticker)
+            .field("frame", &self.frame)
+            .field("cursor", &self.cursor)
+            .field("audio", &self.audio)
+            .field("conn", &self.conn)
+            .field("failure", &self.failure)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Input {
+    /// Aborts the spawned [`AudioCapture`], if any.
+    ///
+    /// Unlike [TeamSpeak], [Mumble] server requires no handshake to disconnect
+    /// from it, so there is no need to await for the [`AudioCapture`] to
+    /// complete.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    #[inline]
+    fn drop(&mut self) {
+        if let Some((conn, _)) = self.conn.take() {
+            conn.abort();
+        }
+    }
+}
+
+/// Possible errors of reading [`Input`].
+#[derive(Debug, Display, Error)]
+pub enum InputError {
+    /// No data can be received from [Mumble] server.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[display(fmt = "Unable to receive data from Mumble server")]
+    NoData,
+
+    /// Input buffer provided to read [`Input`] is too small to read any data.
+    #[display(fmt = "Input buffer is too small")]
+    TooSmallBuffer,
+}
+
+impl From<InputError> for io::Error {
+    fn from(e: InputError) -> Self {
+        use InputError as E;
+
+        let kind = match e {
+            E::NoData => io::ErrorKind::NotConnected,
+            E::TooSmallBuffer => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, e)
+    }
+}
+
+/// Mixer of audio of all talking [Mumble] channel members.
+///
+/// [Mumble]: https://www.mumble.info
+#[derive(Debug, Default)]
+pub struct AudioMixer {
+    /// Audio streams of the talking channel members.
+    members: HashMap<Session, MemberAudio>,
+}
+
+impl AudioMixer {
+    /// Number of samples to be buffered for a channel member before mixing
+    /// them, compensating network jitter (60 ms).
+    const PREBUFFER: usize = Input::FRAME_SIZE * 3;
+
+    /// Maximum number of samples buffered for a channel member (1 second), so
+    /// the older ones are dropped.
+    const MAX_BUFFERED: usize = Input::SAMPLE_RATE * Input::CHANNELS;
+
+    /// Maximum number of samples in a single [Opus] packet (120 ms).
+    ///
+    /// [Opus]: https://opus-codec.org
+    const MAX_PACKET_SAMPLES: usize =
+        Input::SAMPLE_RATE / 1000 * 120 * Input::CHANNELS;
+
+    /// Decodes the given [Opus] packet received from the channel member with
+    /// the given `session`, and buffers its samples for mixing.
+    ///
+    /// # Errors
+    ///
+    /// If decoding the packet fails.
+    ///
+    /// [Opus]: https://opus-codec.org
+    pub fn handle_packet(
+        &mut self,
+        session: Session,
+        opus: &[u8],
+    ) -> Result<(), audiopus::Error> {
+        let member = match self.members.entry(session) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(MemberAudio {
+                decoder: Decoder::new(SampleRate::Hz48000, Channels::Stereo)?,
+                samples: VecDeque::new(),
+                is_playing: false,
+            }),
+        };
+
+        let mut decoded = vec![0.0; Self::MAX_PACKET_SAMPLES];
+        let len =
+            member
+                .decoder
+                .decode_float(Some(opus), &mut decoded[..], false)?
+                * Input::CHANNELS;
+        member.samples.extend(&decoded[..len]);
+
+        let excess = member.samples.len().saturating_sub(Self::MAX_BUFFERED);
+        drop(member.samples.drain(..excess));
+        Ok(())
+    }
+
+    /// Mixes the buffered samples of all the talking channel members into the
+    /// given `frame`.
+    pub fn fill_buffer(&mut self, frame: &mut [f32]) {
+        for member in self.members.values_mut() {
+            if !member.is_playing {
+                if member.samples.len() < Self::PREBUFFER {
+                    continue;
+                }
+                member.is_playing = true;
+            }
+
+            let len = frame.len().min(member.samples.len());
+            for (dst, src) in frame.iter_mut().zip(member.samples.drain(..len))
+            {
+                *dst += src;
+            }
+
+            if member.samples.is_empty() {
+                member.is_playing = false;
+            }
+        }
+    }
+
+    /// Forgets the channel member with the given `session`, once it leaves
+    /// the server.
+    pub fn remove(&mut self, session: Session) {
+        drop(self.members.remove(&session));
+    }
+
+    /// Forgets all the channel members.
+    pub fn reset(&mut self) {
+        self.members.clear();
+    }
+}
+
+/// Audio stream of a single talking [Mumble] channel member.
+///
+/// [Mumble]: https://www.mumble.info
+struct MemberAudio {
+    /// [Opus] decoder of the channel member's audio packets.
+    ///
+    /// [Opus]: https://opus-codec.org
+    decoder: Decoder,
+
+    /// Decoded samples not mixed yet.
+    samples: VecDeque<f32>,
+
+    /// Indicator whether enough samples have been buffered to mix them.
+    is_playing: bool,
+}
+
+impl fmt::Debug for MemberAudio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemberAudio")
+            .field("samples", &self.samples.len())
+            .field("is_playing", &self.is_playing)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Listener of [Mumble] channel, which captures audio packets of each talking
+/// channel member and feeds them into an [`AudioMixer`].
+///
+/// Audio packets are received via the TCP control connection (tunneled), so
+/// no UDP connection is established at all.
+///
+/// [Mumble]: https://www.mumble.info
+#[derive(Debug)]
+pub struct AudioCapture {
+    /// [`Config`] of the connection.
+    cfg: Config,
+
+    /// Mixer of audio packets received from [Mumble] server.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    audio: Arc<Mutex<AudioMixer>>,
+
+    /// Channels of the [Mumble] server, by their IDs.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    channels: HashMap<u32, Channel>,
+
+    /// Session of this [`AudioCapture`], once synchronized with [Mumble]
+    /// server.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    session: Option<Session>,
+}
+
+impl AudioCapture {
+    /// Version of [Mumble] protocol to announce (`1.4.0`), so the server sends
+    /// audio packets in the legacy format.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    const VERSION: u32 = 1 << 16 | 4 << 8;
+
+    /// Creates new [`AudioCapture`] with the given [`Config`] and for the given
+    /// [`AudioMixer`].
+    #[inline]
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn new(cfg: Config, audio: Arc<Mutex<AudioMixer>>) -> Self {
+        audio.lock().unwrap().reset();
+        Self {
+            cfg,
+            audio,
+            channels: HashMap::new(),
+            session: None,
+        }
+    }
+
+    /// Creates a new [`AudioCapture`] using the given [`Config`] for the given
+    /// [`AudioMixer`], connects to [Mumble] server and awaits its completion.
+    ///
+    /// # Errors
+    ///
+    /// - If connecting to [Mumble] server fails;
+    /// - If [Mumble] server rejects the connection or denies to join the
+    ///   channel.
+    ///
+    /// See [`AudioCaptureError`] for details.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[instrument(skip_all, name = "mumble::AudioCapture")]
+    pub async fn run(
+        cfg: Config,
+        audio: Arc<Mutex<AudioMixer>>,
+    ) -> Result<(), AudioCaptureError> {
+        use AudioCaptureError as E;
+
+        tracing::debug!(
+            "Connecting to Mumble server: {}:{}/{}",
+            cfg.host,
+            cfg.port,
+            cfg.channel,
+        );
+        let tcp = TcpStream::connect((cfg.host.as_str(), cfg.port))
+            .await
+            .map_err(E::ConnectionFailed)?;
+        // Mumble servers use self-signed certificates mostly.
+        let tls = native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true)
+            .build()
+            .map_err(E::InitializationFailed)?;
+        let stream = TlsConnector::from(tls)
+            .connect(&cfg.host, tcp)
+            .await
+            .map_err(E::TlsHandshakeFailed)?;
+        AudioCapture::new(cfg, audio).capture(stream).await
+    }
+
+    /// Authenticates on [Mumble] server via the given `stream`, joins the
+    /// configured channel and feeds all the received audio packets into the
+    /// [`AudioMixer`].
+    ///
+    /// # Errors
+    ///
+    /// See [`AudioCaptureError`] for details.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    pub async fn capture<S>(
+        mut self,
+        stream: S,
+    ) -> Result<(), AudioCaptureError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        use AudioCaptureError as E;
+
+        let (rx, mut tx) = io::split(stream);
+
+        send(
+            &mut tx,
+            kind::VERSION,
+            &proto::Version {
+                version_v1: Some(Self::VERSION),
+                release: Some(format!("ephyr {}", env!("CARGO_PKG_VERSION"))),
+                os: Some(std::env::consts::OS.to_owned()),
+            },
+        )
+        .await
+        .map_err(E::ConnectionFailed)?;
+        send(
+            &mut tx,
+            kind::AUTHENTICATE,
+            &proto::Authenticate {
+                username: Some(self.cfg.name.clone()),
+                password: self.cfg.password.clone(),
+                tokens: self.cfg.channel_password.iter().cloned().collect(),
+                opus: Some(true),
+            },
+        )
+        .await
+        .map_err(E::ConnectionFailed)?;
+
+        // Receiving is done via a `Stream`, as reading a message is not
+        // cancellation safe, while pinging interrupts awaiting the next one.
+        let messages = stream::unfold(rx, |mut rx| async move {
+            let msg = recv(&mut rx).await;
+            Some((msg, rx))
+        });
+        pin!(messages);
+        let mut pinger = time::interval(PING_INTERVAL);
+
+        loop {
+            tokio::select! {
+                msg = messages.next() => {
+                    let (kind, payload) = msg
+                        .ok_or(E::UnexpectedFinish)?
+                        .map_err(|e| {
+                            if e.kind() == io::ErrorKind::UnexpectedEof {
+                                E::UnexpectedFinish
+                            } else {
+                                E::ConnectionFailed(e)
+                            }
+                        })?;
+                    self.handle_message(kind, &payload, &mut tx).await?;
+                }
+                _ = pinger.tick() => {
+                    let timestamp = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_secs());
+                    send(
+                        &mut tx,
+                        kind::PING,
+                        &proto::Ping { timestamp: Some(timestamp) },
+                    )
+                    .await
+                    .map_err(E::ConnectionFailed)?;
+                }
+            }
+        }
+    }
+
+    /// Handles the given control message of the given `kind` received from
+    /// [Mumble] server, replying via the given `tx` if required.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    async fn handle_message<W: AsyncWrite + Unpin>(
+        &mut self,
+        kind: u16,
+        payload: &[u8],
+        tx: &mut W,
+    ) -> Result<(), AudioCaptureError> {
+        use AudioCaptureError as E;
+
+        match kind {
+            kind::UDP_TUNNEL => {
+                let Some(packet) = AudioPacket::parse(payload) else {
+                    return Ok(());
+                };
+                if packet.data.is_empty() {
+                    return Ok(());
+                }
+                if let Err(e) = self
+                    .audio
+                    .lock()
+                    .unwrap()
+                    .handle_packet(packet.session, packet.data)
+                {
+                    tracing::warn!("Drop audio packet from Mumble server: {e}");
+                }
+            }
+
+            kind::REJECT => {
+                let msg = proto::Reject::decode(payload)?;
+                return Err(E::Rejected {
+                    kind: msg.kind.map_or(RejectKind::Other, RejectKind::from),
+                    reason: msg.reason.unwrap_or_default(),
+                });
+            }
+
+            kind::CHANNEL_STATE => {
+                let msg = proto::ChannelState::decode(payload)?;
+                if let Some(id) = msg.channel_id {
+                    let channel = self.channels.entry(id).or_default();
+                    if msg.parent.is_some() {
+                        channel.parent = msg.parent;
+                    }
+                    if let Some(name) = msg.name {
+                        channel.name = name;
+                    }
+                }
+            }
+
+            kind::CHANNEL_REMOVE => {
+                let msg = proto::ChannelRemove::decode(payload)?;
+                if let Some(id) = msg.channel_id {
+                    drop(self.channels.remove(&id));
+                }
+            }
+
+            kind::SERVER_SYNC => {
+                let msg = proto::ServerSync::decode(payload)?;
+                self.session = msg.session;
+                if !self.cfg.channel.is_empty() {
+                    let channel_id = self.find_channel().ok_or_else(|| {
+                        E::ChannelNotFound(self.cfg.channel.clone())
+                    })?;
+                    send(
+                        tx,
+                        kind::USER_STATE,
+                        &proto::UserState {
+                            session: self.session,
+                            channel_id: Some(channel_id),
+                        },
+                    )
+                    .await
+                    .map_err(E::ConnectionFailed)?;
+                }
+                tracing::debug!("Joined Mumble channel: {}", self.cfg.channel);
+            }
+
+            kind::USER_REMOVE => {
+                let msg = proto::UserRemove::decode(payload)?;
+                if msg.session.is_some() && msg.session == self.session {
+                    return Err(E::Kicked(msg.reason.unwrap_or_default()));
+                }
+                if let Some(session) = msg.session {
+                    self.audio.lock().unwrap().remove(session);
+                }
+            }
+
+            kind::PERMISSION_DENIED => {
+                let msg = proto::PermissionDenied::decode(payload)?;
+                let reason = msg.reason.unwrap_or_default();
+                match msg.kind {
+                    Some(proto::DENY_PERMISSION)
+                        if msg.permission == Some(proto::PERMISSION_ENTER) =>
+                    {
+                        return Err(E::ChannelAccessDenied(
+                            self.cfg.channel.clone(),
+                        ));
+                    }
+                    Some(proto::DENY_CHANNEL_FULL) => {
+                        return Err(E::ChannelFull(self.cfg.channel.clone()));
+                    }
+                    _ => {
+                        tracing::warn!("Mumble server denied action: {reason}");
+                    }
+                }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Looks up the ID of the configured channel by its path, starting from
+    /// the root channel.
+    fn find_channel(&self) -> Option<u32> {
+        self.cfg.channel.split('/').try_fold(
+            Channel::ROOT_ID,
+            |parent, name| {
+                self.channels.iter().find_map(|(id, c)| {
+                    (*id != Channel::ROOT_ID
+                        && c.parent == Some(parent)
+                        && c.name == name)
+                        .then_some(*id)
+                })
+            },
+        )
+    }
+}
+
+/// Channel of a [Mumble] server.
+///
+/// [Mumble]: https://www.mumble.info
+#[derive(Clone, Debug, Default)]
+struct Channel {
+    /// ID of the parent channel, if any.
+    parent: Option<u32>,
+
+    /// Name of this [`Channel`].
+    name: String,
+}
+
+impl Channel {
+    /// ID of the root [`Channel`] of a [Mumble] server.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    const ROOT_ID: u32 = 0;
+}
+
+/// Audio packet received from [Mumble] server in the legacy format.
+///
+/// [Mumble]: https://www.mumble.info
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct AudioPacket<'a> {
+    /// Session of the channel member sent this [`AudioPacket`].
+    session: Session,
+
+    /// Sequence number of this [`AudioPacket`].
+    sequence: u64,
+
+    /// [Opus] encoded audio data.
+    ///
+    /// [Opus]: https://opus-codec.org
+    data: &'a [u8],
+
+    /// Indicator whether this [`AudioPacket`] is the last one in the
+    /// transmission.
+    is_last: bool,
+}
+
+impl<'a> AudioPacket<'a> {
+    /// Type of [Opus] encoded [`AudioPacket`]s.
+    ///
+    /// [Opus]: https://opus-codec.org
+    const OPUS: u8 = 4;
+
+    /// Parses an [`AudioPacket`] out of the given bytes.
+    ///
+    /// Returns [`None`] if the bytes are malformed or don't represent an
+    /// [Opus] encoded [`AudioPacket`].
+    ///
+    /// [Opus]: https://opus-codec.org
+    fn parse(mut bytes: &'a [u8]) -> Option<Self> {
+        let (&header, rest) = bytes.split_first()?;
+        if header >> 5 != Self::OPUS {
+            return None;
+        }
+        bytes = rest;
+
+        let session = Session::try_from(read_varint(&mut bytes)?).ok()?;
+        let sequence = read_varint(&mut bytes)?;
+        let size = read_varint(&mut bytes)?;
+        let data = bytes.get(..usize::try_from(size & 0x1FFF).ok()?)?;
+        Some(Self {
+            session,
+            sequence,
+            data,
+            is_last: size & 0x2000 != 0,
+        })
+    }
+}
+
+/// Reads a [Mumble] variable-length integer from the given `bytes`, advancing
+/// them.
+///
+/// Returns [`None`] if the `bytes` are malformed or represent a negative
+/// integer, which is never used in [`AudioPacket`]s.
+///
+/// [Mumble]: https://www.mumble.info
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    fn take(bytes: &mut &[u8], n: usize) -> Option<u64> {
+        let taken = bytes.get(..n)?;
+        *bytes = &bytes[n..];
+        Some(taken.iter().fold(0, |v, b| v << 8 | u64::from(*b)))
+    }
+
+    let (&first, rest) = bytes.split_first()?;
+    *bytes = rest;
+    let b = u64::from(first);
+    Some(match first {
+        0x00..=0x7F => b,
+        0x80..=0xBF => (b & 0x3F) << 8 | take(bytes, 1)?,
+        0xC0..=0xDF => (b & 0x1F) << 16 | take(bytes, 2)?,
+        0xE0..=0xEF => (b & 0x0F) << 24 | take(bytes, 3)?,
+        0xF0..=0xF3 => take(bytes, 4)?,
+        0xF4..=0xF7 => take(bytes, 8)?,
+        _ => return None,
+    })
+}
+
+/// Reads a single control message from [Mumble] server, returning its kind
+/// and payload.
+///
+/// [Mumble]: https://www.mumble.info
+async fn recv<R: AsyncRead + Unpin>(rx: &mut R) -> io::Result<(u16, Vec<u8>)> {
+    let kind = rx.read_u16().await?;
+    let size = usize::try_from(rx.read_u32().await?)
+        .ok()
+        .filter(|s| *s <= MAX_MESSAGE_SIZE)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Too large message received from Mumble server",
+            )
+        })?;
+    let mut payload = vec![0; size];
+    _ = rx.read_exact(&mut payload).await?;
+    Ok((kind, payload))
+}
+
+/// Sends the given control message of the given `kind` to [Mumble] server.
+///
+/// [Mumble]: https://www.mumble.info
+async fn send<W, M>(tx: &mut W, kind: u16, msg: &M) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    M: prost::Message,
+{
+    let payload = msg.encode_to_vec();
+    let size = u32::try_from(payload.len())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut frame = Vec::with_capacity(6 + payload.len());
+    frame.extend_from_slice(&kind.to_be_bytes());
+    frame.extend_from_slice(&size.to_be_bytes());
+    frame.extend_from_slice(&payload);
+    tx.write_all(&frame).await?;
+    tx.flush().await
+}
+
+/// Kinds of [Mumble] control messages used by an [`AudioCapture`].
+///
+/// [Mumble]: https://www.mumble.info
+mod kind {
+    pub(super) const VERSION: u16 = 0;
+    pub(super) const UDP_TUNNEL: u16 = 1;
+    pub(super) const AUTHENTICATE: u16 = 2;
+    pub(super) const PING: u16 = 3;
+    pub(super) const REJECT: u16 = 4;
+    pub(super) const SERVER_SYNC: u16 = 5;
+    pub(super) const CHANNEL_REMOVE: u16 = 6;
+    pub(super) const CHANNEL_STATE: u16 = 7;
+    pub(super) const USER_REMOVE: u16 = 8;
+    pub(super) const USER_STATE: u16 = 9;
+    pub(super) const PERMISSION_DENIED: u16 = 12;
+}
+
+/// [Protocol Buffers] definitions of [Mumble] control messages, containing
+/// only the fields used by an [`AudioCapture`].
+///
+/// [Mumble]: https://www.mumble.info
+/// [Protocol Buffers]: https://protobuf.dev
+mod proto {
+    #![allow(clippy::derive_partial_eq_without_eq)]
+
+    /// `PermissionDenied.type` of a denied permission.
+    pub(super) const DENY_PERMISSION: i32 = 1;
+
+    /// `PermissionDenied.type` of a full channel.
+    pub(super) const DENY_CHANNEL_FULL: i32 = 9;
+
+    /// `PermissionDenied.permission` of entering a channel.
+    pub(super) const PERMISSION_ENTER: u32 = 0x4;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct Version {
+        #[prost(uint32, optional, tag = "1")]
+        pub(super) version_v1: Option<u32>,
+        #[prost(string, optional, tag = "2")]
+        pub(super) release: Option<String>,
+        #[prost(string, optional, tag = "3")]
+        pub(super) os: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct Authenticate {
+        #[prost(string, optional, tag = "1")]
+        pub(super) username: Option<String>,
+        #[prost(string, optional, tag = "2")]
+        pub(super) password: Option<String>,
+        #[prost(string, repeated, tag = "3")]
+        pub(super) tokens: Vec<String>,
+        #[prost(bool, optional, tag = "5")]
+        pub(super) opus: Option<bool>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct Ping {
+        #[prost(uint64, optional, tag = "1")]
+        pub(super) timestamp: Option<u64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct Reject {
+        #[prost(int32, optional, tag = "1")]
+        pub(super) kind: Option<i32>,
+        #[prost(string, optional, tag = "2")]
+        pub(super) reason: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ServerSync {
+        #[prost(uint32, optional, tag = "1")]
+        pub(super) session: Option<u32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ChannelRemove {
+        #[prost(uint32, optional, tag = "1")]
+        pub(super) channel_id: Option<u32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ChannelState {
+        #[prost(uint32, optional, tag = "1")]
+        pub(super) channel_id: Option<u32>,
+        #[prost(uint32, optional, tag = "2")]
+        pub(super) parent: Option<u32>,
+        #[prost(string, optional, tag = "3")]
+        pub(super) name: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct UserRemove {
+        #[prost(uint32, optional, tag = "1")]
+        pub(super) session: Option<u32>,
+        #[prost(string, optional, tag = "3")]
+        pub(super) reason: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct UserState {
+        #[prost(uint32, optional, tag = "1")]
+        pub(super) session: Option<u32>,
+        #[prost(uint32, optional, tag = "5")]
+        pub(super) channel_id: Option<u32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct PermissionDenied {
+        #[prost(uint32, optional, tag = "1")]
+        pub(super) permission: Option<u32>,
+        #[prost(string, optional, tag = "4")]
+        pub(super) reason: Option<String>,
+        #[prost(int32, optional, tag = "5")]
+        pub(super) kind: Option<i32>,
+    }
+}
+
+/// Reason of [Mumble] server rejecting a connection.
+///
+/// [Mumble]: https://www.mumble.info
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum RejectKind {
+    /// Version of the client is not supported.
+    #[display(fmt = "wrong version")]
+    WrongVersion,
+
+    /// Name of the client is not valid.
+    #[display(fmt = "invalid username")]
+    InvalidUsername,
+
+    /// Password of the registered user is wrong.
+    #[display(fmt = "wrong user password")]
+    WrongUserPassword,
+
+    /// Server password is wrong.
+    #[display(fmt = "wrong server password")]
+    WrongServerPassword,
+
+    /// Name of the client is used by another one already.
+    #[display(fmt = "username in use")]
+    UsernameInUse,
+
+    /// Server is full.
+    #[display(fmt = "server full")]
+    ServerFull,
+
+    /// Server requires a client certificate.
+    #[display(fmt = "no certificate")]
+    NoCertificate,
+
+    /// Server authenticator failed.
+    #[display(fmt = "authenticator fail")]
+    AuthenticatorFail,
+
+    /// Other reason.
+    #[display(fmt = "other")]
+    Other,
+}
+
+impl From<i32> for RejectKind {
+    fn from(kind: i32) -> Self {
+        match kind {
+            1 => Self::WrongVersion,
+            2 => Self::InvalidUsername,
+            3 => Self::WrongUserPassword,
+            4 => Self::WrongServerPassword,
+            5 => Self::UsernameInUse,
+            6 => Self::ServerFull,
+            7 => Self::NoCertificate,
+            8 => Self::AuthenticatorFail,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Possible errors of capturing audio from [Mumble] server.
+///
+/// [Mumble]: https://www.mumble.info
+#[derive(Debug, Display, Error)]
+pub enum AudioCaptureError {
+    /// Initializing TLS connector failed.
+    #[display(fmt = "Initializing TLS connector failed: {_0}")]
+    InitializationFailed(native_tls::Error),
+
+    /// Establishing connection with [Mumble] server failed.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[display(fmt = "Connecting to Mumble server failed: {_0}")]
+    ConnectionFailed(io::Error),
+
+    /// TLS handshake with [Mumble] server failed.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[display(fmt = "TLS handshake with Mumble server failed: {_0}")]
+    TlsHandshakeFailed(native_tls::Error),
+
+    /// [Mumble] server rejected the connection.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[display(fmt = "Mumble server rejected connection ({kind}): {reason}")]
+    Rejected {
+        /// Reason of the rejection.
+        kind: RejectKind,
+
+        /// Human-readable description of the rejection.
+        reason: String,
+    },
+
+    /// Channel to join doesn't exist on [Mumble] server.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[display(fmt = "Mumble channel `{_0}` doesn't exist")]
+    ChannelNotFound(#[error(not(source))] String),
+
+    /// [Mumble] server denied to enter the channel (because of a missing or
+    /// wrong channel password, for example).
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[display(fmt = "Mumble server denied to enter channel `{_0}`")]
+    ChannelAccessDenied(#[error(not(source))] String),
+
+    /// Channel to join is full.
+    #[display(fmt = "Mumble channel `{_0}` is full")]
+    ChannelFull(#[error(not(source))] String),
+
+    /// [Mumble] server kicked the client.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[display(fmt = "Kicked from Mumble server: {_0}")]
+    Kicked(#[error(not(source))] String),
+
+    /// Malformed control message received from [Mumble] server.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[display(fmt = "Malformed message received from Mumble server: {_0}")]
+    MalformedMessage(prost::DecodeError),
+
+    /// Receiving messages from [Mumble] server finished unexpectedly.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    #[display(
+        fmt = "Receiving messages from Mumble server finished unexpectedly"
+    )]
+    UnexpectedFinish,
+}
+
+impl From<prost::DecodeError> for AudioCaptureError {
+    fn from(e: prost::DecodeError) -> Self {
+        Self::MalformedMessage(e)
+    }
+}
+
+impl AudioCaptureError {
+    /// Wraps this [`AudioCaptureError`] into a [`backoff::Error`] carefully
+    /// distinguishing transient and permanent errors.
+    #[must_use]
+    pub fn into_backoff(self) -> backoff::Error<Self> {
+        use RejectKind as R;
+
+        let is_permanent = match &self {
+            Self::InitializationFailed(_)
+            | Self::ChannelNotFound(_)
+            | Self::ChannelAccessDenied(_) => true,
+            Self::Rejected { kind, .. } => !matches!(
+                kind,
+                R::UsernameInUse | R::ServerFull | R::AuthenticatorFail,
+            ),
+            Self::ConnectionFailed(_)
+            | Self::TlsHandshakeFailed(_)
+            | Self::ChannelFull(_)
+            | Self::Kicked(_)
+            | Self::MalformedMessage(_)
+            | Self::UnexpectedFinish => false,
+        };
+        if is_permanent {
+            backoff::Error::Permanent(self)
+        } else {
+            backoff::Error::transient(self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod audio_packet_spec {
+    use super::{read_varint, AudioPacket};
+
+    #[test]
+    fn reads_varints() {
+        for (bytes, expected) in [
+            (&[0x07][..], 7),
+            (&[0x81, 0x2C], 300),
+            (&[0xC1, 0x00, 0x00], 0x1_0000),
+            (&[0xE1, 0x00, 0x00, 0x00], 0x100_0000),
+            (&[0xF0, 0x12, 0x34, 0x56, 0x78], 0x1234_5678),
+        ] {
+            let mut bytes = bytes;
+            assert_eq!(read_varint(&mut bytes), Some(expected));
+            assert!(bytes.is_empty());
+        }
+        assert_eq!(read_varint(&mut &[0x81][..]), None);
+        assert_eq!(read_varint(&mut &[0xFC][..]), None);
+    }
+
+    #[test]
+    fn parses_opus_packet() {
+        let bytes = [0x80, 0x07, 0x81, 0x2C, 0xA0, 0x03, 1, 2, 3, 0xFF];
+
+        assert_eq!(
+            AudioPacket::parse(&bytes),
+            Some(AudioPacket {
+                session: 7,
+                sequence: 300,
+                data: &[1, 2, 3],
+                is_last: true,
+            }),
+        );
+    }
+
+    #[test]
+    fn skips_non_opus_packet() {
+        // Ping packet.
+        assert_eq!(AudioPacket::parse(&[0x20, 0x07]), None);
+        // CELT Alpha packet.
+        assert_eq!(AudioPacket::parse(&[0x00, 0x07, 0x01, 0x00]), None);
+    }
+}
+
+#[cfg(test)]
+mod audio_capture_spec {
+    use std::sync::{Arc, Mutex};
+
+    use prost::Message as _;
+    use tokio::io::{self, DuplexStream};
+
+    use super::{
+        kind, proto, recv, send, AudioCapture, AudioCaptureError, Config,
+        RejectKind,
+    };
+
+    /// Stand-in of a Mumble server, talking to an [`AudioCapture`] via an
+    /// in-memory stream.
+    struct StandIn(DuplexStream);
+
+    impl StandIn {
+        /// Spawns an [`AudioCapture`] talking to a new [`StandIn`].
+        fn spawn(
+            cfg: Config,
+        ) -> (Self, tokio::task::JoinHandle<Result<(), AudioCaptureError>>)
+        {
+            let (client, server) = io::duplex(64 * 1024);
+            let capture = AudioCapture::new(
+                cfg,
+                Arc::new(Mutex::new(Default::default())),
+            );
+            (Self(server), tokio::spawn(capture.capture(client)))
+        }
+
+        /// Receives the next non-ping message sent by the [`AudioCapture`].
+        async fn recv<M: prost::Message + Default>(&mut self, kind: u16) -> M {
+            loop {
+                let (k, payload) = recv(&mut self.0).await.unwrap();
+                if k == kind::PING {
+                    continue;
+                }
+                assert_eq!(k, kind, "unexpected message kind");
+                return M::decode(&payload[..]).unwrap();
+            }
+        }
+
+        /// Sends the given message to the [`AudioCapture`].
+        async fn send<M: prost::Message>(&mut self, kind: u16, msg: &M) {
+            send(&mut self.0, kind, msg).await.unwrap();
+        }
+
+        /// Sends the given channel to the [`AudioCapture`].
+        async fn channel(&mut self, id: u32, parent: Option<u32>, name: &str) {
+            self.send(
+                kind::CHANNEL_STATE,
+                &proto::ChannelState {
+                    channel_id: Some(id),
+                    parent,
+                    name: Some(name.to_owned()),
+                },
+            )
+            .await;
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            host: "localhost".to_owned(),
+            port: super::DEFAULT_PORT,
+            channel: "Event/Booth EN".to_owned(),
+            name: "🤖 Ephyr".to_owned(),
+            password: Some("server secret".to_owned()),
+            channel_password: Some("booth secret".to_owned()),
+        }
+    }
+
+    #[tokio::test]
+    async fn joins_nested_channel() {
+        let (mut server, capture) = StandIn::spawn(config());
+
+        let version: proto::Version = server.recv(kind::VERSION).await;
+        assert_eq!(version.version_v1, Some(AudioCapture::VERSION));
+        let auth: proto::Authenticate = server.recv(kind::AUTHENTICATE).await;
+        assert_eq!(auth.username.as_deref(), Some("🤖 Ephyr"));
+        assert_eq!(auth.password.as_deref(), Some("server secret"));
+        assert_eq!(auth.tokens, ["booth secret"]);
+        assert_eq!(auth.opus, Some(true));
+
+        server.channel(0, None, "Root").await;
+        server.channel(1, Some(0), "Event").await;
+        server.channel(2, Some(0), "Booth EN").await;
+        server.channel(3, Some(1), "Booth EN").await;
+        server
+            .send(kind::SERVER_SYNC, &proto::ServerSync { session: Some(7) })
+            .await;
+
+        let join: proto::UserState = server.recv(kind::USER_STATE).await;
+        assert_eq!(join.session, Some(7));
+        assert_eq!(join.channel_id, Some(3));
+
+        drop(server);
+        assert!(matches!(
+            capture.await.unwrap(),
+            Err(AudioCaptureError::UnexpectedFinish),
+        ));
+    }
+
+    #[tokio::test]
+    async fn fails_permanently_on_wrong_password() {
+        let (mut server, capture) = StandIn::spawn(config());
+
+        let _: proto::Version = server.recv(kind::VERSION).await;
+        let _: proto::Authenticate = server.recv(kind::AUTHENTICATE).await;
+        server
+            .send(
+                kind::REJECT,
+                &proto::Reject {
+                    kind: Some(4),
+                    reason: Some("Wrong server password".to_owned()),
+                },
+            )
+            .await;
+
+        let err = capture.await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            AudioCaptureError::Rejected {
+                kind: RejectKind::WrongServerPassword,
+                ..
+            },
+        ));
+        assert!(matches!(err.into_backoff(), backoff::Error::Permanent(_)));
+    }
+
+    #[tokio::test]
+    async fn fails_permanently_on_missing_channel() {
+        let (mut server, capture) = StandIn::spawn(config());
+
+        let _: proto::Version = server.recv(kind::VERSION).await;
+        let _: proto::Authenticate = server.recv(kind::AUTHENTICATE).await;
+        server.channel(0, None, "Root").await;
+        server.channel(1, Some(0), "Event").await;
+        server
+            .send(kind::SERVER_SYNC, &proto::ServerSync { session: Some(7) })
+            .await;
+
+        let err = capture.await.unwrap().unwrap_err();
+        assert!(matches!(err, AudioCaptureError::ChannelNotFound(_)));
+        assert!(matches!(err.into_backoff(), backoff::Error::Permanent(_)));
+    }
+}
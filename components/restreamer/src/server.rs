@@ -4,7 +4,11 @@ pub mod client;
 pub mod periodic_tasks;
 pub mod srs_callback;
 
-use std::{net::IpAddr, time::Duration};
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use ephyr_log::{tracing, TelemetryConfig};
 use futures::future;
@@ -55,9 +59,25 @@ pub async fn run(mut cfg: Opts) -> Result<(), Failure> {
             tracing::error!("Failed to resolve FFmpeg binary path: {e}");
         })?;
 
-    let state = State::try_new(&cfg.state_path).await.map_err(|e| {
-        tracing::error!("Failed to initialize server state: {e}");
-    })?;
+    let state = State::try_new(&cfg.state_path, cfg.state_backups)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to initialize server state: {e}");
+        })?;
+
+    // DVR files are served by `server::client` behind its authorization only,
+    // so they should not be located inside SRS HTTP directory.
+    let dvr_dir = async {
+        fs::create_dir_all(&cfg.dvr_dir).await?;
+        fs::canonicalize(&cfg.dvr_dir).await
+    }
+    .await
+    .map_err(|e| tracing::error!("Failed to resolve DVR directory: {e}"))?;
+    dvr::Storage { root_path: dvr_dir }
+        .set_global()
+        .map_err(|e| {
+            tracing::error!("Failed to initialize DVR storage: {e}");
+        })?;
 
     let srs = srs::Server::try_new(
         &cfg.srs_path,
@@ -79,15 +99,30 @@ pub async fn run(mut cfg: Opts) -> Result<(), Failure> {
         },
     );
 
-    let mut restreamers = ffmpeg::RestreamersPool::new(
+    let restreamers = Arc::new(Mutex::new(ffmpeg::RestreamersPool::new(
         ffmpeg_path,
         state.clone(),
         cfg.file_root.clone(),
-    );
+    )));
+    let pool = Arc::clone(&restreamers);
     State::on_change("spawn_restreamers", &state.restreams, move |restreams| {
-        restreamers.apply(&restreams);
+        if let Ok(mut pool) = pool.lock() {
+            pool.apply(&restreams);
+        }
         future::ready(())
     });
+    // DVR recordings are suspended and resumed without touching `Restream`s.
+    let state_for_dvr = state.clone();
+    State::on_change(
+        "suspend_dvr_restreamers",
+        &state.dvr_space_low,
+        move |_| {
+            if let Ok(mut pool) = restreamers.lock() {
+                pool.apply(&state_for_dvr.restreams.get_cloned());
+            }
+            future::ready(())
+        },
+    );
     let file_manager = FileManager::new(&cfg, state.clone());
     file_manager.check_files();
     State::on_change("handle_fm_commands", &state.file_commands, move |_| {
@@ -111,12 +146,16 @@ pub async fn run(mut cfg: Opts) -> Result<(), Failure> {
         },
     );
 
-    future::try_join3(
+    let res = future::try_join3(
         self::client::run(&cfg, state.clone()),
         self::periodic_tasks::run(state.clone()),
-        self::srs_callback::run(&cfg, state),
+        self::srs_callback::run(&cfg, state.clone()),
     )
-    .await?;
+    .await;
+
+    // Persist the changes still being debounced, so they're not lost.
+    state.flush().await;
+    res?;
 
     drop(srs);
     // Wait for all the async `Drop`s to proceed well.
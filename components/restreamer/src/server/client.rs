@@ -1,18 +1,27 @@
 //! Client HTTP server responding to client requests.
-use std::time::Duration;
+use std::{io, path::Path, str::FromStr as _, time::Duration};
 
 use actix_service::Service as _;
 use actix_web::{
-    dev::ServiceRequest, get, route, web, App, Error, HttpRequest,
-    HttpResponse, HttpServer,
+    dev::ServiceRequest,
+    get,
+    http::{
+        header::{
+            self, ContentDisposition, ContentRange, ContentRangeSpec,
+            DispositionParam, DispositionType, Range,
+        },
+        StatusCode,
+    },
+    route, web, App, Error, HttpRequest, HttpResponse, HttpServer,
 };
 use actix_web_httpauth::extractors::{
     basic::{self, BasicAuth},
     AuthExtractor as _, AuthExtractorConfig, AuthenticationError,
 };
 use actix_web_static_files::ResourceFiles;
+use chrono::{DateTime, Utc};
 use ephyr_log::{tracing, tracing_actix_web::TracingLogger};
-use futures::{future, FutureExt as _};
+use futures::{future, stream, FutureExt as _, StreamExt as _};
 use juniper::http::playground::playground_source;
 use juniper_actix::{
     graphql_handler, subscriptions::ws_handler as subscriptions_handler,
@@ -22,10 +31,15 @@ use juniper_graphql_ws::ConnectionConfig;
 use crate::{
     api,
     cli::{Failure, Opts},
-    State,
+    dvr, State,
 };
 use ephyr_log::tracing::instrument;
 use std::fmt;
+use tokio::{
+    fs,
+    io::{AsyncReadExt as _, AsyncSeekExt as _},
+};
+use uuid::Uuid;
 
 const MIX_ROUTE: &str = "/mix";
 const MIX_ROUTE_API: &str = "/api-mix";
@@ -111,7 +125,9 @@ pub async fn run(cfg: &Opts, state: State) -> Result<(), Failure> {
             .service(graphql_client)
             .service(graphql_mix)
             .service(graphql_statistics)
-            .service(graphql_dashboard);
+            .service(graphql_dashboard)
+            .service(dvr_recording)
+            .service(dvr_file);
         if in_debug_mode {
             app = app
                 .service(playground_client)
@@ -245,6 +261,136 @@ async fn graphql(
     }
 }
 
+/// Endpoint serving a [ZIP] archive of all the files of a DVR recording
+/// session, started by the specified `Output` (or `Input`) at the moment
+/// specified by the `startedAt` query parameter (as returned by
+/// `Query.dvrRecordings` or `Query.inputDvrRecordings`).
+///
+/// [ZIP]: https://en.wikipedia.org/wiki/ZIP_(file_format)
+#[get("/dvr/{id}/recording.zip")]
+async fn dvr_recording(
+    id: web::Path<Uuid>,
+    query: web::Query<DvrRecordingQuery>,
+) -> HttpResponse {
+    let Some(zip) = dvr::Storage::global()
+        .zip_recording(*id, query.started_at)
+        .await
+    else {
+        return HttpResponse::NotFound().finish();
+    };
+    HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header(attachment(&format!(
+            "{}_{}.zip",
+            id,
+            query.started_at.timestamp(),
+        )))
+        .streaming(zip.map(|chunk| chunk.map(web::Bytes::from)))
+}
+
+/// Query parameters of the [`dvr_recording`] endpoint.
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DvrRecordingQuery {
+    /// Moment when the requested recording session has started.
+    started_at: DateTime<Utc>,
+}
+
+/// Endpoint serving a recorded DVR file of the specified `Output` (or
+/// `Input`).
+///
+/// Supports [byte-range requests][1], so the file may be downloaded partially
+/// or played right in a browser.
+///
+/// [1]: https://developer.mozilla.org/docs/Web/HTTP/Range_requests
+#[get("/dvr/{id}/{file}")]
+async fn dvr_file(
+    req: HttpRequest,
+    path: web::Path<(Uuid, String)>,
+) -> Result<HttpResponse, Error> {
+    let (id, name) = path.into_inner();
+    let Some(file_path) = dvr::Storage::global().file_path(id, &name) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let mut file = match fs::File::open(&file_path).await {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(HttpResponse::NotFound().finish());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let len = file.metadata().await?.len();
+
+    let mut resp = HttpResponse::Ok();
+    _ = resp
+        .content_type(dvr_content_type(&file_path))
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .insert_header(attachment(&name));
+
+    let range = req
+        .headers()
+        .get(header::RANGE)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| Range::from_str(h).ok());
+    let (start, end) = match range {
+        // Multiple ranges are not supported, so the whole file is served.
+        Some(Range::Bytes(specs)) if specs.len() == 1 => {
+            let Some((start, end)) = specs[0].to_satisfiable_range(len) else {
+                return Ok(HttpResponse::RangeNotSatisfiable()
+                    .insert_header(ContentRange(ContentRangeSpec::Bytes {
+                        range: None,
+                        instance_length: Some(len),
+                    }))
+                    .finish());
+            };
+            _ = resp.status(StatusCode::PARTIAL_CONTENT).insert_header(
+                ContentRange(ContentRangeSpec::Bytes {
+                    range: Some((start, end)),
+                    instance_length: Some(len),
+                }),
+            );
+            (start, end + 1)
+        }
+        _ => (0, len),
+    };
+    if start > 0 {
+        _ = file.seek(io::SeekFrom::Start(start)).await?;
+    }
+
+    let chunks =
+        stream::try_unfold(file.take(end - start), |mut file| async move {
+            let mut chunk = vec![0; 64 * 1024];
+            let n = file.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(None);
+            }
+            chunk.truncate(n);
+            Ok::<_, io::Error>(Some((web::Bytes::from(chunk), file)))
+        });
+    Ok(resp.no_chunking(end - start).streaming(chunks))
+}
+
+/// Returns a `Content-Disposition` header making a browser to download the
+/// response as a file with the given name.
+fn attachment(filename: &str) -> ContentDisposition {
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(filename.to_owned())],
+    }
+}
+
+/// Returns a MIME type of the DVR file located by the given `path`.
+fn dvr_content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("flv") => "video/x-flv",
+        Some("mkv") => "video/x-matroska",
+        Some("mp3") => "audio/mpeg",
+        Some("mp4") => "video/mp4",
+        Some("wav") => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
+
 /// Endpoint serving [GraphQL Playground][1] for exploring
 /// [`api::graphql::client`].
 ///
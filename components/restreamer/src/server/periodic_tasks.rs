@@ -1,13 +1,18 @@
 //! Module for running periodic tasks
+use chrono::Utc;
 use std::future::Future;
 use systemstat::{Platform, System};
 use tokio::time;
 
 use crate::{
     cli::Failure,
-    display_panic,
+    console_logger::{ConsoleLogger, ConsoleMessageKind, ConsoleMessageSource},
+    display_panic, dvr,
     file_manager::{FileCommand, FileState},
-    state::{InputEndpointKind, InputSrc, ServerInfo, Status},
+    state::{
+        InputEndpointKind, InputSrc, ScheduleAction, ScheduledAction,
+        ServerInfo, Status,
+    },
     types::UNumber,
     State,
 };
@@ -91,6 +96,32 @@ pub async fn run(state: State) -> Result<(), Failure> {
         |state| async move { start_pending_downloads(state) },
     );
 
+    // Scheduled actions missed while the server was down are performed on
+    // startup, since the last persisted check.
+    perform_actions(&state, state.check_scheduled_actions(Utc::now()));
+    run_periodic(
+        state.clone(),
+        time::Duration::from_secs(1),
+        |state| async move { perform_scheduled_actions(state) },
+    );
+
+    run_periodic(
+        state.clone(),
+        time::Duration::from_secs(10),
+        |state| async move { watch_dvr_free_space(state) },
+    );
+
+    run_periodic(
+        state.clone(),
+        time::Duration::from_secs(60),
+        |state| async move {
+            let retention =
+                state.settings.lock_ref().dvr_retention.unwrap_or_default();
+            dvr::Storage::global().enforce_retention(&retention).await;
+            Ok(())
+        },
+    );
+
     Ok(())
 }
 
@@ -222,6 +253,93 @@ fn sync_stream_info(state: State) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Enables and disables `Restream`s and `Output`s according to their
+/// `Schedule`s, performing actions happened since their last check.
+#[allow(clippy::unnecessary_wraps)]
+#[allow(clippy::needless_pass_by_value)]
+fn perform_scheduled_actions(state: State) -> Result<(), anyhow::Error> {
+    perform_actions(&state, state.check_scheduled_actions(Utc::now()));
+    Ok(())
+}
+
+/// Performs the given [`ScheduledAction`]s on `Restream`s and `Output`s of the
+/// given [`State`].
+fn perform_actions(state: &State, actions: Vec<ScheduledAction>) {
+    for a in actions {
+        let performed = match (a.action, a.output_id) {
+            (ScheduleAction::Enable, Some(id)) => {
+                state.enable_output(id, a.restream_id)
+            }
+            (ScheduleAction::Disable, Some(id)) => {
+                state.disable_output(id, a.restream_id)
+            }
+            (ScheduleAction::Enable, None) => {
+                state.enable_restream(a.restream_id)
+            }
+            (ScheduleAction::Disable, None) => {
+                state.disable_restream(a.restream_id)
+            }
+        };
+        tracing::info!(
+            "Performed scheduled {:?} action of Schedule {} on Restream {}{}: \
+             {}",
+            a.action,
+            a.schedule_id,
+            a.restream_id,
+            a.output_id
+                .map(|id| format!(" Output {id}"))
+                .unwrap_or_default(),
+            match performed {
+                Some(true) => "done",
+                Some(false) => "already in place",
+                None => "target not found",
+            },
+        );
+    }
+}
+
+/// Suspends all the recordings of `Output`s and `Input`s once free space of
+/// the DVR disk goes below the `DvrRetention.minFreeSpace`, so the disk doesn't
+/// get full, and resumes them once enough space is freed.
+///
+/// Recordings are suspended in runtime only, so the persisted configuration
+/// remains untouched.
+#[allow(clippy::unnecessary_wraps)]
+#[allow(clippy::needless_pass_by_value)]
+fn watch_dvr_free_space(state: State) -> Result<(), anyhow::Error> {
+    let retention = state.settings.lock_ref().dvr_retention.unwrap_or_default();
+    let min_free = retention.min_free_bytes();
+    let is_low = min_free > 0
+        && dvr::Storage::global()
+            .free_space()
+            .map_or(false, |free| free < min_free);
+    if state.dvr_space_low.get() == is_low {
+        return Ok(());
+    }
+    state.dvr_space_low.set(is_low);
+
+    let (message, kind) = if is_low {
+        let message = format!(
+            "Free disk space for DVR is below {} GB, recording of all Outputs \
+             and Inputs is suspended",
+            retention.min_free_space.0,
+        );
+        tracing::warn!("{message}");
+        (message, ConsoleMessageKind::Warning)
+    } else {
+        let message = "Free disk space for DVR is enough again, recording of \
+                       all Outputs and Inputs is resumed";
+        tracing::info!("{message}");
+        (message.to_owned(), ConsoleMessageKind::Info)
+    };
+    ConsoleLogger::new(state).log_message(
+        message,
+        kind,
+        ConsoleMessageSource::Dvr,
+    );
+    Ok(())
+}
+
 /// Controls the number of simultaneous downloads in queue
 #[allow(clippy::unnecessary_wraps)]
 #[allow(clippy::needless_pass_by_value)]
@@ -366,8 +366,12 @@ fn on_hls(req: &SrsCallbackReq, state: &State) -> Result<(), Error> {
     Ok(())
 }
 
+/// Probes the stream at the given `url` and stores its info into the
+/// [`InputEndpoint`] with the given `id`.
+///
+/// [`InputEndpoint`]: crate::state::InputEndpoint
 #[instrument(skip_all)]
-fn update_stream_info(id: EndpointId, url: String, state: State) {
+pub(crate) fn update_stream_info(id: EndpointId, url: String, state: State) {
     drop(
         tokio::spawn(
             AssertUnwindSafe(
@@ -4,6 +4,7 @@
 //! [`State`]: crate::state::State
 
 pub mod v1;
+pub mod v2;
 
 use derive_more::From;
 use serde::{Deserialize, Serialize};
@@ -17,15 +18,244 @@ use serde::{Deserialize, Serialize};
 pub enum Spec {
     /// Version 1 of this [`Spec`].
     V1(v1::Spec),
+
+    /// Version 2 of this [`Spec`].
+    V2(v2::Spec),
 }
 
 impl Spec {
-    /// Converts this [`Spec`] into a [`v1::Spec`].
+    /// Converts this [`Spec`] into a [`v2::Spec`], migrating it from older
+    /// versions, if required.
     #[inline]
     #[must_use]
-    pub fn into_v1(self) -> v1::Spec {
+    pub fn into_v2(self) -> v2::Spec {
         match self {
-            Self::V1(s) => s,
+            Self::V1(s) => s.into(),
+            Self::V2(s) => s,
+        }
+    }
+}
+
+#[cfg(test)]
+mod migration_spec {
+    use super::{v2, Spec};
+
+    /// Archived [`Spec::V1`] export.
+    const V1: &str = include_str!("fixtures/v1.json");
+
+    /// [`V1`] export as it should look after being migrated to [`Spec::V2`].
+    const V2: &str = include_str!("fixtures/v2.json");
+
+    fn parse_v2(json: &str) -> v2::Spec {
+        match serde_json::from_str(json).expect("failed to parse Spec") {
+            Spec::V2(s) => s,
+            Spec::V1(_) => panic!("expected Spec::V2"),
         }
     }
+
+    #[test]
+    fn migrates_v1_to_v2() {
+        let old: Spec = serde_json::from_str(V1).expect("failed to parse V1");
+        assert!(matches!(old, Spec::V1(_)), "expected Spec::V1");
+
+        assert_eq!(old.into_v2(), parse_v2(V2));
+    }
+
+    #[test]
+    fn migrates_with_playback_encoding() {
+        let spec = serde_json::from_str::<Spec>(V1).unwrap().into_v2();
+
+        assert!(spec.restreams[0].with_playback_encoding);
+        assert!(!spec.restreams[1].with_playback_encoding);
+    }
+
+    #[test]
+    fn migrates_mixin_sidechain() {
+        let spec = serde_json::from_str::<Spec>(V1).unwrap().into_v2();
+        let mixins = &spec.restreams[0].outputs[0].mixins;
+
+        assert_eq!(mixins[0].sidechain, Some(v2::Sidechain::default()));
+        assert_eq!(mixins[1].sidechain, None);
+    }
+
+    #[test]
+    fn roundtrips_v2() {
+        let spec = parse_v2(V2);
+        let json = serde_json::to_string(&Spec::V2(spec.clone())).unwrap();
+
+        assert_eq!(parse_v2(&json), spec);
+    }
+}
+
+#[cfg(test)]
+mod validation_spec {
+    use serde_json::{json, Value};
+
+    use super::v2;
+
+    /// Parses a [`v2::Spec`] with the given `settings` and `restreams`.
+    fn parse(
+        settings: Value,
+        restreams: Value,
+    ) -> Result<v2::Spec, serde_json::Error> {
+        serde_json::from_value(json!({
+            "settings": settings,
+            "restreams": restreams,
+        }))
+    }
+
+    /// Forms a pushed `Restream` with the given `key`, accepting SRT pushes
+    /// via the given `srt` listener.
+    fn srt_restream(key: &str, srt: Value) -> Value {
+        json!({
+            "key": key,
+            "input": {
+                "key": "origin",
+                "endpoints": [{"kind": "rtmp"}, {"kind": "srt", "srt": srt}],
+            },
+            "outputs": [],
+        })
+    }
+
+    /// Forms a pushed `Restream` re-streaming to the given `output`.
+    fn output_restream(output: Value) -> Value {
+        json!({
+            "key": "en",
+            "input": {"key": "origin", "endpoints": [{"kind": "rtmp"}]},
+            "outputs": [output],
+        })
+    }
+
+    #[test]
+    fn rejects_invalid_retry_policy() {
+        let policy = |initial: u16, max: u16| {
+            let policy = json!({"initial_delay": initial, "max_delay": max});
+            json!({ "retry_policy": policy })
+        };
+
+        assert!(parse(policy(1, 30), json!([])).is_ok());
+        assert!(parse(policy(0, 30), json!([])).is_err());
+        assert!(parse(policy(30, 1), json!([])).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_dvr_retention() {
+        let retention = |output: u16, total: u16| {
+            let retention = json!({
+                "max_output_size": output,
+                "max_total_size": total,
+                "min_free_space": 1,
+            });
+            json!({ "dvr_retention": retention })
+        };
+
+        assert!(parse(retention(10, 100), json!([])).is_ok());
+        assert!(parse(retention(0, 100), json!([])).is_err());
+        assert!(parse(retention(100, 10), json!([])).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_srt_listener() {
+        let valid = |srt: Value| {
+            parse(json!({}), json!([srt_restream("en", srt)])).is_ok()
+        };
+
+        assert!(valid(json!({"port": 9000, "stream_id": "en/origin"})));
+        assert!(!valid(json!({"port": 0})));
+        assert!(!valid(json!({"port": 9000, "stream_id": ""})));
+        assert!(!valid(json!({"port": 9000, "passphrase": "short"})));
+    }
+
+    #[test]
+    fn rejects_invalid_sidechain() {
+        let valid = |sidechain: Value| {
+            let mixin = json!({
+                "src": "ts://example.com/live",
+                "sidechain": sidechain,
+            });
+            let output = json!({
+                "dst": "rtmp://example.com/live/en",
+                "mixins": [mixin],
+            });
+            parse(json!({}), json!([output_restream(output)])).is_ok()
+        };
+        let sidechain = |threshold: i32, ratio: u16| {
+            json!({
+                "threshold": threshold,
+                "ratio": ratio,
+                "attack": 200,
+                "release": 1000,
+                "knee": 1,
+                "makeup": 0,
+            })
+        };
+
+        assert!(valid(json!(true)));
+        assert!(valid(sidechain(-30, 8)));
+        assert!(!valid(sidechain(10, 8)));
+        assert!(!valid(sidechain(-30, 0)));
+    }
+
+    #[test]
+    fn rejects_invalid_loudness() {
+        let loudness = |target: Option<i32>, true_peak: i32| {
+            let loudness = json!({
+                "target": target,
+                "range": 7,
+                "true_peak": true_peak,
+            });
+            let output = json!({
+                "dst": "rtmp://example.com/live/en",
+                "loudness": loudness,
+            });
+            parse(json!({}), json!([output_restream(output)]))
+        };
+
+        assert!(loudness(Some(-23), -1).is_ok());
+        assert!(loudness(None, 0).is_ok());
+        assert!(loudness(None, -20).is_err());
+        assert!(loudness(Some(0), -1).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_audio_encoding() {
+        let encoding = |dst: &str, format: &str, bitrate: u16| {
+            let encoding = json!({"format": format, "bitrate": bitrate});
+            let output = json!({"dst": dst, "audio_encoding": encoding});
+            parse(json!({}), json!([output_restream(output)]))
+        };
+
+        assert!(encoding("file:///record.mp3", "mp3", 128).is_ok());
+        assert!(encoding("file:///record.mp3", "mp3", 8).is_err());
+        assert!(encoding("file:///record.mp3", "aac", 128).is_err());
+        assert!(encoding("icecast://example.com/live", "aac", 128).is_ok());
+    }
+
+    #[test]
+    fn moves_icecast_password_out_of_dst() {
+        let spec = parse(
+            json!({}),
+            json!([output_restream(json!({
+                "dst": "icecast://example.com/live.mp3?password=a",
+            }))]),
+        )
+        .unwrap();
+        let output = &spec.restreams[0].outputs[0];
+
+        assert_eq!(output.dst.as_str(), "icecast://example.com/live.mp3");
+        assert_eq!(output.password.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn rejects_duplicate_srt_ports() {
+        let restreams = |port: u16| {
+            json!([
+                srt_restream("en", json!({"port": 9000})),
+                srt_restream("de", json!({ "port": port })),
+            ])
+        };
+
+        assert!(parse(json!({}), restreams(9001)).is_ok());
+        assert!(parse(json!({}), restreams(9000)).is_err());
+    }
 }
@@ -1,12 +1,15 @@
 //! Version 1 of a shareable (exportable and importable) specification of
 //! application's [`State`].
 //!
+//! It's kept for importing previously exported specifications only, which are
+//! migrated into the [`v2::Spec`] on load.
+//!
+//! [`v2::Spec`]: super::v2::Spec
 //! [`State`]: state::State
 use ephyr_serde::is_false;
 use std::collections::HashSet;
 
 use crate::{file_manager::FileId, state, types::UNumber};
-use juniper::GraphQLInputObject;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use url::Url;
 
@@ -156,84 +159,6 @@ pub struct Input {
     pub enabled: bool,
 }
 
-impl Input {
-    /// Creates a file backup [`Input`] out of the given [`FileId`].
-    #[must_use]
-    pub fn new_file_backup(file_id: FileId) -> Self {
-        Self {
-            id: None,
-            key: state::InputKey::file_backup(),
-            endpoints: vec![InputEndpoint {
-                kind: state::InputEndpointKind::File,
-                label: None,
-                file_id: Some(file_id),
-            }],
-            src: None,
-            enabled: true,
-        }
-    }
-    /// Creates a new primary [`Input`] out of the given [`state::InputSrcUrl`].
-    #[must_use]
-    pub fn new_primary(src: Option<state::InputSrcUrl>) -> Self {
-        Self {
-            id: None,
-            key: state::InputKey::primary(),
-            endpoints: vec![InputEndpoint {
-                kind: state::InputEndpointKind::Rtmp,
-                label: None,
-                file_id: None,
-            }],
-            src: src.map(InputSrc::RemoteUrl),
-            enabled: true,
-        }
-    }
-
-    /// Creates a new backup [`Input`] out of the given [`BackupInput`].
-    #[must_use]
-    pub fn new_backup(backup: BackupInput) -> Self {
-        Self {
-            id: None,
-            key: backup.key,
-            endpoints: vec![InputEndpoint {
-                kind: state::InputEndpointKind::Rtmp,
-                label: None,
-                file_id: None,
-            }],
-            src: backup.src.map(InputSrc::RemoteUrl),
-            enabled: true,
-        }
-    }
-
-    /// Creates a new [`Input`] out of the given
-    /// [`state::InputKey`] and [`InputSrc`].
-    #[must_use]
-    pub fn new(
-        input_key: state::InputKey,
-        input_src: Option<InputSrc>,
-        with_hls: bool,
-    ) -> Input {
-        let mut endpoints = vec![InputEndpoint {
-            kind: state::InputEndpointKind::Rtmp,
-            label: None,
-            file_id: None,
-        }];
-        if with_hls {
-            endpoints.push(InputEndpoint {
-                kind: state::InputEndpointKind::Hls,
-                label: None,
-                file_id: None,
-            });
-        }
-
-        Input {
-            id: None,
-            key: input_key,
-            endpoints,
-            src: input_src,
-            enabled: true,
-        }
-    }
-}
 impl<'de> Deserialize<'de> for Input {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -474,30 +399,21 @@ impl Volume {
     #[inline]
     #[must_use]
     pub fn is_origin(&self) -> bool {
-        state::Volume::new(self).is_origin()
+        state::Volume::new(&self.clone().into()).is_origin()
     }
 }
 
 /// Implementation of Default volume as [`state::Volume::default`]
 impl Default for Volume {
     fn default() -> Self {
-        state::Volume::default().export()
+        let v = state::Volume::default();
+        Self {
+            level: v.level,
+            muted: v.muted,
+        }
     }
 }
 
-/// Backup input
-#[derive(
-    Clone, Debug, Deserialize, Eq, PartialEq, Serialize, GraphQLInputObject,
-)]
-pub struct BackupInput {
-    /// Key for this [`BackupInput`]
-    pub key: state::InputKey,
-
-    /// URL to pull a live stream from for a backup endpoint.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub src: Option<state::InputSrcUrl>,
-}
-
 /// Shareable (exportable and importable) specification of a [`state::Playlist`]
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Playlist {
@@ -505,24 +421,6 @@ pub struct Playlist {
     pub queue: Vec<PlaylistFileInfo>,
 }
 
-impl Playlist {
-    /// Creates a new [`Playlist`] out of the given
-    /// [`state::Playlist`].
-    #[must_use]
-    pub fn new(playlist: state::Playlist) -> Playlist {
-        Self {
-            queue: playlist
-                .queue
-                .into_iter()
-                .map(|x| PlaylistFileInfo {
-                    name: x.name,
-                    file_id: x.file_id,
-                })
-                .collect(),
-        }
-    }
-}
-
 /// Shareable (exportable and importable) specification of a
 /// [`PlaylistFileInfo`]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -0,0 +1,1412 @@
+//! Version 2 of a shareable (exportable and importable) specification of
+//! application's [`State`].
+//!
+//! Specifications of older versions are migrated into this one via [`From`]
+//! implementations.
+//!
+//! [`State`]: state::State
+use ephyr_serde::is_false;
+use std::collections::HashSet;
+
+use crate::{file_manager::FileId, spec::v1, state, types::UNumber};
+use chrono::{DateTime, Utc};
+use juniper::GraphQLInputObject;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use url::Url;
+
+/// Shareable (exportable and importable) specification of a [`State`].
+///
+/// [`State`]: state::State
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Spec {
+    /// [`Settings`] to be performed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<Settings>,
+
+    /// [`Restream`]s to be performed.
+    #[serde(deserialize_with = "Spec::deserialize_restreams")]
+    pub restreams: Vec<Restream>,
+}
+
+impl Spec {
+    fn deserialize_restreams<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Restream>, D::Error> {
+        let mut restreams = <Vec<Restream>>::deserialize(deserializer)?;
+
+        // `Output`s are matched by their `dst` on applying, so passwords
+        // should be stored out of it beforehand.
+        for o in restreams.iter_mut().flat_map(|r| &mut r.outputs) {
+            let password = o.dst.take_password();
+            o.password =
+                o.password.take().or(password).filter(|p| !p.is_empty());
+        }
+
+        if !restreams.is_empty() {
+            let mut unique = HashSet::with_capacity(restreams.len());
+            for r in &restreams {
+                if let Some(key) = unique.replace(&r.key) {
+                    return Err(D::Error::custom(format!(
+                        "Duplicate Restream.key in Spec.restreams: {key}",
+                    )));
+                }
+            }
+
+            let mut ports = HashSet::new();
+            for l in restreams.iter().flat_map(|r| r.input.srt_listeners()) {
+                if !ports.insert(l.port.0) {
+                    return Err(D::Error::custom(format!(
+                        "Duplicate SrtListener.port in Spec.restreams: {}",
+                        l.port.0,
+                    )));
+                }
+            }
+        }
+
+        Ok(restreams)
+    }
+}
+
+/// Server's settings.
+///
+/// It keeps different settings not related to restreams but to whole server
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct Settings {
+    /// Title for the server
+    /// It is used for differentiating servers on UI side if multiple servers
+    /// are used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Whether do we need to confirm deletion of inputs and outputs
+    /// If `true` we should confirm deletion, `false` - do not confirm.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete_confirmation: Option<bool>,
+
+    /// Whether do we need to confirm enabling/disabling of inputs \
+    /// or outputs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_confirmation: Option<bool>,
+
+    /// Google API key for file playback and downloading.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub google_api_key: Option<String>,
+
+    /// Max number of files allowed in [Restream]'s playlist.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_downloading_files: Option<UNumber>,
+
+    /// Global [`RetryPolicy`] of restarting failed re-streaming processes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_policy: Option<RetryPolicy>,
+
+    /// [`DvrRetention`] policy of recorded files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dvr_retention: Option<DvrRetention>,
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::Restream`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Restream {
+    /// Unique ID of [`Restream`].
+    pub id: Option<state::RestreamId>,
+
+    /// Unique key of this [`Restream`] identifying it, and used to form its
+    /// endpoints URLs.
+    pub key: state::RestreamKey,
+
+    /// Optional label of this [`Restream`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<state::Label>,
+
+    /// [`Input`] that a live stream is received from.
+    pub input: Input,
+
+    /// [`Output`]s that a live stream is re-streamed to.
+    #[serde(
+        default,
+        deserialize_with = "Restream::deserialize_outputs",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub outputs: Vec<Output>,
+
+    /// Playlist for this restream
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playlist: Option<Playlist>,
+
+    /// Indicator whether the playback [`Input`] of this [`Restream`] should
+    /// be encoded.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub with_playback_encoding: bool,
+
+    /// [`Schedule`]s of enabling and disabling this [`Restream`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<Schedule>,
+}
+
+impl Restream {
+    /// Deserializes [`Restream::outputs`] ensuring its invariants preserved.
+    fn deserialize_outputs<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Output>, D::Error> {
+        let outputs = <Vec<Output>>::deserialize(deserializer)?;
+
+        if !outputs.is_empty() {
+            let mut unique = HashSet::with_capacity(outputs.len());
+            for o in &outputs {
+                if let Some(dst) = unique.replace(&o.dst) {
+                    return Err(D::Error::custom(format!(
+                        "Duplicate Output.dst in Restream.outputs: {dst}"
+                    )));
+                }
+                if let Some(encoding) = &o.audio_encoding {
+                    state::AudioEncoding::validate_dst(encoding, &o.dst)
+                        .map_err(D::Error::custom)?;
+                }
+            }
+        }
+
+        Ok(outputs)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a [`state::Input`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Input {
+    /// Unique ID of this `Input`.
+    ///
+    /// Once assigned, it never changes.
+    pub id: Option<state::InputId>,
+
+    /// Key of this [`Input`] to expose its [`InputEndpoint`]s with for
+    /// accepting and serving a live stream.
+    pub key: state::InputKey,
+
+    /// Endpoints of this [`Input`] serving a live stream for `Output`s and
+    /// clients.
+    pub endpoints: Vec<InputEndpoint>,
+
+    /// Source to pull a live stream from.
+    ///
+    /// If empty then a live stream is received (pushed) rather than is pulled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub src: Option<InputSrc>,
+
+    /// Indicator whether this [`Input`] is enabled, so is allowed to receive a
+    /// live stream from its upstream sources.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub enabled: bool,
+
+    /// Indicator whether a live stream received by this [`Input`] should be
+    /// recorded into DVR files.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub record: bool,
+}
+
+impl Input {
+    /// Creates a file backup [`Input`] out of the given [`FileId`].
+    #[must_use]
+    pub fn new_file_backup(file_id: FileId) -> Self {
+        Self {
+            id: None,
+            key: state::InputKey::file_backup(),
+            endpoints: vec![InputEndpoint {
+                kind: state::InputEndpointKind::File,
+                label: None,
+                file_id: Some(file_id),
+                srt: None,
+            }],
+            src: None,
+            enabled: true,
+            record: false,
+        }
+    }
+    /// Creates a new primary [`Input`] out of the given [`state::InputSrcUrl`].
+    #[must_use]
+    pub fn new_primary(src: Option<state::InputSrcUrl>) -> Self {
+        Self {
+            id: None,
+            key: state::InputKey::primary(),
+            endpoints: vec![InputEndpoint {
+                kind: state::InputEndpointKind::Rtmp,
+                label: None,
+                file_id: None,
+                srt: None,
+            }],
+            src: src.map(InputSrc::RemoteUrl),
+            enabled: true,
+            record: false,
+        }
+    }
+
+    /// Creates a new backup [`Input`] out of the given [`BackupInput`].
+    #[must_use]
+    pub fn new_backup(backup: BackupInput) -> Self {
+        Self {
+            id: None,
+            key: backup.key,
+            endpoints: vec![InputEndpoint {
+                kind: state::InputEndpointKind::Rtmp,
+                label: None,
+                file_id: None,
+                srt: None,
+            }],
+            src: backup.src.map(InputSrc::RemoteUrl),
+            enabled: true,
+            record: false,
+        }
+    }
+
+    /// Creates a new [`Input`] out of the given
+    /// [`state::InputKey`] and [`InputSrc`].
+    #[must_use]
+    pub fn new(
+        input_key: state::InputKey,
+        input_src: Option<InputSrc>,
+        with_hls: bool,
+    ) -> Input {
+        let mut endpoints = vec![InputEndpoint {
+            kind: state::InputEndpointKind::Rtmp,
+            label: None,
+            file_id: None,
+            srt: None,
+        }];
+        if with_hls {
+            endpoints.push(InputEndpoint {
+                kind: state::InputEndpointKind::Hls,
+                label: None,
+                file_id: None,
+                srt: None,
+            });
+        }
+
+        Input {
+            id: None,
+            key: input_key,
+            endpoints,
+            src: input_src,
+            enabled: true,
+            record: false,
+        }
+    }
+
+    /// Collects [`SrtListener`]s of this [`Input`] and all its failover
+    /// [`Input`]s.
+    #[must_use]
+    pub fn srt_listeners(&self) -> Vec<&SrtListener> {
+        let mut listeners: Vec<_> = self
+            .endpoints
+            .iter()
+            .filter_map(|e| e.srt.as_ref())
+            .collect();
+        if let Some(InputSrc::FailoverInputs(inputs)) = &self.src {
+            listeners.extend(inputs.iter().flat_map(Input::srt_listeners));
+        }
+        listeners
+    }
+}
+impl<'de> Deserialize<'de> for Input {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawInput {
+            key: state::InputKey,
+            endpoints: Vec<InputEndpoint>,
+            #[serde(default)]
+            src: Option<InputSrc>,
+            #[serde(default)]
+            enabled: bool,
+            #[serde(default)]
+            record: bool,
+        }
+
+        let raw = RawInput::deserialize(deserializer)?;
+
+        let mut unique_endpoints = HashSet::with_capacity(raw.endpoints.len());
+        for e in &raw.endpoints {
+            if let Some(kind) = unique_endpoints.replace(e.kind) {
+                return Err(D::Error::custom(format!(
+                    "Duplicate InputEndpoint.kind in Input.endpoints: {kind}"
+                )));
+            }
+        }
+        if let Some(e) = raw.endpoints.iter().find(|e| {
+            e.kind == state::InputEndpointKind::Srt && e.srt.is_none()
+        }) {
+            return Err(D::Error::custom(format!(
+                "InputEndpoint.srt should be specified for {} endpoint",
+                e.kind,
+            )));
+        }
+        if unique_endpoints.contains(&state::InputEndpointKind::Srt)
+            && !unique_endpoints.contains(&state::InputEndpointKind::Rtmp)
+        {
+            return Err(D::Error::custom(format!(
+                "Input.endpoints should contain {} endpoint along with {} one",
+                state::InputEndpointKind::Rtmp,
+                state::InputEndpointKind::Srt,
+            )));
+        }
+        if !unique_endpoints.contains(&state::InputEndpointKind::Rtmp)
+            && !unique_endpoints.contains(&state::InputEndpointKind::File)
+        {
+            return Err(D::Error::custom(format!(
+                "Input.endpoints should contain at least one {} or {} endpoint",
+                state::InputEndpointKind::Rtmp,
+                state::InputEndpointKind::File,
+            )));
+        }
+
+        if let Some(src) = &raw.src {
+            fn ensure_srcs_unique<'i>(
+                src: &'i InputSrc,
+                unique_urls: &mut HashSet<&'i state::InputSrcUrl>,
+                unique_keys: &mut HashSet<&'i state::InputKey>,
+            ) -> Result<(), String> {
+                match src {
+                    InputSrc::RemoteUrl(url) => {
+                        if let Some(url) = unique_urls.replace(url) {
+                            return Err(format!(
+                                "Duplicate RemoteInputSrc.url in Input.src: \
+                                {url}"
+                            ));
+                        }
+                    }
+                    InputSrc::FailoverInputs(inputs) => {
+                        for i in inputs {
+                            if let Some(key) = unique_keys.replace(&i.key) {
+                                return Err(format!(
+                                    "Duplicate Input.key in Input.srcs: {key}"
+                                ));
+                            }
+                            if let Some(s) = i.src.as_ref() {
+                                ensure_srcs_unique(
+                                    s,
+                                    unique_urls,
+                                    unique_keys,
+                                )?;
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
+
+            let mut unique_urls = HashSet::new();
+            let mut unique_keys = HashSet::with_capacity(1);
+            _ = unique_keys.insert(&raw.key);
+            ensure_srcs_unique(src, &mut unique_urls, &mut unique_keys)
+                .map_err(D::Error::custom)?;
+        }
+
+        Ok(Self {
+            id: None,
+            key: raw.key,
+            endpoints: raw.endpoints,
+            src: raw.src,
+            enabled: raw.enabled,
+            record: raw.record,
+        })
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::InputEndpoint`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InputEndpoint {
+    /// Kind of this [`InputEndpoint`].
+    pub kind: state::InputEndpointKind,
+
+    /// Label for this input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<state::Label>,
+
+    /// If the endpoint has kind FILE then this contains the file ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<FileId>,
+
+    /// If the endpoint has kind SRT then this contains its [`SrtListener`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt: Option<SrtListener>,
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::SrtListener`].
+#[derive(Clone, Debug, Eq, GraphQLInputObject, PartialEq, Serialize)]
+#[graphql(name = "SrtListenerInput")]
+pub struct SrtListener {
+    /// UDP port to listen for SRT pushes on.
+    pub port: UNumber,
+
+    /// `streamid` of the listener, which pushing SRT callers are expected to
+    /// be configured with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<String>,
+
+    /// Passphrase that pushed SRT streams should be encrypted with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for SrtListener {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawSrtListener {
+            port: UNumber,
+            #[serde(default)]
+            stream_id: Option<String>,
+            #[serde(default)]
+            passphrase: Option<String>,
+        }
+
+        let raw = RawSrtListener::deserialize(deserializer)?;
+        let spec = Self {
+            port: raw.port,
+            stream_id: raw.stream_id,
+            passphrase: raw.passphrase,
+        };
+        state::SrtListener::validate(&spec).map_err(D::Error::custom)?;
+
+        Ok(spec)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::InputSrc`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputSrc {
+    /// Remote endpoint represented by its URL.
+    RemoteUrl(state::InputSrcUrl),
+
+    /// Multiple [`Input`]s forming a failover source.
+    FailoverInputs(Vec<Input>),
+}
+
+/// Shareable (exportable and importable) specification of a [`state::Output`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Output {
+    /// Unique ID of this `Output`.
+    ///
+    /// Once assigned, it never changes.
+    pub id: Option<state::OutputId>,
+
+    /// Downstream URL to re-stream a live stream onto.
+    ///
+    /// For compatibility, `password` query parameter of an `icecast://` URL
+    /// is accepted, being moved into [`Output::password`].
+    pub dst: state::OutputDstUrl,
+
+    /// Password of the [Icecast] mount to publish onto.
+    ///
+    /// [Icecast]: https://icecast.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// Optional label of this [`Output`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<state::Label>,
+
+    /// Url of stream preview.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_url: Option<Url>,
+
+    /// Volume rate of this [`Output`]'s audio tracks when mixed with
+    /// [`Output::mixins`].
+    #[serde(default, skip_serializing_if = "Volume::is_origin")]
+    pub volume: Volume,
+
+    /// [`Mixin`]s to mix this [`Output`] with before re-streaming it to its
+    /// downstream destination.
+    ///
+    /// If empty, then no mixing is performed.
+    #[serde(
+        default,
+        deserialize_with = "Output::deserialize_mixins",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub mixins: Vec<Mixin>,
+
+    /// Indicator whether this [`Output`]  is enabled, so is allowed to perform
+    /// a live stream re-streaming to its downstream destination.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub enabled: bool,
+
+    /// [`Schedule`]s of enabling and disabling this [`Output`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<Schedule>,
+
+    /// [`TranscodingProfile`] to transcode a live stream with before
+    /// re-streaming it to the downstream destination.
+    ///
+    /// If [`None`], then a live stream is re-streamed "as is".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcoding: Option<TranscodingProfile>,
+
+    /// [`AudioEncoding`] to encode audio with before re-streaming it to the
+    /// audio-only downstream destination.
+    ///
+    /// If [`None`], then the default [`state::AudioEncoding`] is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_encoding: Option<AudioEncoding>,
+
+    /// [`Loudness`] to normalize and limit the mixed audio of this [`Output`]
+    /// with.
+    ///
+    /// If [`None`], then the mixed audio is re-streamed "as is".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness: Option<Loudness>,
+
+    /// [`RetryPolicy`] of restarting a failed re-streaming to the downstream
+    /// destination.
+    ///
+    /// If [`None`], then the global one from [`Settings`] is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_policy: Option<RetryPolicy>,
+}
+
+impl Output {
+    /// Deserializes [`Output::mixins`] ensuring its invariants preserved.
+    fn deserialize_mixins<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Mixin>, D::Error> {
+        let mixins = <Vec<Mixin>>::deserialize(deserializer)?;
+
+        if !mixins.is_empty() {
+            let mut unique = HashSet::with_capacity(mixins.len());
+            let mut ts_count: u8 = 0;
+            let mut has_sidechain = false;
+            for m in &mixins {
+                if let Some(src) = unique.replace(&m.src) {
+                    return Err(D::Error::custom(format!(
+                        "Duplicate Mixin.src in Output.mixins: {src}"
+                    )));
+                }
+                if m.src.scheme() == "ts" {
+                    ts_count += 1;
+                    if ts_count > 3 {
+                        return Err(D::Error::custom(format!(
+                            "Maximum 3 TeamSpeak Mixin.src allowed \
+                            in Output.mixins: {}",
+                            m.src,
+                        )));
+                    }
+                }
+                if m.sidechain.is_some() {
+                    if has_sidechain {
+                        return Err(D::Error::custom(format!(
+                            "Only one Mixin.sidechain is allowed \
+                            in Output.mixins: {}",
+                            m.src
+                        )));
+                    }
+                    has_sidechain = true;
+                }
+            }
+        }
+
+        Ok(mixins)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a [`state::Mixin`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Mixin {
+    /// URL of the source to be mixed with an [`Output`].
+    ///
+    /// For compatibility, `password` and `channel_password` query parameters
+    /// are accepted, being moved into [`Mixin::password`] and
+    /// [`Mixin::channel_password`].
+    pub src: state::MixinSrcUrl,
+
+    /// Volume rate of this [`Mixin`]'s audio tracks to mix them with.
+    #[serde(default, skip_serializing_if = "Volume::is_origin")]
+    pub volume: Volume,
+
+    /// Delay that this [`Mixin`] should wait before being mixed with an
+    /// [`Output`].
+    #[serde(default, skip_serializing_if = "state::Delay::is_zero")]
+    pub delay: state::Delay,
+
+    /// [`Sidechain`] parameters, if this [`Mixin`] should be side-chained
+    /// with an [`Output`].
+    ///
+    /// For compatibility, `true` and `false` are accepted as default
+    /// parameters and no side-chaining respectively.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_sidechain",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sidechain: Option<Sidechain>,
+
+    /// [`SpeakerFilter`] of [TeamSpeak] channel members to capture audio of,
+    /// if this [`Mixin`] is sourced from [TeamSpeak].
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[serde(default, skip_serializing_if = "SpeakerFilter::is_empty")]
+    pub speakers: SpeakerFilter,
+
+    /// Identity to connect to a [TeamSpeak] server with, so the same client
+    /// is recognized by it after importing this [`Mixin`] elsewhere.
+    ///
+    /// Is exported only on demand, being a secret.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub teamspeak_identity: Option<String>,
+
+    /// Password to authenticate on a [TeamSpeak] or [Mumble] server with.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// Password to join a [TeamSpeak] or [Mumble] channel with.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_password: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for Mixin {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawMixin {
+            src: state::MixinSrcUrl,
+            #[serde(default)]
+            volume: Volume,
+            #[serde(default)]
+            delay: state::Delay,
+            #[serde(default, deserialize_with = "deserialize_sidechain")]
+            sidechain: Option<Sidechain>,
+            #[serde(default)]
+            speakers: SpeakerFilter,
+            #[serde(default)]
+            teamspeak_identity: Option<String>,
+            #[serde(default)]
+            password: Option<String>,
+            #[serde(default)]
+            channel_password: Option<String>,
+        }
+
+        let mut raw = RawMixin::deserialize(deserializer)?;
+        let password = raw.src.take_param(state::MixinSrcUrl::PASSWORD_PARAM);
+        let channel_password = raw
+            .src
+            .take_param(state::MixinSrcUrl::CHANNEL_PASSWORD_PARAM);
+
+        Ok(Self {
+            src: raw.src,
+            volume: raw.volume,
+            delay: raw.delay,
+            sidechain: raw.sidechain,
+            speakers: raw.speakers,
+            teamspeak_identity: raw.teamspeak_identity,
+            password: raw.password.or(password).filter(|p| !p.is_empty()),
+            channel_password: raw
+                .channel_password
+                .or(channel_password)
+                .filter(|p| !p.is_empty()),
+        })
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::SpeakerFilter`].
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    GraphQLInputObject,
+    PartialEq,
+    Serialize,
+)]
+#[graphql(name = "SpeakerFilterInput")]
+pub struct SpeakerFilter {
+    /// Channel members to capture audio of only.
+    ///
+    /// If empty, then all the not denied members are captured.
+    #[serde(default, skip_serializing_if = "SpeakerList::is_empty")]
+    #[graphql(default)]
+    pub allow: SpeakerList,
+
+    /// Channel members to never capture audio of.
+    #[serde(default, skip_serializing_if = "SpeakerList::is_empty")]
+    #[graphql(default)]
+    pub deny: SpeakerList,
+}
+
+impl SpeakerFilter {
+    /// Indicates whether this [`SpeakerFilter`] captures all the channel
+    /// members.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::SpeakerList`].
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    GraphQLInputObject,
+    PartialEq,
+    Serialize,
+)]
+#[graphql(name = "SpeakerListInput")]
+pub struct SpeakerList {
+    /// Nicknames of the members, matched case-insensitively.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[graphql(default)]
+    pub nicknames: Vec<String>,
+
+    /// Unique IDs of the members (base64-encoded public key hashes).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[graphql(default)]
+    pub uids: Vec<String>,
+
+    /// IDs of the server groups the members belong to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[graphql(default)]
+    pub server_groups: Vec<UNumber>,
+}
+
+impl SpeakerList {
+    /// Indicates whether this [`SpeakerList`] contains no members.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nicknames.is_empty()
+            && self.uids.is_empty()
+            && self.server_groups.is_empty()
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::Sidechain`].
+#[derive(Clone, Copy, Debug, Eq, GraphQLInputObject, PartialEq, Serialize)]
+#[graphql(name = "SidechainInput")]
+pub struct Sidechain {
+    /// Level of a [`Mixin`]'s audio above which an [`Output`]'s audio is
+    /// ducked, in dBFS.
+    pub threshold: i32,
+
+    /// Ratio by which an [`Output`]'s audio is ducked.
+    pub ratio: UNumber,
+
+    /// Time of ducking an [`Output`]'s audio, in milliseconds.
+    pub attack: UNumber,
+
+    /// Time of restoring an [`Output`]'s audio, in milliseconds.
+    pub release: UNumber,
+
+    /// Width of the curve knee around the `threshold`, in dB.
+    pub knee: UNumber,
+
+    /// Gain applied to the ducked audio, in dB.
+    pub makeup: UNumber,
+}
+
+impl Default for Sidechain {
+    #[inline]
+    fn default() -> Self {
+        state::Sidechain::default().export()
+    }
+}
+
+impl<'de> Deserialize<'de> for Sidechain {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawSidechain {
+            threshold: i32,
+            ratio: UNumber,
+            attack: UNumber,
+            release: UNumber,
+            knee: UNumber,
+            makeup: UNumber,
+        }
+
+        let raw = RawSidechain::deserialize(deserializer)?;
+        let spec = Self {
+            threshold: raw.threshold,
+            ratio: raw.ratio,
+            attack: raw.attack,
+            release: raw.release,
+            knee: raw.knee,
+            makeup: raw.makeup,
+        };
+        state::Sidechain::validate(&spec).map_err(D::Error::custom)?;
+
+        Ok(spec)
+    }
+}
+
+/// Deserializes an optional [`Sidechain`] (either the [`state`] or the spec
+/// one), accepting a boolean flag as well, which was used before.
+///
+/// # Errors
+///
+/// If neither a boolean nor valid [`Sidechain`] parameters are provided.
+pub(crate) fn deserialize_sidechain<'de, D, T>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    // Parameters are deserialized separately, so their validation error is
+    // not swallowed by the `untagged` enum.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Flag(bool),
+        Params(Option<serde_json::Value>),
+    }
+
+    Ok(match Raw::deserialize(deserializer)? {
+        Raw::Flag(flag) => flag.then(T::default),
+        Raw::Params(params) => params
+            .map(T::deserialize)
+            .transpose()
+            .map_err(D::Error::custom)?,
+    })
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::TranscodingProfile`].
+#[derive(Clone, Debug, Eq, GraphQLInputObject, PartialEq, Serialize)]
+#[graphql(name = "TranscodingProfileInput")]
+pub struct TranscodingProfile {
+    /// Codec to encode video with.
+    #[serde(default)]
+    #[graphql(default)]
+    pub video_codec: state::VideoCodec,
+
+    /// Bitrate of the encoded video, in kbit/s.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_bitrate: Option<UNumber>,
+
+    /// Width of the encoded video, in pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<UNumber>,
+
+    /// Height of the encoded video, in pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<UNumber>,
+
+    /// Frame rate of the encoded video.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fps: Option<UNumber>,
+
+    /// Size of the GOP (distance between keyframes) of the encoded video, in
+    /// frames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gop: Option<UNumber>,
+
+    /// Codec to encode audio with.
+    #[serde(default)]
+    #[graphql(default)]
+    pub audio_codec: state::AudioCodec,
+
+    /// Bitrate of the encoded audio, in kbit/s.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_bitrate: Option<UNumber>,
+}
+
+impl<'de> Deserialize<'de> for TranscodingProfile {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawTranscodingProfile {
+            #[serde(default)]
+            video_codec: state::VideoCodec,
+            #[serde(default)]
+            video_bitrate: Option<UNumber>,
+            #[serde(default)]
+            width: Option<UNumber>,
+            #[serde(default)]
+            height: Option<UNumber>,
+            #[serde(default)]
+            fps: Option<UNumber>,
+            #[serde(default)]
+            gop: Option<UNumber>,
+            #[serde(default)]
+            audio_codec: state::AudioCodec,
+            #[serde(default)]
+            audio_bitrate: Option<UNumber>,
+        }
+
+        let raw = RawTranscodingProfile::deserialize(deserializer)?;
+        let spec = Self {
+            video_codec: raw.video_codec,
+            video_bitrate: raw.video_bitrate,
+            width: raw.width,
+            height: raw.height,
+            fps: raw.fps,
+            gop: raw.gop,
+            audio_codec: raw.audio_codec,
+            audio_bitrate: raw.audio_bitrate,
+        };
+        state::TranscodingProfile::validate(&spec).map_err(D::Error::custom)?;
+
+        Ok(spec)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::AudioEncoding`].
+#[derive(Clone, Copy, Debug, Eq, GraphQLInputObject, PartialEq, Serialize)]
+#[graphql(name = "AudioEncodingInput")]
+pub struct AudioEncoding {
+    /// Format to encode audio into.
+    #[serde(default)]
+    #[graphql(default)]
+    pub format: state::AudioFormat,
+
+    /// Bitrate of the encoded audio, in kbit/s.
+    pub bitrate: UNumber,
+}
+
+impl<'de> Deserialize<'de> for AudioEncoding {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawAudioEncoding {
+            #[serde(default)]
+            format: state::AudioFormat,
+            bitrate: UNumber,
+        }
+
+        let raw = RawAudioEncoding::deserialize(deserializer)?;
+        let spec = Self {
+            format: raw.format,
+            bitrate: raw.bitrate,
+        };
+        state::AudioEncoding::validate(&spec).map_err(D::Error::custom)?;
+
+        Ok(spec)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::Loudness`].
+#[derive(Clone, Debug, Eq, GraphQLInputObject, PartialEq, Serialize)]
+#[graphql(name = "LoudnessInput")]
+pub struct Loudness {
+    /// Integrated loudness to normalize audio to, in LUFS.
+    ///
+    /// If [`None`], then audio is only limited to the
+    /// [`Loudness::true_peak`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<i32>,
+
+    /// Loudness range to normalize audio to, in LU.
+    pub range: UNumber,
+
+    /// Maximum true peak level of audio, in dBTP.
+    pub true_peak: i32,
+}
+
+impl<'de> Deserialize<'de> for Loudness {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawLoudness {
+            #[serde(default)]
+            target: Option<i32>,
+            range: UNumber,
+            true_peak: i32,
+        }
+
+        let raw = RawLoudness::deserialize(deserializer)?;
+        let spec = Self {
+            target: raw.target,
+            range: raw.range,
+            true_peak: raw.true_peak,
+        };
+        state::Loudness::validate(&spec).map_err(D::Error::custom)?;
+
+        Ok(spec)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::RetryPolicy`].
+#[derive(Clone, Debug, Eq, GraphQLInputObject, PartialEq, Serialize)]
+#[graphql(name = "RetryPolicyInput")]
+pub struct RetryPolicy {
+    /// Delay before the first restart after a failure, in seconds.
+    pub initial_delay: UNumber,
+
+    /// Maximum delay between restarts, in seconds.
+    pub max_delay: UNumber,
+
+    /// Maximum number of consecutive failures, after which an [`Output`] is
+    /// disabled automatically.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<UNumber>,
+}
+
+impl<'de> Deserialize<'de> for RetryPolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawRetryPolicy {
+            initial_delay: UNumber,
+            max_delay: UNumber,
+            #[serde(default)]
+            max_attempts: Option<UNumber>,
+        }
+
+        let raw = RawRetryPolicy::deserialize(deserializer)?;
+        let spec = Self {
+            initial_delay: raw.initial_delay,
+            max_delay: raw.max_delay,
+            max_attempts: raw.max_attempts,
+        };
+        state::RetryPolicy::validate(&spec).map_err(D::Error::custom)?;
+
+        Ok(spec)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::DvrRetention`].
+#[derive(Clone, Debug, Eq, GraphQLInputObject, PartialEq, Serialize)]
+#[graphql(name = "DvrRetentionInput")]
+pub struct DvrRetention {
+    /// Maximum age of a recorded file, in hours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<UNumber>,
+
+    /// Maximum total size of files recorded by a single [`Output`] (or
+    /// [`Input`]), in gigabytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_size: Option<UNumber>,
+
+    /// Maximum total size of all the recorded files, in gigabytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_size: Option<UNumber>,
+
+    /// Minimum free space on the disk storing recorded files, in gigabytes.
+    pub min_free_space: UNumber,
+}
+
+impl<'de> Deserialize<'de> for DvrRetention {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawDvrRetention {
+            #[serde(default)]
+            max_age: Option<UNumber>,
+            #[serde(default)]
+            max_output_size: Option<UNumber>,
+            #[serde(default)]
+            max_total_size: Option<UNumber>,
+            min_free_space: UNumber,
+        }
+
+        let raw = RawDvrRetention::deserialize(deserializer)?;
+        let spec = Self {
+            max_age: raw.max_age,
+            max_output_size: raw.max_output_size,
+            max_total_size: raw.max_total_size,
+            min_free_space: raw.min_free_space,
+        };
+        state::DvrRetention::validate(&spec).map_err(D::Error::custom)?;
+
+        Ok(spec)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::Schedule`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Schedule {
+    /// Moment when the first window of this [`Schedule`] starts.
+    pub start: DateTime<Utc>,
+
+    /// Moment when the first window of this [`Schedule`] ends.
+    pub end: DateTime<Utc>,
+
+    /// Period of repeating the window of this [`Schedule`].
+    #[serde(default, skip_serializing_if = "state::ScheduleRepeat::is_once")]
+    pub repeat: state::ScheduleRepeat,
+}
+
+impl<'de> Deserialize<'de> for Schedule {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawSchedule {
+            start: DateTime<Utc>,
+            end: DateTime<Utc>,
+            #[serde(default)]
+            repeat: state::ScheduleRepeat,
+        }
+
+        let raw = RawSchedule::deserialize(deserializer)?;
+        state::Schedule::validate(raw.start, raw.end, raw.repeat)
+            .map_err(D::Error::custom)?;
+
+        Ok(Self {
+            start: raw.start,
+            end: raw.end,
+            repeat: raw.repeat,
+        })
+    }
+}
+
+/// Shareable specification of [`state::Volume`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Volume {
+    /// Volume level or rate in percentages
+    #[serde(default)]
+    pub level: state::VolumeLevel,
+
+    /// Flag if the volume is muted
+    #[serde(default)]
+    pub muted: bool,
+
+    /// Flag if the volume is soloed
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub solo: bool,
+}
+
+impl Volume {
+    /// Indicates whether this [`Volume`] rate value corresponds
+    /// origin value of `state::Volume`
+    #[allow(clippy::trivially_copy_pass_by_ref)] // required for `serde`
+    #[inline]
+    #[must_use]
+    pub fn is_origin(&self) -> bool {
+        state::Volume::new(self).is_origin()
+    }
+}
+
+/// Implementation of Default volume as [`state::Volume::default`]
+impl Default for Volume {
+    fn default() -> Self {
+        state::Volume::default().export()
+    }
+}
+
+/// Backup input
+#[derive(
+    Clone, Debug, Deserialize, Eq, PartialEq, Serialize, GraphQLInputObject,
+)]
+pub struct BackupInput {
+    /// Key for this [`BackupInput`]
+    pub key: state::InputKey,
+
+    /// URL to pull a live stream from for a backup endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub src: Option<state::InputSrcUrl>,
+}
+
+/// Shareable (exportable and importable) specification of a [`state::Playlist`]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Playlist {
+    /// List of files in playlist
+    pub queue: Vec<PlaylistFileInfo>,
+}
+
+impl Playlist {
+    /// Creates a new [`Playlist`] out of the given
+    /// [`state::Playlist`].
+    #[must_use]
+    pub fn new(playlist: state::Playlist) -> Playlist {
+        Self {
+            queue: playlist
+                .queue
+                .into_iter()
+                .map(|x| PlaylistFileInfo {
+                    name: x.name,
+                    file_id: x.file_id,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`PlaylistFileInfo`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlaylistFileInfo {
+    /// Google ID of this file
+    pub file_id: FileId,
+
+    /// Name of this file
+    pub name: String,
+}
+
+impl From<v1::Spec> for Spec {
+    fn from(old: v1::Spec) -> Self {
+        Self {
+            settings: old.settings.map(Into::into),
+            restreams: old.restreams.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<v1::Settings> for Settings {
+    fn from(old: v1::Settings) -> Self {
+        Self {
+            title: old.title,
+            delete_confirmation: old.delete_confirmation,
+            enable_confirmation: old.enable_confirmation,
+            google_api_key: old.google_api_key,
+            max_downloading_files: old.max_downloading_files,
+            retry_policy: None,
+            dvr_retention: None,
+        }
+    }
+}
+
+impl From<v1::Restream> for Restream {
+    fn from(old: v1::Restream) -> Self {
+        Self {
+            id: old.id,
+            key: old.key,
+            label: old.label,
+            input: old.input.into(),
+            outputs: old.outputs.into_iter().map(Into::into).collect(),
+            playlist: old.playlist.map(Into::into),
+            with_playback_encoding: old
+                .with_playback_encoding
+                .unwrap_or_default(),
+            schedules: vec![],
+        }
+    }
+}
+
+impl From<v1::Input> for Input {
+    fn from(old: v1::Input) -> Self {
+        Self {
+            id: old.id,
+            key: old.key,
+            endpoints: old.endpoints.into_iter().map(Into::into).collect(),
+            src: old.src.map(Into::into),
+            enabled: old.enabled,
+            record: false,
+        }
+    }
+}
+
+impl From<v1::InputEndpoint> for InputEndpoint {
+    fn from(old: v1::InputEndpoint) -> Self {
+        Self {
+            kind: old.kind,
+            label: old.label,
+            file_id: old.file_id,
+            srt: None,
+        }
+    }
+}
+
+impl From<v1::InputSrc> for InputSrc {
+    fn from(old: v1::InputSrc) -> Self {
+        match old {
+            v1::InputSrc::RemoteUrl(url) => Self::RemoteUrl(url),
+            v1::InputSrc::FailoverInputs(inputs) => Self::FailoverInputs(
+                inputs.into_iter().map(Into::into).collect(),
+            ),
+        }
+    }
+}
+
+impl From<v1::Output> for Output {
+    fn from(old: v1::Output) -> Self {
+        Self {
+            id: old.id,
+            dst: old.dst,
+            password: None,
+            label: old.label,
+            preview_url: old.preview_url,
+            volume: old.volume.into(),
+            mixins: old.mixins.into_iter().map(Into::into).collect(),
+            enabled: old.enabled,
+            schedules: vec![],
+            transcoding: None,
+            audio_encoding: None,
+            loudness: None,
+            retry_policy: None,
+        }
+    }
+}
+
+impl From<v1::Mixin> for Mixin {
+    fn from(old: v1::Mixin) -> Self {
+        Self {
+            src: old.src,
+            volume: old.volume.into(),
+            delay: old.delay,
+            sidechain: old.sidechain.then(Sidechain::default),
+            speakers: SpeakerFilter::default(),
+            teamspeak_identity: None,
+            password: None,
+            channel_password: None,
+        }
+    }
+}
+
+impl From<v1::Volume> for Volume {
+    fn from(old: v1::Volume) -> Self {
+        Self {
+            level: old.level,
+            muted: old.muted,
+            solo: false,
+        }
+    }
+}
+
+impl From<v1::Playlist> for Playlist {
+    fn from(old: v1::Playlist) -> Self {
+        Self {
+            queue: old.queue.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<v1::PlaylistFileInfo> for PlaylistFileInfo {
+    fn from(old: v1::PlaylistFileInfo) -> Self {
+        Self {
+            file_id: old.file_id,
+            name: old.name,
+        }
+    }
+}
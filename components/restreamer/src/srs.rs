@@ -3,7 +3,7 @@
 //! [SRS]: https://github.com/ossrs/srs
 
 use crate::{
-    display_panic, dvr,
+    display_panic,
     proc::{kill_process, kill_process_by_name},
 };
 use anyhow::anyhow;
@@ -121,11 +121,6 @@ impl Server {
             )
         })?;
 
-        // Set directory for dvr::Storage served by this SRS instance.
-        let mut dvr_dir = http_dir.clone();
-        dvr_dir.push("dvr");
-        dvr::Storage { root_path: dvr_dir }.set_global()?;
-
         let mut cmd = Command::new(bin_path);
         _ = cmd
             .stdin(Stdio::null())
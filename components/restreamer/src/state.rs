@@ -2,36 +2,51 @@
 #![allow(clippy::module_name_repetitions)]
 
 mod client_statistics;
+mod dvr_retention;
 mod input;
 mod label;
 mod output;
+mod persistence;
 mod restream;
+mod retry_policy;
+mod schedule;
 mod settings;
+mod snapshot;
 
 pub use self::{
     client_statistics::{
         Client, ClientId, ClientStatistics, ClientStatisticsResponse,
         ServerInfo, StatusStatistics,
     },
+    dvr_retention::DvrRetention,
     input::{
         EndpointId, FailoverInputSrc, Input, InputEndpoint, InputEndpointKind,
         InputId, InputKey, InputSrc, InputSrcUrl, RemoteInputSrc,
+        RtspTransport, SrtListener,
     },
     label::Label,
     output::{
-        Delay, Mixin, MixinId, MixinSrcUrl, Output, OutputDstUrl, OutputId,
-        Volume, VolumeLevel,
+        AudioCodec, AudioEncoding, AudioFormat, Delay, HlsPush, HlsPushMethod,
+        Icecast, Loudness, Mixin, MixinId, MixinSrcUrl, Output, OutputDstUrl,
+        OutputId, Sidechain, Speaker, SpeakerFilter, SpeakerList,
+        TalkingSpeakers, TranscodingProfile, VideoCodec, Volume, VolumeLevel,
     },
     restream::{Restream, RestreamId, RestreamKey},
+    retry_policy::RetryPolicy,
+    schedule::{
+        Schedule, ScheduleAction, ScheduleId, ScheduleRepeat, ScheduledAction,
+    },
     settings::Settings,
+    snapshot::{PendingSnapshot, Snapshot, SnapshotId, SpecChange},
 };
 
 use actix_web::http::StatusCode;
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
 use derive_more::{Display, From, Into};
 use ephyr_log::tracing;
 use futures::{
-    future::TryFutureExt as _,
+    future::{self, TryFutureExt as _},
     sink,
     stream::{StreamExt as _, TryStreamExt as _},
 };
@@ -39,8 +54,10 @@ use futures_signals::signal::{Mutable, SignalExt as _};
 use juniper::{GraphQLEnum, GraphQLObject, GraphQLScalar};
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
-use std::{future::Future, mem, panic::AssertUnwindSafe, path::Path};
-use tokio::{fs, io::AsyncReadExt as _};
+use std::{
+    future::Future, iter, mem, panic::AssertUnwindSafe, path::Path, sync::Arc,
+    time::Duration,
+};
 
 use crate::{
     api::graphql,
@@ -50,12 +67,15 @@ use crate::{
     file_manager::{FileCommand, FileId, LocalFileInfo, PlaylistFileInfo},
     spec,
     stream_probe::StreamInfo,
-    stream_statistics::StreamStatistics,
+    stream_statistics::{AudioLevel, StreamFailure, StreamStatistics},
+    types::UNumber,
     Spec,
 };
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use self::persistence::Persistence;
+
 /// Reactive application's state.
 ///
 /// Any changes to it automatically propagate to the appropriate subscribers.
@@ -86,83 +106,129 @@ pub struct State {
 
     /// Errors and other messages visible in UI console
     pub console_log: Mutable<Vec<ConsoleMessage>>,
+
+    /// Moment when [`Schedule`]s have been checked for their actions the last
+    /// time.
+    ///
+    /// Persisted along with any other change (performing an action is one),
+    /// so the actions missed while the server is down are performed on its
+    /// startup, while the ones performed already are not.
+    #[serde(default)]
+    pub schedules_checked_at: Mutable<Option<DateTime<Utc>>>,
+
+    /// Bounded history of [`Snapshot`]s taken before mutations, ordered
+    /// from the oldest to the newest one.
+    #[serde(skip)]
+    pub history: Mutable<Vec<Snapshot>>,
+
+    /// Real-time [`AudioLevel`]s of the currently mixed [`Output`]s, measured
+    /// for their origin audio and each of their [`Mixin`]s.
+    #[serde(skip)]
+    pub audio_levels: Mutable<HashMap<OutputId, Vec<AudioLevel>>>,
+
+    /// [TeamSpeak] channel members talking at the moment in the channels of
+    /// the currently mixed [`Output`]s' [`Mixin`]s.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[serde(skip)]
+    pub talking_speakers: Mutable<HashMap<OutputId, Vec<TalkingSpeakers>>>,
+
+    /// Generations of [`Volume`] fadings, bumped by each new fading or tuning
+    /// of a [`Volume`] of an [`Output`] or its [`Mixin`], so the outdated
+    /// fading stops.
+    #[serde(skip)]
+    pub volume_fades: Mutable<HashMap<(OutputId, Option<MixinId>), u64>>,
+
+    /// Indicator whether free space of the DVR disk is below the
+    /// [`DvrRetention::min_free_space`], so all the recordings are suspended
+    /// until enough space is freed.
+    #[serde(skip)]
+    pub dvr_space_low: Mutable<bool>,
+
+    /// [`Persistence`] of this [`State`] into a file, if any.
+    #[serde(skip)]
+    persistence: Option<Persistence>,
 }
 
 impl State {
     /// Instantiates a new [`State`] reading it from a `file` (if any) and
     /// performing all the required inner subscriptions.
     ///
-    /// # Errors
-    ///
-    /// If [`State`] file exists, but fails to be parsed.
+    /// If the `file` is corrupted, then the newest valid one of its `backups`
+    /// is used instead. At most `backups` timestamped backups of the `file`
+    /// are kept.
     ///
-    /// ## Panics
+    /// # Errors
     ///
-    /// If state json file cannot be deserialized.
+    /// If [`State`] file exists, but neither it nor any of its backups can be
+    /// parsed.
     pub async fn try_new<P: AsRef<Path>>(
         file: P,
+        backups: usize,
     ) -> Result<Self, anyhow::Error> {
-        let file = file.as_ref();
-
-        let mut contents = vec![];
-        _ = fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .read(true)
-            .open(&file)
-            .await
-            .map_err(|e| {
-                anyhow!("Failed to open '{}' file: {}", file.display(), e)
-            })?
-            .read_to_end(&mut contents)
-            .await
-            .map_err(|e| {
-                anyhow!("Failed to read '{}' file: {}", file.display(), e)
-            })?;
-
-        let state = if contents.is_empty() {
-            State::default()
-        } else {
-            serde_json::from_slice(&contents).map_err(|e| {
-                anyhow!(
-                    "Failed to deserialize state from '{}' file: {}",
-                    file.display(),
-                    e,
-                )
-            })?
-        };
+        let persistence = Persistence::new(file, backups);
+
+        let mut state: Self = persistence.load().await?.unwrap_or_default();
+        state.persistence = Some(persistence.clone());
+
+        // `Mixin`s persisted before `Mixin::teamspeak_identity` was
+        // introduced should obtain their identities once, and the ones
+        // persisted with passwords in their `src` (or `Output`s in their
+        // `dst`) should store them separately.
+        for r in state.restreams.lock_mut().iter_mut() {
+            for o in &mut r.outputs {
+                _ = o.extract_password();
+                for m in &mut o.mixins {
+                    _ = m.extract_passwords();
+                    _ = m.ensure_teamspeak_identity();
+                }
+            }
+        }
 
-        let (file, persisted_state) = (file.to_owned(), state.clone());
-        let persist_state1 = move || {
-            fs::write(
-                file.clone(),
-                serde_json::to_vec(&persisted_state)
-                    .expect("Failed to serialize server state"),
-            )
-            .map_err(|e| tracing::error!("Failed to persist server state: {e}"))
-        };
-        let persist_state2 = persist_state1.clone();
-        let persist_state3 = persist_state1.clone();
+        let notify = persistence.spawn(state.clone());
+        let (notify1, notify2, notify3) =
+            (Arc::clone(&notify), Arc::clone(&notify), notify);
 
         Self::on_change("persist_restreams", &state.restreams, move |_| {
-            persist_state1()
+            notify1.notify_one();
+            future::ready(())
         });
         Self::on_change("persist_settings", &state.settings, move |_| {
-            persist_state2()
+            notify2.notify_one();
+            future::ready(())
         });
         Self::on_change("persist_clients", &state.clients, move |_| {
-            persist_state3()
+            notify3.notify_one();
+            future::ready(())
         });
 
         Ok(state)
     }
 
+    /// Writes the changes of this [`State`], which are still waiting to be
+    /// persisted, into its file right away.
+    ///
+    /// Should be called on shutdown, so no changes are lost.
+    pub async fn flush(&self) {
+        let Some(persistence) = &self.persistence else {
+            return;
+        };
+        if let Err(e) = persistence.flush(self).await {
+            tracing::error!("Failed to persist server state: {e}");
+        }
+    }
+
     /// Applies the given [`Spec`] to this [`State`].
     ///
+    /// [`Spec`]s of older versions are migrated to the [`spec::v2::Spec`]
+    /// before being applied.
+    ///
     /// If `replace` is `true` then all the [`Restream`]s, [`Restream::outputs`]
     /// and [`Output::mixins`] will be replaced with new ones, otherwise new
     /// ones will be merged with already existing ones.
-    pub fn apply(&self, new: spec::v1::Spec, replace: bool) {
+    pub fn apply<S: Into<Spec>>(&self, new: S, replace: bool) {
+        let new = new.into().into_v2();
+
         let mut restreams = self.restreams.lock_mut();
         if replace {
             let mut olds = mem::replace(
@@ -202,11 +268,11 @@ impl State {
         }
     }
 
-    /// Exports this [`State`] as a [`spec::v1::Spec`].
+    /// Exports this [`State`] as a [`spec::v2::Spec`].
     #[inline]
     #[must_use]
     pub fn export(&self) -> Spec {
-        spec::v1::Spec {
+        spec::v2::Spec {
             settings: Some(self.settings.get_cloned().export()),
             restreams: self
                 .restreams
@@ -218,6 +284,83 @@ impl State {
         .into()
     }
 
+    /// Takes a [`Snapshot`] of the current exported configuration of this
+    /// [`State`] before performing the given `mutation`.
+    ///
+    /// The [`Snapshot`] is recorded into the [`State::history`] once the
+    /// returned [`PendingSnapshot`] is dropped, and only if the `mutation` has
+    /// changed the configuration, so failed mutations leave no [`Snapshot`]s.
+    /// That's why it should be held until the `mutation` is performed.
+    pub fn record_snapshot(&self, mutation: &str) -> PendingSnapshot {
+        PendingSnapshot::new(self.clone(), mutation)
+    }
+
+    /// Rolls back the configuration of this [`State`] to the [`Snapshot`]
+    /// with the given `id`, replacing the current one.
+    ///
+    /// Returns [`None`] if there is no [`Snapshot`] with the given `id` in the
+    /// [`State::history`].
+    #[must_use]
+    pub fn rollback_to(&self, id: SnapshotId) -> Option<bool> {
+        let spec = self
+            .history
+            .lock_ref()
+            .iter()
+            .find(|s| s.id == id)?
+            .spec
+            .clone();
+        self.apply(spec.clone(), true);
+
+        // Applying a spec leaves `enabled` and `record` switches intact, so
+        // ongoing re-streams aren't broken on editing, while rolling back
+        // should restore them as well.
+        let mut restreams = self.restreams.lock_mut();
+        for new in &spec.restreams {
+            let Some(r) = restreams.iter_mut().find(|r| r.key == new.key)
+            else {
+                continue;
+            };
+            r.input.apply_switches(&new.input);
+            for new in &new.outputs {
+                let Some(o) = r.outputs.iter_mut().find(|o| o.dst == new.dst)
+                else {
+                    continue;
+                };
+                if o.enabled != new.enabled {
+                    o.enabled = new.enabled;
+                    o.disabled_reason = None;
+                }
+            }
+        }
+        Some(true)
+    }
+
+    /// Computes [`SpecChange`]s between the [`Snapshot`] with the given `from`
+    /// ID and the one with the given `to` ID, or the current configuration of
+    /// this [`State`] if `to` is [`None`].
+    ///
+    /// Returns [`None`] if any of the specified [`Snapshot`]s doesn't exist in
+    /// the [`State::history`].
+    #[must_use]
+    pub fn diff_snapshots(
+        &self,
+        from: SnapshotId,
+        to: Option<SnapshotId>,
+    ) -> Option<Vec<SpecChange>> {
+        let history = self.history.lock_ref();
+        let find = |id| history.iter().find(|s| s.id == id).map(|s| &s.spec);
+
+        let before = find(from)?;
+        let current;
+        let after = if let Some(to) = to {
+            find(to)?
+        } else {
+            current = self.export().into_v2();
+            &current
+        };
+        Some(SpecChange::diff(before, after))
+    }
+
     /// Subscribes the specified `hook` to changes of the [`Mutable`] `val`ue.
     ///
     /// `name` is just a convenience for describing the `hook` in logs.
@@ -273,6 +416,22 @@ impl State {
         (clients.len() != prev_len).then_some(())
     }
 
+    /// Indicates whether the given UDP `port` is used already by an
+    /// [`SrtListener`] of any [`Restream`] in this [`State`], except the one
+    /// with the `except` ID.
+    #[must_use]
+    pub fn is_srt_port_taken(
+        &self,
+        port: UNumber,
+        except: Option<RestreamId>,
+    ) -> bool {
+        self.restreams
+            .lock_ref()
+            .iter()
+            .filter(|r| Some(r.id) != except)
+            .any(|r| r.input.uses_srt_port(port))
+    }
+
     /// Adds a new [`Restream`] by the given `spec` to this [`State`].
     ///
     /// # Errors
@@ -280,7 +439,7 @@ impl State {
     /// If this [`State`] has a [`Restream`] with such `key` already.
     pub fn add_restream(
         &self,
-        spec: spec::v1::Restream,
+        spec: spec::v2::Restream,
     ) -> anyhow::Result<Option<RestreamId>> {
         let mut restreams = self.restreams.lock_mut();
 
@@ -306,7 +465,7 @@ impl State {
     pub fn edit_restream(
         &self,
         id: RestreamId,
-        spec: spec::v1::Restream,
+        mut spec: spec::v2::Restream,
     ) -> anyhow::Result<Option<RestreamId>> {
         let mut restreams = self.restreams.lock_mut();
 
@@ -314,10 +473,11 @@ impl State {
             return Err(anyhow!("Restream.key '{}' is used already", spec.key));
         }
 
-        _ = restreams
-            .iter_mut()
-            .find(|r| r.id == id)
-            .map(|r| r.apply(spec, false));
+        _ = restreams.iter_mut().find(|r| r.id == id).map(|r| {
+            // Recording of `Input`s is switched by `setInputRecording` only.
+            r.input.keep_recording(&mut spec.input);
+            r.apply(spec, false);
+        });
 
         Ok(Some(id))
     }
@@ -398,6 +558,27 @@ impl State {
             .map(Input::disable)
     }
 
+    /// Enables or disables recording of an [`Input`] with the given `id` in
+    /// the specified [`Restream`] of this [`State`].
+    ///
+    /// Returns `true` if it has been changed, or `false` if it already has
+    /// been set so, or [`None`] if it doesn't exist.
+    #[must_use]
+    pub fn set_input_recording(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+        record: bool,
+    ) -> Option<bool> {
+        self.restreams
+            .lock_mut()
+            .iter_mut()
+            .find(|r| r.id == restream_id)?
+            .input
+            .find_mut(id)
+            .map(|i| i.set_recording(record))
+    }
+
     /// Moves this [`Input`] in given direction.
     ///
     /// This may affect the order and priority of endpoints.
@@ -507,7 +688,7 @@ impl State {
     pub fn add_output(
         &self,
         restream_id: RestreamId,
-        spec: spec::v1::Output,
+        spec: spec::v2::Output,
     ) -> anyhow::Result<Option<OutputId>> {
         let mut restreams = self.restreams.lock_mut();
 
@@ -543,7 +724,7 @@ impl State {
         &self,
         restream_id: RestreamId,
         id: OutputId,
-        spec: spec::v1::Output,
+        spec: spec::v2::Output,
     ) -> anyhow::Result<Option<OutputId>> {
         let mut restreams = self.restreams.lock_mut();
 
@@ -611,6 +792,7 @@ impl State {
         }
 
         output.enabled = true;
+        output.disabled_reason = None;
         Some(true)
     }
 
@@ -633,6 +815,8 @@ impl State {
             .iter_mut()
             .find(|o| o.id == id)?;
 
+        // Disabled explicitly now, so the automatic reason is not relevant.
+        output.disabled_reason = None;
         if !output.enabled {
             return Some(false);
         }
@@ -641,6 +825,243 @@ impl State {
         Some(true)
     }
 
+    /// Disables an [`Output`] with the given `id` in any [`Restream`] of this
+    /// [`State`] automatically, remembering the `reason` of doing so.
+    ///
+    /// Returns `false` if it doesn't exist or is disabled already.
+    pub fn auto_disable_output(&self, id: OutputId, reason: String) -> bool {
+        let mut restreams = self.restreams.lock_mut();
+        let Some(output) = restreams
+            .iter_mut()
+            .flat_map(|r| r.outputs.iter_mut())
+            .find(|o| o.id == id && o.enabled)
+        else {
+            return false;
+        };
+
+        output.enabled = false;
+        output.disabled_reason = Some(reason);
+        true
+    }
+
+    /// Returns the [`RetryPolicy`] of restarting a re-streaming process with
+    /// the given `id` in this [`State`].
+    ///
+    /// Falls back to the global [`Settings::retry_policy`] (or the default
+    /// one) if the process doesn't belong to an [`Output`] having its own
+    /// [`RetryPolicy`].
+    #[must_use]
+    pub fn retry_policy(&self, id: Uuid) -> RetryPolicy {
+        let id = OutputId::from(id);
+        self.restreams
+            .lock_ref()
+            .iter()
+            .flat_map(|r| r.outputs.iter())
+            .find(|o| o.id == id)
+            .and_then(|o| o.retry_policy)
+            .or(self.settings.lock_ref().retry_policy)
+            .unwrap_or_default()
+    }
+
+    /// Adds a new [`Schedule`] to the specified [`Restream`] of this [`State`],
+    /// or to its [`Output`] if `output_id` is specified.
+    ///
+    /// If `id` is specified, then the existing [`Schedule`] is edited instead.
+    ///
+    /// Returns [`None`] if the specified [`Restream`], [`Output`] or
+    /// [`Schedule`] doesn't exist.
+    #[must_use]
+    pub fn set_schedule(
+        &self,
+        restream_id: RestreamId,
+        output_id: Option<OutputId>,
+        id: Option<ScheduleId>,
+        spec: spec::v2::Schedule,
+    ) -> Option<ScheduleId> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams.iter_mut().find(|r| r.id == restream_id)?;
+        let schedules = if let Some(output_id) = output_id {
+            &mut restream
+                .outputs
+                .iter_mut()
+                .find(|o| o.id == output_id)?
+                .schedules
+        } else {
+            &mut restream.schedules
+        };
+
+        if let Some(id) = id {
+            let schedule = schedules.iter_mut().find(|s| s.id == id)?;
+            schedule.start = spec.start;
+            schedule.end = spec.end;
+            schedule.repeat = spec.repeat;
+            Some(id)
+        } else {
+            let schedule = Schedule::new(spec);
+            let id = schedule.id;
+            schedules.push(schedule);
+            Some(id)
+        }
+    }
+
+    /// Removes a [`Schedule`] with the given `id` from the specified
+    /// [`Restream`] of this [`State`], or from its [`Output`] if `output_id` is
+    /// specified.
+    ///
+    /// Returns [`None`] if the specified [`Restream`], [`Output`] or
+    /// [`Schedule`] doesn't exist.
+    #[allow(clippy::must_use_candidate)]
+    pub fn remove_schedule(
+        &self,
+        restream_id: RestreamId,
+        output_id: Option<OutputId>,
+        id: ScheduleId,
+    ) -> Option<()> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams.iter_mut().find(|r| r.id == restream_id)?;
+        let schedules = if let Some(output_id) = output_id {
+            &mut restream
+                .outputs
+                .iter_mut()
+                .find(|o| o.id == output_id)?
+                .schedules
+        } else {
+            &mut restream.schedules
+        };
+
+        let prev_len = schedules.len();
+        schedules.retain(|s| s.id != id);
+        (schedules.len() != prev_len).then_some(())
+    }
+
+    /// Clears recorded [`StreamFailure`]s of the specified [`Output`] or
+    /// [`InputEndpoint`] in the specified [`Restream`] of this [`State`].
+    ///
+    /// If neither `output_id`, nor `endpoint_id` is specified, then clears
+    /// [`StreamFailure`]s of all the [`Output`]s and [`InputEndpoint`]s of the
+    /// [`Restream`].
+    ///
+    /// Returns `true` if any [`StreamFailure`]s have been cleared, `false` if
+    /// there were none, or [`None`] if the specified [`Restream`], [`Output`]
+    /// or [`InputEndpoint`] doesn't exist.
+    #[must_use]
+    pub fn clear_failures(
+        &self,
+        restream_id: RestreamId,
+        output_id: Option<OutputId>,
+        endpoint_id: Option<EndpointId>,
+    ) -> Option<bool> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams.iter_mut().find(|r| r.id == restream_id)?;
+
+        let mut cleared = false;
+        let mut clear = |failures: &mut Vec<StreamFailure>| {
+            cleared |= !failures.is_empty();
+            failures.clear();
+        };
+        match (output_id, endpoint_id) {
+            (None, None) => {
+                for o in &mut restream.outputs {
+                    clear(&mut o.failures);
+                }
+                for e in &mut restream.input.endpoints {
+                    clear(&mut e.failures);
+                }
+                if let Some(InputSrc::Failover(s)) = &mut restream.input.src {
+                    for e in
+                        s.inputs.iter_mut().flat_map(|i| i.endpoints.iter_mut())
+                    {
+                        clear(&mut e.failures);
+                    }
+                }
+            }
+            (output_id, endpoint_id) => {
+                if let Some(id) = output_id {
+                    let o = restream.outputs.iter_mut().find(|o| o.id == id)?;
+                    clear(&mut o.failures);
+                }
+                if let Some(id) = endpoint_id {
+                    clear(&mut restream.input.find_endpoint(id)?.failures);
+                }
+            }
+        }
+        Some(cleared)
+    }
+
+    /// Returns all the [`ScheduledAction`]s of [`Restream`]s and [`Output`]s
+    /// of this [`State`] happening within the `(from, to]` time range, ordered
+    /// by their time.
+    #[must_use]
+    pub fn scheduled_actions_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<ScheduledAction> {
+        self.scheduled_actions(|s| s.actions_between(from, to))
+    }
+
+    /// Returns all the [`ScheduledAction`]s happened since the
+    /// [`State::schedules_checked_at`] moment till the given `now` one, and
+    /// marks [`Schedule`]s as checked at `now`.
+    ///
+    /// Returns nothing if [`Schedule`]s have never been checked before.
+    #[must_use]
+    pub fn check_scheduled_actions(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Vec<ScheduledAction> {
+        self.schedules_checked_at
+            .replace(Some(now))
+            .map(|from| self.scheduled_actions_between(from, now))
+            .unwrap_or_default()
+    }
+
+    /// Returns the nearest upcoming [`ScheduledAction`]s of [`Restream`]s and
+    /// [`Output`]s of this [`State`], ordered by their time.
+    #[must_use]
+    pub fn upcoming_scheduled_actions(
+        &self,
+        limit: usize,
+    ) -> Vec<ScheduledAction> {
+        let now = Utc::now();
+        let mut actions = self.scheduled_actions(|s| s.next_actions(now));
+        actions.truncate(limit);
+        actions
+    }
+
+    /// Collects [`ScheduledAction`]s produced by the given function out of all
+    /// the [`Schedule`]s of this [`State`], ordered by their time.
+    fn scheduled_actions<F>(&self, actions_of: F) -> Vec<ScheduledAction>
+    where
+        F: Fn(&Schedule) -> Vec<(DateTime<Utc>, ScheduleAction)>,
+    {
+        let restreams = self.restreams.lock_ref();
+        let targets = restreams.iter().flat_map(|r| {
+            iter::once((r.id, None, &r.schedules)).chain(
+                r.outputs
+                    .iter()
+                    .map(move |o| (r.id, Some(o.id), &o.schedules)),
+            )
+        });
+
+        let mut actions = vec![];
+        for (restream_id, output_id, schedules) in targets {
+            for s in schedules {
+                actions.extend(actions_of(s).into_iter().map(
+                    |(at, action)| ScheduledAction {
+                        at,
+                        action,
+                        schedule_id: s.id,
+                        restream_id,
+                        output_id,
+                    },
+                ));
+            }
+        }
+        actions.sort_by_key(|a| a.at);
+        actions
+    }
+
     /// Get [Output] from [Restream] by `restream_id` and `output_id`
     #[must_use]
     pub fn get_output(
@@ -712,6 +1133,155 @@ impl State {
         volume: Volume,
     ) -> Option<bool> {
         let mut restreams = self.restreams.lock_mut();
+        let curr_volume =
+            Self::volume_mut(&mut restreams, restream_id, output_id, mixin_id)?;
+        _ = self.next_volume_fade(output_id, mixin_id);
+
+        // `Volume::solo` is tuned separately, so shouldn't be lost here.
+        let volume = Volume {
+            solo: curr_volume.solo,
+            ..volume
+        };
+        if *curr_volume == volume {
+            return Some(false);
+        }
+
+        *curr_volume = volume;
+        Some(true)
+    }
+
+    /// Tunes a [`Volume::solo`] flag of the specified [`Output`] or its
+    /// [`Mixin`] in this [`State`], keeping its [`Volume`] rate.
+    ///
+    /// Returns `true` if a [`Volume::solo`] flag has been changed, or `false`
+    /// if it has the same value already.
+    ///
+    /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
+    #[must_use]
+    pub fn tune_solo(
+        &self,
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: Option<MixinId>,
+        solo: bool,
+    ) -> Option<bool> {
+        let mut restreams = self.restreams.lock_mut();
+        let volume =
+            Self::volume_mut(&mut restreams, restream_id, output_id, mixin_id)?;
+
+        if volume.solo == solo {
+            return Some(false);
+        }
+
+        volume.solo = solo;
+        Some(true)
+    }
+
+    /// Fades a [`Volume`] rate of the specified [`Output`] or its [`Mixin`]
+    /// in this [`State`] to the `target` one smoothly, during the given
+    /// `duration`.
+    ///
+    /// A muted [`Volume`] is unmuted and faded in from [`VolumeLevel::OFF`].
+    /// Fading is performed in a background task by small steps, and stops
+    /// once a new fading or tuning of the same [`Volume`] happens, or it's
+    /// changed by anything else.
+    ///
+    /// Returns `true` if a fading has been started, or `false` if the
+    /// [`Volume`] has the `target` rate already.
+    ///
+    /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
+    #[must_use]
+    pub fn fade_volume(
+        &self,
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: Option<MixinId>,
+        target: VolumeLevel,
+        duration: Duration,
+    ) -> Option<bool> {
+        /// Interval between steps of a fading.
+        const STEP: Duration = Duration::from_millis(100);
+
+        let (start, fade) = {
+            let mut restreams = self.restreams.lock_mut();
+            let volume = Self::volume_mut(
+                &mut restreams,
+                restream_id,
+                output_id,
+                mixin_id,
+            )?;
+            if !volume.muted && volume.level == target {
+                return Some(false);
+            }
+            let start = if volume.muted {
+                VolumeLevel::OFF
+            } else {
+                volume.level
+            };
+            volume.level = start;
+            volume.muted = false;
+            (start, self.next_volume_fade(output_id, mixin_id))
+        };
+
+        let steps = u32::try_from(duration.as_millis() / STEP.as_millis())
+            .unwrap_or(u32::MAX)
+            .max(1);
+        let (from, to) = (i64::from(start.0), i64::from(target.0));
+        let state = self.clone();
+        drop(tokio::spawn(async move {
+            let mut level = start;
+            for step in 1..=steps {
+                tokio::time::sleep(STEP).await;
+
+                let mut restreams = state.restreams.lock_mut();
+                let Some(volume) = Self::volume_mut(
+                    &mut restreams,
+                    restream_id,
+                    output_id,
+                    mixin_id,
+                ) else {
+                    break;
+                };
+                if volume.muted
+                    || volume.level != level
+                    || state.volume_fades.lock_ref().get(&(output_id, mixin_id))
+                        != Some(&fade)
+                {
+                    // Changed by someone else, so fading is not actual.
+                    break;
+                }
+                let next =
+                    from + (to - from) * i64::from(step) / i64::from(steps);
+                level = VolumeLevel::new(next).unwrap_or(target);
+                volume.level = level;
+            }
+        }));
+        Some(true)
+    }
+
+    /// Starts a new generation of fadings of a [`Volume`] of the specified
+    /// [`Output`] or its [`Mixin`], making the running one outdated.
+    ///
+    /// Returns the started generation.
+    fn next_volume_fade(
+        &self,
+        output_id: OutputId,
+        mixin_id: Option<MixinId>,
+    ) -> u64 {
+        let mut fades = self.volume_fades.lock_mut();
+        let fade = fades.entry((output_id, mixin_id)).or_default();
+        *fade += 1;
+        *fade
+    }
+
+    /// Looks up a [`Volume`] of the specified [`Output`] or its [`Mixin`] in
+    /// the given [`Restream`]s.
+    fn volume_mut(
+        restreams: &mut [Restream],
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: Option<MixinId>,
+    ) -> Option<&mut Volume> {
         let output = restreams
             .iter_mut()
             .find(|r| r.id == restream_id)?
@@ -719,18 +1289,11 @@ impl State {
             .iter_mut()
             .find(|o| o.id == output_id)?;
 
-        let curr_volume = if let Some(id) = mixin_id {
+        Some(if let Some(id) = mixin_id {
             &mut output.mixins.iter_mut().find(|m| m.id == id)?.volume
         } else {
             &mut output.volume
-        };
-
-        if *curr_volume == volume {
-            return Some(false);
-        }
-
-        *curr_volume = volume;
-        Some(true)
+        })
     }
 
     /// Tunes a [`Delay`] of the specified [`Mixin`] in this [`State`].
@@ -768,6 +1331,9 @@ impl State {
 
     /// Tunes a the specified [`Mixin.sidechain`] in this [`State`].
     ///
+    /// If no `params` are specified for an enabled `sidechain`, then the
+    /// current [`Sidechain`] parameters are kept, or the default ones are used.
+    ///
     /// Returns `true` if a [`Mixin.sidechain`] has been changed, or `false`
     /// if it has the same value already.
     ///
@@ -779,6 +1345,7 @@ impl State {
         output_id: OutputId,
         mixin_id: MixinId,
         sidechain: bool,
+        params: Option<Sidechain>,
     ) -> Option<bool> {
         let mut restreams = self.restreams.lock_mut();
         let mixin = restreams
@@ -791,6 +1358,8 @@ impl State {
             .iter_mut()
             .find(|m| m.id == mixin_id)?;
 
+        let sidechain =
+            sidechain.then(|| params.or(mixin.sidechain).unwrap_or_default());
         if mixin.sidechain == sidechain {
             return Some(false);
         }
@@ -799,6 +1368,74 @@ impl State {
         Some(true)
     }
 
+    /// Renews [`Mixin::error`] of the specified [`Mixin`] in this [`State`],
+    /// if it differs from the given `error`.
+    pub fn renew_mixin_error(
+        &self,
+        output_id: OutputId,
+        mixin_id: MixinId,
+        error: Option<String>,
+    ) {
+        let is_same = self
+            .restreams
+            .lock_ref()
+            .iter()
+            .flat_map(|r| &r.outputs)
+            .filter(|o| o.id == output_id)
+            .flat_map(|o| &o.mixins)
+            .find(|m| m.id == mixin_id)
+            .map_or(true, |m| m.error == error);
+        // Avoid notifying subscribers if nothing has changed.
+        if is_same {
+            return;
+        }
+
+        if let Some(mixin) = self
+            .restreams
+            .lock_mut()
+            .iter_mut()
+            .flat_map(|r| &mut r.outputs)
+            .filter(|o| o.id == output_id)
+            .flat_map(|o| &mut o.mixins)
+            .find(|m| m.id == mixin_id)
+        {
+            mixin.error = error;
+        }
+    }
+
+    /// Tunes a the specified [`Mixin::speakers`] in this [`State`].
+    ///
+    /// Returns `true` if a [`Mixin::speakers`] has been changed, or `false`
+    /// if it has the same value already.
+    ///
+    /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
+    #[must_use]
+    pub fn tune_speakers(
+        &self,
+        input_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: MixinId,
+        speakers: SpeakerFilter,
+    ) -> Option<bool> {
+        let mut restreams = self.restreams.lock_mut();
+        let mixin = restreams
+            .iter_mut()
+            .find(|r| r.id == input_id)?
+            .outputs
+            .iter_mut()
+            .find(|o| o.id == output_id)?
+            .mixins
+            .iter_mut()
+            .find(|m| m.id == mixin_id)?;
+
+        if mixin.speakers == speakers {
+            return Some(false);
+        }
+
+        mixin.speakers = speakers;
+        Some(true)
+    }
+
     /// Syncronize stream statistics
     pub fn sync_stream_info(&self) {
         let files = self.files.lock_mut();
@@ -961,8 +1598,12 @@ impl State {
                 .find(|r| r.id == restream_id)?
                 .outputs
                 .iter_mut()
-                .filter(|o| o.enabled != enabled)
-                .fold(false, |_, o| {
+                .fold(false, |changed, o| {
+                    // Already disabled ones are disabled explicitly now too.
+                    o.disabled_reason = None;
+                    if o.enabled == enabled {
+                        return changed;
+                    }
                     o.enabled = enabled;
                     true
                 }),
@@ -976,8 +1617,12 @@ impl State {
         restreams
             .iter_mut()
             .flat_map(|r| r.outputs.iter_mut())
-            .filter(|o| o.enabled != enabled)
-            .fold(false, |_, o| {
+            .fold(false, |changed, o| {
+                // Already disabled ones are disabled explicitly now too.
+                o.disabled_reason = None;
+                if o.enabled == enabled {
+                    return changed;
+                }
                 o.enabled = enabled;
                 true
             })
@@ -1030,7 +1675,7 @@ pub struct Playlist {
 impl Playlist {
     /// Creates new [`Playlist`] from spec
     #[must_use]
-    pub fn new(spec: Option<spec::v1::Playlist>) -> Playlist {
+    pub fn new(spec: Option<spec::v2::Playlist>) -> Playlist {
         let mut playlist = Self {
             id: PlaylistId::random(),
             queue: vec![],
@@ -1047,7 +1692,7 @@ impl Playlist {
     /// Apply new playlist to this one
     pub fn apply(
         &mut self,
-        queue_spec: Vec<spec::v1::PlaylistFileInfo>,
+        queue_spec: Vec<spec::v2::PlaylistFileInfo>,
         replace: bool,
     ) {
         if replace {
@@ -1060,7 +1705,7 @@ impl Playlist {
                 })
                 .collect();
         } else {
-            for spec::v1::PlaylistFileInfo { file_id, name } in queue_spec {
+            for spec::v2::PlaylistFileInfo { file_id, name } in queue_spec {
                 if !self.queue.iter().any(|x| x.file_id == file_id) {
                     self.queue.push(PlaylistFileInfo {
                         file_id,
@@ -1073,15 +1718,15 @@ impl Playlist {
         self.currently_playing_file = None;
     }
 
-    /// Exports this [`Playlist`] as a [`spec::v1::Playlist`].
+    /// Exports this [`Playlist`] as a [`spec::v2::Playlist`].
     #[must_use]
-    pub fn export(&self) -> spec::v1::Playlist {
-        spec::v1::Playlist {
+    pub fn export(&self) -> spec::v2::Playlist {
+        spec::v2::Playlist {
             queue: self
                 .queue
                 .clone()
                 .into_iter()
-                .map(|x| spec::v1::PlaylistFileInfo {
+                .map(|x| spec::v2::PlaylistFileInfo {
                     name: x.name,
                     file_id: x.file_id,
                 })
@@ -1129,3 +1774,209 @@ pub enum Direction {
     /// Down
     Down,
 }
+
+#[cfg(test)]
+mod scheduled_actions_spec {
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+
+    use crate::spec;
+
+    use super::{ScheduleAction, State};
+
+    fn t(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    /// Creates a [`State`] with an enabled `Output` having a one-shot
+    /// `Schedule` window from 10:00 till 12:00 of 2024-01-01.
+    fn state() -> State {
+        let schedule = json!({
+            "start": "2024-01-01T10:00:00Z",
+            "end": "2024-01-01T12:00:00Z",
+        });
+        let output = json!({
+            "dst": "rtmp://example.com/live/en",
+            "enabled": true,
+            "schedules": [schedule],
+        });
+        let spec: spec::v2::Spec = serde_json::from_value(json!({
+            "restreams": [{
+                "key": "en",
+                "input": {"key": "origin", "endpoints": [{"kind": "rtmp"}]},
+                "outputs": [output],
+            }],
+        }))
+        .unwrap();
+
+        let state = State::default();
+        state.apply(spec, true);
+        state
+    }
+
+    #[test]
+    fn skips_actions_performed_before_restart() {
+        let state = state();
+        state
+            .schedules_checked_at
+            .set(Some(t("2024-01-01T12:00:01Z")));
+
+        let now = t("2024-03-01T00:00:00Z");
+        assert_eq!(state.check_scheduled_actions(now), vec![]);
+        assert_eq!(state.schedules_checked_at.get(), Some(now));
+    }
+
+    #[test]
+    fn performs_actions_missed_while_down() {
+        let state = state();
+        state
+            .schedules_checked_at
+            .set(Some(t("2024-01-01T11:00:00Z")));
+
+        let actions = state.check_scheduled_actions(t("2024-03-01T00:00:00Z"));
+        assert_eq!(
+            actions.iter().map(|a| (a.at, a.action)).collect::<Vec<_>>(),
+            vec![(t("2024-01-01T12:00:00Z"), ScheduleAction::Disable)],
+        );
+    }
+
+    #[test]
+    fn performs_nothing_on_first_check() {
+        let state = state();
+
+        let now = t("2024-03-01T00:00:00Z");
+        assert_eq!(state.check_scheduled_actions(now), vec![]);
+        assert_eq!(state.schedules_checked_at.get(), Some(now));
+    }
+}
+
+#[cfg(test)]
+mod volume_fade_spec {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::spec;
+
+    use super::{State, VolumeLevel};
+
+    #[tokio::test]
+    async fn new_fade_cancels_running_one() {
+        let spec: spec::v2::Spec = serde_json::from_value(json!({
+            "restreams": [{
+                "key": "en",
+                "input": {"key": "origin", "endpoints": [{"kind": "rtmp"}]},
+                "outputs": [{"dst": "rtmp://example.com/live/en"}],
+            }],
+        }))
+        .unwrap();
+        let state = State::default();
+        state.apply(spec, true);
+        let (restream_id, output_id) = {
+            let restreams = state.restreams.lock_ref();
+            (restreams[0].id, restreams[0].outputs[0].id)
+        };
+        let level = || state.restreams.lock_ref()[0].outputs[0].volume.level;
+
+        let fade = |target, millis| {
+            state.fade_volume(
+                restream_id,
+                output_id,
+                None,
+                VolumeLevel::new(target).unwrap(),
+                Duration::from_millis(millis),
+            )
+        };
+
+        assert_eq!(fade(0_u16, 1000), Some(true));
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(level() < VolumeLevel::ORIGIN);
+
+        assert_eq!(fade(100_u16, 300), Some(true));
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+        assert_eq!(level(), VolumeLevel::ORIGIN);
+    }
+}
+
+#[cfg(test)]
+mod export_spec {
+    use serde_json::json;
+
+    use crate::spec;
+
+    use super::State;
+
+    /// Creates a [`State`] with an `Output` mixed with a [TeamSpeak] channel.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    fn state() -> State {
+        let spec: spec::v2::Spec = serde_json::from_value(json!({
+            "restreams": [{
+                "key": "en",
+                "input": {"key": "origin", "endpoints": [{"kind": "rtmp"}]},
+                "outputs": [{
+                    "dst": "rtmp://example.com/live/en",
+                    "mixins": [{"src": "ts://example.com/Event?name=EN"}],
+                }],
+            }],
+        }))
+        .unwrap();
+
+        let state = State::default();
+        state.apply(spec, true);
+        state
+    }
+
+    fn identity(state: &State) -> Option<String> {
+        state.restreams.lock_ref()[0].outputs[0].mixins[0]
+            .teamspeak_identity
+            .clone()
+    }
+
+    /// Exports the given [`State`] into a JSON spec and parses it back.
+    fn export(state: &State, with_secrets: bool) -> spec::v2::Spec {
+        let restreams = state
+            .restreams
+            .get_cloned()
+            .iter()
+            .map(|r| {
+                if with_secrets {
+                    r.export_with_secrets()
+                } else {
+                    r.export()
+                }
+            })
+            .collect();
+        let json = serde_json::to_string(&spec::v2::Spec {
+            settings: None,
+            restreams,
+        })
+        .unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn exports_teamspeak_identity_only_with_secrets() {
+        let state = state();
+
+        let mixin = &export(&state, false).restreams[0].outputs[0].mixins[0];
+        assert_eq!(mixin.teamspeak_identity, None);
+
+        let mixin = &export(&state, true).restreams[0].outputs[0].mixins[0];
+        assert!(mixin.teamspeak_identity.is_some());
+        assert_eq!(mixin.teamspeak_identity, identity(&state));
+    }
+
+    #[test]
+    fn teamspeak_identity_survives_apply() {
+        let orig = state();
+
+        let imported = State::default();
+        imported.apply(export(&orig, true), true);
+        assert!(identity(&imported).is_some());
+        assert_eq!(identity(&imported), identity(&orig));
+
+        imported.apply(export(&orig, false), true);
+        assert_eq!(identity(&imported), identity(&orig));
+    }
+}
@@ -0,0 +1,141 @@
+//! Policy of retaining recorded DVR files.
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::{spec, types::UNumber};
+
+/// Policy of retaining recorded [DVR] files, enforced periodically by
+/// removing the oldest ones.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct DvrRetention {
+    /// Maximum age of a recorded file, in hours.
+    ///
+    /// If `null`, then files are not removed because of their age.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<UNumber>,
+
+    /// Maximum total size of files recorded by a single `Output` (or `Input`),
+    /// in gigabytes.
+    ///
+    /// If `null`, then the size is not limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_size: Option<UNumber>,
+
+    /// Maximum total size of all the recorded files, in gigabytes.
+    ///
+    /// If `null`, then the size is not limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_size: Option<UNumber>,
+
+    /// Minimum free space on the disk storing recorded files, in gigabytes.
+    ///
+    /// Once the free space goes below it, recording of all the `Output`s and
+    /// `Input`s is suspended until enough space is freed.
+    /// If `0`, then the free space is not watched.
+    pub min_free_space: UNumber,
+}
+
+impl DvrRetention {
+    /// Creates a new [`DvrRetention`] out of the given
+    /// [`spec::v2::DvrRetention`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::DvrRetention) -> Self {
+        Self {
+            max_age: spec.max_age,
+            max_output_size: spec.max_output_size,
+            max_total_size: spec.max_total_size,
+            min_free_space: spec.min_free_space,
+        }
+    }
+
+    /// Exports this [`DvrRetention`] as a [`spec::v2::DvrRetention`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::DvrRetention {
+        spec::v2::DvrRetention {
+            max_age: self.max_age,
+            max_output_size: self.max_output_size,
+            max_total_size: self.max_total_size,
+            min_free_space: self.min_free_space,
+        }
+    }
+
+    /// Validates invariants of the given [`spec::v2::DvrRetention`]: its
+    /// limits should be positive, and a single `Output` shouldn't be allowed
+    /// to use more space than all of them.
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    pub fn validate(spec: &spec::v2::DvrRetention) -> Result<(), String> {
+        for (name, limit) in [
+            ("maxAge", spec.max_age),
+            ("maxOutputSize", spec.max_output_size),
+            ("maxTotalSize", spec.max_total_size),
+        ] {
+            if limit.map_or(false, |l| l.0 == 0) {
+                return Err(format!("DvrRetention.{name} should be positive"));
+            }
+        }
+        if let (Some(output), Some(total)) =
+            (spec.max_output_size, spec.max_total_size)
+        {
+            if output.0 > total.0 {
+                return Err("DvrRetention.maxOutputSize should not exceed \
+                            DvrRetention.maxTotalSize"
+                    .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the [`DvrRetention::max_age`] in seconds, if any.
+    #[inline]
+    #[must_use]
+    pub fn max_age_secs(&self) -> Option<u64> {
+        self.max_age.map(|h| u64::from(h.0) * 3600)
+    }
+
+    /// Returns the [`DvrRetention::max_output_size`] in bytes, if any.
+    #[inline]
+    #[must_use]
+    pub fn max_output_bytes(&self) -> Option<u64> {
+        self.max_output_size.map(|s| gigabytes(s.0))
+    }
+
+    /// Returns the [`DvrRetention::max_total_size`] in bytes, if any.
+    #[inline]
+    #[must_use]
+    pub fn max_total_bytes(&self) -> Option<u64> {
+        self.max_total_size.map(|s| gigabytes(s.0))
+    }
+
+    /// Returns the [`DvrRetention::min_free_space`] in bytes.
+    #[inline]
+    #[must_use]
+    pub fn min_free_bytes(&self) -> u64 {
+        gigabytes(self.min_free_space.0)
+    }
+}
+
+impl Default for DvrRetention {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            max_output_size: None,
+            max_total_size: None,
+            min_free_space: UNumber(1),
+        }
+    }
+}
+
+/// Converts the given number of gigabytes into bytes.
+fn gigabytes(gb: u16) -> u64 {
+    u64::from(gb) << 30
+}
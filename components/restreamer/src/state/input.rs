@@ -2,8 +2,12 @@ mod input_endpoint;
 mod input_src;
 
 pub use self::{
-    input_endpoint::{EndpointId, InputEndpoint, InputEndpointKind},
-    input_src::{FailoverInputSrc, InputSrc, InputSrcUrl, RemoteInputSrc},
+    input_endpoint::{
+        EndpointId, InputEndpoint, InputEndpointKind, SrtListener,
+    },
+    input_src::{
+        FailoverInputSrc, InputSrc, InputSrcUrl, RemoteInputSrc, RtspTransport,
+    },
 };
 
 use std::{borrow::Cow, mem};
@@ -16,7 +20,7 @@ use regex::Regex;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use uuid::Uuid;
 
-use crate::{spec, state::Status};
+use crate::{spec, state::Status, types::UNumber};
 
 /// Upstream source that a `Restream` receives a live stream from.
 #[derive(
@@ -48,12 +52,17 @@ pub struct Input {
     /// live stream from its upstream sources.
     #[serde(default, skip_serializing_if = "is_false")]
     pub enabled: bool,
+
+    /// Indicator whether a live stream received by this `Input` should be
+    /// recorded into DVR files, along with `Output`s.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub record: bool,
 }
 
 impl Input {
-    /// Creates a new [`Input`] out of the given [`spec::v1::Input`].
+    /// Creates a new [`Input`] out of the given [`spec::v2::Input`].
     #[must_use]
-    pub fn new(spec: spec::v1::Input) -> Self {
+    pub fn new(spec: spec::v2::Input) -> Self {
         Self {
             id: InputId::random(),
             key: spec.key,
@@ -64,11 +73,12 @@ impl Input {
                 .collect(),
             src: spec.src.map(InputSrc::new),
             enabled: spec.enabled,
+            record: spec.record,
         }
     }
 
-    /// Applies the given [`spec::v1::Input`] to this [`Input`].
-    pub fn apply(&mut self, new: spec::v1::Input) {
+    /// Applies the given [`spec::v2::Input`] to this [`Input`].
+    pub fn apply(&mut self, new: spec::v2::Input) {
         if self.key != new.key
             || !new.enabled
             || (self.src.is_none() && new.src.is_some())
@@ -86,6 +96,7 @@ impl Input {
         // Temporary omit changing existing `enabled` value to avoid unexpected
         // breakages of ongoing re-streams.
         //self.enabled = new.enabled;
+        self.record = new.record;
 
         let mut olds = mem::replace(
             &mut self.endpoints,
@@ -115,10 +126,10 @@ impl Input {
         }
     }
 
-    /// Exports this [`Input`] as a [`spec::v1::Input`].
+    /// Exports this [`Input`] as a [`spec::v2::Input`].
     #[must_use]
-    pub fn export(&self) -> spec::v1::Input {
-        spec::v1::Input {
+    pub fn export(&self) -> spec::v2::Input {
+        spec::v2::Input {
             id: Some(self.id),
             key: self.key.clone(),
             endpoints: self
@@ -128,9 +139,21 @@ impl Input {
                 .collect(),
             src: self.src.as_ref().map(InputSrc::export),
             enabled: self.enabled,
+            record: self.record,
         }
     }
 
+    /// Indicates whether the given UDP `port` is used by an [`SrtListener`] of
+    /// this [`Input`] or any of its failover [`Input`]s.
+    #[must_use]
+    pub fn uses_srt_port(&self, port: UNumber) -> bool {
+        self.endpoints
+            .iter()
+            .any(|e| e.srt.as_ref().map_or(false, |l| l.port == port))
+            || matches!(&self.src, Some(InputSrc::Failover(s))
+                if s.inputs.iter().any(|i| i.uses_srt_port(port)))
+    }
+
     /// Enables this [`Input`].
     ///
     /// Returns `false` if it has been enabled already.
@@ -174,6 +197,60 @@ impl Input {
         changed
     }
 
+    /// Sets the [`Input::record`] indicator of this [`Input`].
+    ///
+    /// Returns `false` if it has been set to the given value already.
+    #[must_use]
+    pub fn set_recording(&mut self, record: bool) -> bool {
+        let changed = self.record != record;
+        self.record = record;
+        changed
+    }
+
+    /// Applies [`Input::enabled`] and [`Input::record`] switches of the given
+    /// [`spec::v2::Input`] to this [`Input`] and its failover [`Input`]s, as
+    /// the former ones are left intact by [`Input::apply()`].
+    pub fn apply_switches(&mut self, new: &spec::v2::Input) {
+        if new.enabled {
+            self.enabled = true;
+        } else {
+            _ = self.disable();
+        }
+        _ = self.set_recording(new.record);
+
+        if let (
+            Some(InputSrc::Failover(s)),
+            Some(spec::v2::InputSrc::FailoverInputs(news)),
+        ) = (self.src.as_mut(), &new.src)
+        {
+            for new in news {
+                if let Some(i) = s.inputs.iter_mut().find(|i| i.key == new.key)
+                {
+                    i.apply_switches(new);
+                }
+            }
+        }
+    }
+
+    /// Copies [`Input::record`] switches of this [`Input`] and its failover
+    /// [`Input`]s (matched by their keys) into the given [`spec::v2::Input`],
+    /// so applying it doesn't interrupt ongoing recordings.
+    pub fn keep_recording(&self, spec: &mut spec::v2::Input) {
+        spec.record = self.record;
+
+        if let (
+            Some(InputSrc::Failover(s)),
+            Some(spec::v2::InputSrc::FailoverInputs(news)),
+        ) = (&self.src, &mut spec.src)
+        {
+            for new in news {
+                if let Some(i) = s.inputs.iter().find(|i| i.key == new.key) {
+                    i.keep_recording(new);
+                }
+            }
+        }
+    }
+
     /// Lookups for an [`Input`] with the given `id` inside this [`Input`] or
     /// its [`FailoverInputSrc::inputs`].
     #[must_use]
@@ -332,3 +409,42 @@ impl PartialEq<str> for InputKey {
         self.0 == other
     }
 }
+
+#[cfg(test)]
+mod input_spec {
+    use serde_json::json;
+
+    use crate::spec;
+
+    use super::Input;
+
+    fn spec(record: bool) -> spec::v2::Input {
+        serde_json::from_value(json!({
+            "key": "origin",
+            "endpoints": [{"kind": "rtmp"}],
+            "record": record,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn applies_recording() {
+        let mut input = Input::new(spec(false));
+
+        input.apply(spec(true));
+        assert!(input.record);
+
+        input.apply(spec(false));
+        assert!(!input.record);
+    }
+
+    #[test]
+    fn keeps_recording_on_editing() {
+        let mut input = Input::new(spec(true));
+        let mut new = spec(false);
+
+        input.keep_recording(&mut new);
+        input.apply(new);
+        assert!(input.record);
+    }
+}
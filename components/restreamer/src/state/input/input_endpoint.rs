@@ -10,7 +10,8 @@ use crate::{
     file_manager::FileId,
     spec, srs,
     state::{InputKey, Label, RestreamKey, Status},
-    stream_statistics::StreamStatistics,
+    stream_statistics::{StreamFailure, StreamProgress, StreamStatistics},
+    types::UNumber,
 };
 
 /// Endpoint of an `Input` serving a live stream for `Output`s and clients.
@@ -37,6 +38,13 @@ pub struct InputEndpoint {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub file_id: Option<FileId>,
 
+    /// `SrtListener` accepting [SRT] pushes, if this `InputEndpoint` is of
+    /// `InputEndpointKind::SRT`.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt: Option<SrtListener>,
+
     /// `Status` of this `InputEndpoint` indicating whether it actually serves a
     /// live stream ready to be consumed by `Output`s and clients.
     #[serde(skip)]
@@ -60,47 +68,62 @@ pub struct InputEndpoint {
 
     /// Corresponding stream info
     pub stream_stat: Option<StreamStatistics>,
+
+    /// Last `StreamFailure`s of the local process serving a live stream to
+    /// this `InputEndpoint`, ordered from the oldest to the newest one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<StreamFailure>,
+
+    /// Real-time `StreamProgress` of the local process serving a live stream
+    /// to this `InputEndpoint`, if any.
+    #[serde(skip)]
+    pub progress: Option<StreamProgress>,
 }
 
 impl InputEndpoint {
     /// Creates a new [`InputEndpoint`] out of the given
-    /// [`spec::v1::InputEndpoint`].
+    /// [`spec::v2::InputEndpoint`].
     #[inline]
     #[must_use]
-    pub fn new(spec: spec::v1::InputEndpoint) -> Self {
+    pub fn new(spec: spec::v2::InputEndpoint) -> Self {
         Self {
             id: EndpointId::random(),
             kind: spec.kind,
             status: Status::Offline,
             file_id: spec.file_id,
             label: spec.label,
+            srt: spec.srt.map(SrtListener::new),
             srs_publisher_id: None,
             srs_player_ids: HashSet::new(),
             stream_stat: None,
+            failures: vec![],
+            progress: None,
         }
     }
 
-    /// Applies the given [`spec::v1::InputEndpoint`] to
+    /// Applies the given [`spec::v2::InputEndpoint`] to
     /// this [`InputEndpoint`].
     ///
     /// If `label` is not `None` than we already
     /// set the value and the update is not required.
     #[inline]
-    pub fn apply(&mut self, new: spec::v1::InputEndpoint) {
+    pub fn apply(&mut self, new: spec::v2::InputEndpoint) {
         self.kind = new.kind;
+        self.srt = new.srt.map(SrtListener::new);
         if new.label.is_some() {
             self.label = new.label;
         };
     }
 
-    /// Exports this [`InputEndpoint`] as a [`spec::v1::InputEndpoint`].
+    /// Exports this [`InputEndpoint`] as a [`spec::v2::InputEndpoint`].
     #[inline]
     #[must_use]
-    pub fn export(&self) -> spec::v1::InputEndpoint {
-        spec::v1::InputEndpoint {
+    pub fn export(&self) -> spec::v2::InputEndpoint {
+        spec::v2::InputEndpoint {
             kind: self.kind,
             label: self.label.clone(),
             file_id: self.file_id.clone(),
+            srt: self.srt.as_ref().map(SrtListener::export),
         }
     }
 
@@ -112,6 +135,14 @@ impl InputEndpoint {
         matches!(self.kind, InputEndpointKind::Rtmp)
     }
 
+    /// Indicates whether this [`InputEndpoint`] is an
+    /// [`InputEndpointKind::Srt`].
+    #[inline]
+    #[must_use]
+    pub fn is_srt(&self) -> bool {
+        matches!(self.kind, InputEndpointKind::Srt)
+    }
+
     /// Indicates whether this [`InputEndpoint`] is an
     /// [`InputEndpointKind::Rtmp`].
     #[inline]
@@ -156,6 +187,16 @@ pub enum InputEndpointKind {
     /// File input.
     #[display(fmt = "FILE")]
     File,
+
+    /// [SRT] endpoint.
+    ///
+    /// Only accepts a live stream (in listener mode) and re-publishes it to
+    /// the [RTMP] endpoint of the same `Input`.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[display(fmt = "SRT")]
+    Srt,
 }
 
 impl InputEndpointKind {
@@ -183,7 +224,9 @@ impl InputEndpointKind {
             "rtmp://127.0.0.1:1935/{}{}/{}",
             restream,
             match kind {
-                InputEndpointKind::Rtmp | InputEndpointKind::File => "",
+                InputEndpointKind::Rtmp
+                | InputEndpointKind::File
+                | InputEndpointKind::Srt => "",
                 InputEndpointKind::Hls => "?vhost=hls",
             },
             input,
@@ -192,6 +235,113 @@ impl InputEndpointKind {
     }
 }
 
+/// Listener of an `InputEndpoint` accepting [SRT] pushes.
+///
+/// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct SrtListener {
+    /// UDP port to listen for [SRT] pushes on.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    pub port: UNumber,
+
+    /// `streamid` of this listener, which [SRT] callers pushing to this
+    /// `Input` are expected to be configured with.
+    ///
+    /// If `null`, then no `streamid` is set.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<String>,
+
+    /// Passphrase that pushed [SRT] streams should be encrypted with.
+    ///
+    /// If `null`, then pushes are accepted unencrypted.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+}
+
+impl SrtListener {
+    /// Creates a new [`SrtListener`] out of the given
+    /// [`spec::v2::SrtListener`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::SrtListener) -> Self {
+        Self {
+            port: spec.port,
+            stream_id: spec.stream_id,
+            passphrase: spec.passphrase,
+        }
+    }
+
+    /// Exports this [`SrtListener`] as a [`spec::v2::SrtListener`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::SrtListener {
+        spec::v2::SrtListener {
+            port: self.port,
+            stream_id: self.stream_id.clone(),
+            passphrase: self.passphrase.clone(),
+        }
+    }
+
+    /// Validates invariants of the given [`spec::v2::SrtListener`]: its
+    /// `port` should be non-zero, and its `streamId` and `passphrase` should
+    /// have lengths allowed by [SRT].
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    pub fn validate(spec: &spec::v2::SrtListener) -> Result<(), String> {
+        if spec.port.0 == 0 {
+            return Err("SrtListener.port should be positive".into());
+        }
+        if let Some(id) = &spec.stream_id {
+            if id.is_empty() || id.len() > 512 {
+                return Err("SrtListener.streamId should be from 1 to 512 \
+                            characters long"
+                    .into());
+            }
+        }
+        if let Some(pass) = &spec.passphrase {
+            if !(10..=79).contains(&pass.len()) {
+                return Err("SrtListener.passphrase should be from 10 to 79 \
+                            characters long"
+                    .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns [FFmpeg] URL listening for [SRT] pushes according to this
+    /// [`SrtListener`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[must_use]
+    pub fn listen_url(&self) -> Url {
+        let mut url =
+            Url::parse(&format!("srt://0.0.0.0:{}", self.port.0)).unwrap();
+        {
+            let mut query = url.query_pairs_mut();
+            _ = query.append_pair("mode", "listener");
+            if let Some(id) = &self.stream_id {
+                _ = query.append_pair("streamid", id);
+            }
+            if let Some(pass) = &self.passphrase {
+                _ = query.append_pair("passphrase", pass);
+            }
+        }
+        url
+    }
+}
+
 /// ID of an `InputEndpoint`.
 #[derive(
     Clone,
@@ -34,15 +34,15 @@ impl Display for InputSrc {
 }
 
 impl InputSrc {
-    /// Creates a new [`InputSrc`] out of the given [`spec::v1::InputSrc`].
+    /// Creates a new [`InputSrc`] out of the given [`spec::v2::InputSrc`].
     #[inline]
     #[must_use]
-    pub fn new(spec: spec::v1::InputSrc) -> Self {
+    pub fn new(spec: spec::v2::InputSrc) -> Self {
         match spec {
-            spec::v1::InputSrc::RemoteUrl(url) => {
+            spec::v2::InputSrc::RemoteUrl(url) => {
                 Self::Remote(RemoteInputSrc { url, label: None })
             }
-            spec::v1::InputSrc::FailoverInputs(inputs) => {
+            spec::v2::InputSrc::FailoverInputs(inputs) => {
                 Self::Failover(FailoverInputSrc {
                     inputs: inputs.into_iter().map(Input::new).collect(),
                 })
@@ -88,15 +88,15 @@ impl InputSrc {
             }),
         }
     }
-    /// Applies the given [`spec::v1::InputSrc`] to this [`InputSrc`].
+    /// Applies the given [`spec::v2::InputSrc`] to this [`InputSrc`].
     ///
     /// Replaces all the [`FailoverInputSrc::inputs`] with new ones.
-    pub fn apply(&mut self, new: spec::v1::InputSrc) {
+    pub fn apply(&mut self, new: spec::v2::InputSrc) {
         match (self, new) {
-            (Self::Remote(old), spec::v1::InputSrc::RemoteUrl(new_url)) => {
+            (Self::Remote(old), spec::v2::InputSrc::RemoteUrl(new_url)) => {
                 old.url = new_url;
             }
-            (Self::Failover(src), spec::v1::InputSrc::FailoverInputs(news)) => {
+            (Self::Failover(src), spec::v2::InputSrc::FailoverInputs(news)) => {
                 let mut olds = mem::replace(
                     &mut src.inputs,
                     Vec::with_capacity(news.len()),
@@ -119,13 +119,13 @@ impl InputSrc {
         }
     }
 
-    /// Exports this [`InputSrc`] as a [`spec::v1::InputSrc`].
+    /// Exports this [`InputSrc`] as a [`spec::v2::InputSrc`].
     #[inline]
     #[must_use]
-    pub fn export(&self) -> spec::v1::InputSrc {
+    pub fn export(&self) -> spec::v2::InputSrc {
         match self {
-            Self::Remote(i) => spec::v1::InputSrc::RemoteUrl(i.url.clone()),
-            Self::Failover(src) => spec::v1::InputSrc::FailoverInputs(
+            Self::Remote(i) => spec::v2::InputSrc::RemoteUrl(i.url.clone()),
+            Self::Failover(src) => spec::v2::InputSrc::FailoverInputs(
                 src.inputs.iter().map(Input::export).collect(),
             ),
         }
@@ -177,10 +177,18 @@ impl Display for FailoverInputSrc {
 /// - [RTMP] URL (starting with `rtmp://` or `rtmps://` scheme and having a
 ///   host);
 /// - [HLS] URL (starting with `http://` or `https://` scheme, having a host,
-///   and with `.m3u8` extension in its path).
+///   and with `.m3u8` extension in its path);
+/// - [RTSP] URL (starting with `rtsp://` or `rtsps://` scheme and having a
+///   host), optionally with a `transport` query parameter being `tcp` or
+///   `udp`;
+/// - [SRT] URL (starting with `srt://` scheme, having a host and a port),
+///   pulled in a caller mode, optionally with `latency` (in microseconds) and
+///   `passphrase` (from 10 to 79 characters) query parameters.
 ///
 /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
 /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+/// [RTSP]: https://en.wikipedia.org/wiki/Real_Time_Streaming_Protocol
+/// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
 #[derive(
     Clone,
     Debug,
@@ -197,6 +205,11 @@ impl Display for FailoverInputSrc {
 pub struct InputSrcUrl(Url);
 
 impl InputSrcUrl {
+    /// Name of the query parameter specifying transport of an [RTSP] URL.
+    ///
+    /// [RTSP]: https://en.wikipedia.org/wiki/Real_Time_Streaming_Protocol
+    pub const RTSP_TRANSPORT_PARAM: &'static str = "transport";
+
     /// Creates a new [`InputSrcUrl`] if the given [`Url`] is suitable for that.
     ///
     /// # Errors
@@ -217,6 +230,23 @@ impl InputSrcUrl {
     pub fn validate(url: &Url) -> bool {
         match url.scheme() {
             "rtmp" | "rtmps" => url.has_host(),
+            "rtsp" | "rtsps" => {
+                url.has_host()
+                    && url.query_pairs().all(|(k, v)| {
+                        k != Self::RTSP_TRANSPORT_PARAM
+                            || RtspTransport::from_param(&v).is_some()
+                    })
+            }
+            "srt" => {
+                url.has_host()
+                    && url.port().is_some()
+                    && url.query_pairs().all(|(k, v)| match k.as_ref() {
+                        "mode" => v == "caller",
+                        "latency" => v.parse::<u32>().is_ok(),
+                        "passphrase" => (10..=79).contains(&v.chars().count()),
+                        _ => true,
+                    })
+            }
             "http" | "https" => {
                 url.has_host()
                     && Path::new(url.path()).extension()
@@ -227,6 +257,38 @@ impl InputSrcUrl {
     }
 }
 
+/// Transport to pull a live stream from an [RTSP] URL with.
+///
+/// [RTSP]: https://en.wikipedia.org/wiki/Real_Time_Streaming_Protocol
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum RtspTransport {
+    /// Interleaving media into the [TCP] connection of [RTSP] itself.
+    ///
+    /// [RTSP]: https://en.wikipedia.org/wiki/Real_Time_Streaming_Protocol
+    /// [TCP]: https://en.wikipedia.org/wiki/Transmission_Control_Protocol
+    #[display(fmt = "tcp")]
+    Tcp,
+
+    /// Receiving media via separate [UDP] packets.
+    ///
+    /// [UDP]: https://en.wikipedia.org/wiki/User_Datagram_Protocol
+    #[display(fmt = "udp")]
+    Udp,
+}
+
+impl RtspTransport {
+    /// Parses the given value of the [`InputSrcUrl::RTSP_TRANSPORT_PARAM`]
+    /// query parameter.
+    #[must_use]
+    pub fn from_param(value: &str) -> Option<Self> {
+        match value {
+            "tcp" => Some(Self::Tcp),
+            "udp" => Some(Self::Udp),
+            _ => None,
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for InputSrcUrl {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -238,3 +300,52 @@ impl<'de> Deserialize<'de> for InputSrcUrl {
         })
     }
 }
+
+#[cfg(test)]
+mod input_src_url_spec {
+    use url::Url;
+
+    use super::InputSrcUrl;
+
+    fn valid(url: &str) -> bool {
+        InputSrcUrl::validate(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn validates_rtsp() {
+        assert!(valid("rtsp://example.com/live"));
+        assert!(valid("rtsps://example.com:322/live"));
+        assert!(valid("rtsp://example.com/live?transport=tcp"));
+        assert!(valid("rtsp://example.com/live?transport=udp"));
+
+        assert!(!valid("rtsp://example.com/live?transport=http"));
+        assert!(!valid("rtsp:/live"));
+    }
+
+    #[test]
+    fn validates_srt() {
+        assert!(valid("srt://example.com:9000"));
+        assert!(valid("srt://example.com:9000?mode=caller"));
+        assert!(valid("srt://example.com:9000?latency=200000"));
+
+        assert!(!valid("srt://example.com"));
+        assert!(!valid("srt://example.com:9000?mode=listener"));
+        assert!(!valid("srt://example.com:9000?latency=low"));
+    }
+
+    #[test]
+    fn validates_srt_passphrase_length() {
+        let with_passphrase = |len| {
+            valid(&format!(
+                "srt://example.com:9000?passphrase={}",
+                "x".repeat(len),
+            ))
+        };
+
+        assert!(with_passphrase(10));
+        assert!(with_passphrase(79));
+
+        assert!(!with_passphrase(9));
+        assert!(!with_passphrase(80));
+    }
+}
@@ -1,12 +1,26 @@
+mod audio_encoding;
+mod hls_push;
+mod icecast;
+mod loudness;
 mod mixin;
+mod sidechain;
+mod speakers;
+mod transcoding;
 mod volume;
 
 pub use self::{
+    audio_encoding::{AudioEncoding, AudioFormat},
+    hls_push::{HlsPush, HlsPushMethod},
+    icecast::Icecast,
+    loudness::Loudness,
     mixin::{Delay, Mixin, MixinId, MixinSrcUrl},
+    sidechain::Sidechain,
+    speakers::{Speaker, SpeakerFilter, SpeakerList, TalkingSpeakers},
+    transcoding::{AudioCodec, TranscodingProfile, VideoCodec},
     volume::{Volume, VolumeLevel},
 };
 
-use std::{mem, path::Path};
+use std::{ffi::OsStr, mem, path::Path};
 
 use derive_more::{Deref, Display, From, Into};
 use ephyr_serde::is_false;
@@ -16,8 +30,9 @@ use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    spec,
-    state::{Label, RestreamKey, Status},
+    dvr, spec,
+    state::{Label, RestreamKey, RetryPolicy, Schedule, Status},
+    stream_statistics::{StreamFailure, StreamProgress},
 };
 
 /// Downstream destination that a `Restream` re-streams a live stream to.
@@ -38,6 +53,16 @@ pub struct Output {
     /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
     pub dst: OutputDstUrl,
 
+    /// Password of the [Icecast] mount to publish onto, if `dst` is an
+    /// `icecast://` URL.
+    ///
+    /// Is not exposed via GraphQL, being a secret.
+    ///
+    /// [Icecast]: https://icecast.org
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
     /// Optional label of this `Output`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub label: Option<Label>,
@@ -66,39 +91,118 @@ pub struct Output {
     #[serde(default, skip_serializing_if = "is_false")]
     pub enabled: bool,
 
+    /// `Schedule`s of enabling and disabling this `Output`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<Schedule>,
+
+    /// `TranscodingProfile` to transcode a live stream with before
+    /// re-streaming it to the downstream destination.
+    ///
+    /// If `null`, then a live stream is re-streamed "as is".
+    ///
+    /// Has no effect when there are `Output.mixins`, or the destination is
+    /// audio-only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcoding: Option<TranscodingProfile>,
+
+    /// `AudioEncoding` to encode audio with before re-streaming it to the
+    /// audio-only downstream destination ([Icecast] or `.mp3` file).
+    ///
+    /// If `null`, then audio is encoded into 64 kbit/s MP3.
+    ///
+    /// Has no effect when the destination is not audio-only.
+    ///
+    /// [Icecast]: https://icecast.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_encoding: Option<AudioEncoding>,
+
+    /// `Loudness` to normalize and limit the mixed audio of this `Output`
+    /// with.
+    ///
+    /// If `null`, then the mixed audio is re-streamed "as is".
+    ///
+    /// Has no effect when there is no `Output.mixins`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness: Option<Loudness>,
+
+    /// `RetryPolicy` of restarting a failed re-streaming to the downstream
+    /// destination of this `Output`.
+    ///
+    /// If `null`, then the global one from server settings is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_policy: Option<RetryPolicy>,
+
+    /// Reason of this `Output` being disabled automatically (after exceeding
+    /// its `RetryPolicy.maxAttempts`, for example).
+    ///
+    /// Is reset once this `Output` is enabled or disabled manually.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled_reason: Option<String>,
+
+    /// Last `StreamFailure`s of re-streaming a live stream to the downstream
+    /// destination of this `Output`, ordered from the oldest to the newest
+    /// one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<StreamFailure>,
+
     /// `Status` of this `Output` indicating whether it actually re-streams a
     /// live stream to its downstream destination.
     #[serde(skip)]
     pub status: Status,
+
+    /// Real-time `StreamProgress` of re-streaming a live stream to the
+    /// downstream destination of this `Output`.
+    ///
+    /// `null` if this `Output` doesn't re-stream at the moment.
+    #[serde(skip)]
+    pub progress: Option<StreamProgress>,
 }
 
 impl Output {
-    /// Creates a new [`Output`] out of the given [`spec::v1::Output`].
+    /// Creates a new [`Output`] out of the given [`spec::v2::Output`].
     #[inline]
     #[must_use]
-    pub fn new(spec: spec::v1::Output) -> Self {
-        Self {
+    pub fn new(spec: spec::v2::Output) -> Self {
+        let mut output = Self {
             id: OutputId::random(),
             dst: spec.dst,
+            password: spec.password,
             label: spec.label,
             preview_url: spec.preview_url,
             volume: Volume::new(&spec.volume),
             mixins: spec.mixins.into_iter().map(Mixin::new).collect(),
             enabled: spec.enabled,
+            schedules: spec.schedules.into_iter().map(Schedule::new).collect(),
+            transcoding: spec.transcoding.map(TranscodingProfile::new),
+            audio_encoding: spec.audio_encoding.map(AudioEncoding::new),
+            loudness: spec.loudness.map(Loudness::new),
+            retry_policy: spec.retry_policy.map(RetryPolicy::new),
+            disabled_reason: None,
+            failures: vec![],
             status: Status::Offline,
-        }
+            progress: None,
+        };
+        _ = output.extract_password();
+        output
     }
 
-    /// Applies the given [`spec::v1::Output`] to this [`Output`].
+    /// Applies the given [`spec::v2::Output`] to this [`Output`].
     ///
-    /// If `replace` is `true` then all the [`Output::mixins`] will be replaced
-    /// with new ones, otherwise new ones will be merged with already existing
-    /// [`Output::mixins`].
-    pub fn apply(&mut self, new: spec::v1::Output, replace: bool) {
+    /// If `replace` is `true` then all the [`Output::mixins`] and
+    /// [`Output::schedules`] will be replaced with new ones, otherwise new ones
+    /// will be merged with already existing ones.
+    pub fn apply(&mut self, new: spec::v2::Output, replace: bool) {
         self.dst = new.dst;
+        self.password = new.password;
+        _ = self.extract_password();
         self.label = new.label;
         self.preview_url = new.preview_url;
         self.volume = Volume::new(&new.volume);
+        self.transcoding = new.transcoding.map(TranscodingProfile::new);
+        self.audio_encoding = new.audio_encoding.map(AudioEncoding::new);
+        self.loudness = new.loudness.map(Loudness::new);
+        self.retry_policy = new.retry_policy.map(RetryPolicy::new);
+        Schedule::apply_all(&mut self.schedules, new.schedules, replace);
         // Temporary omit changing existing `enabled` value to avoid unexpected
         // breakages of ongoing re-streams.
         //self.enabled = new.enabled;
@@ -133,18 +237,45 @@ impl Output {
         }
     }
 
-    /// Exports this [`Output`] as a [`spec::v1::Output`].
+    /// Moves the [`Icecast::PASSWORD_PARAM`] query parameter of the
+    /// [`Output::dst`] into the [`Output::password`], so it's not exposed
+    /// along with it.
+    ///
+    /// Empty parameter clears the stored password.
+    ///
+    /// Returns `true` if the parameter has been moved.
+    pub fn extract_password(&mut self) -> bool {
+        let Some(password) = self.dst.take_password() else {
+            return false;
+        };
+        self.password = Some(password).filter(|p| !p.is_empty());
+        true
+    }
+
+    /// Exports this [`Output`] as a [`spec::v2::Output`].
     #[inline]
     #[must_use]
-    pub fn export(&self) -> spec::v1::Output {
-        spec::v1::Output {
+    pub fn export(&self) -> spec::v2::Output {
+        spec::v2::Output {
             id: Some(self.id),
             dst: self.dst.clone(),
+            password: self.password.clone(),
             label: self.label.clone(),
             preview_url: self.preview_url.clone(),
             volume: self.volume.export(),
             mixins: self.mixins.iter().map(Mixin::export).collect(),
             enabled: self.enabled,
+            schedules: self.schedules.iter().map(Schedule::export).collect(),
+            transcoding: self
+                .transcoding
+                .as_ref()
+                .map(TranscodingProfile::export),
+            audio_encoding: self
+                .audio_encoding
+                .as_ref()
+                .map(AudioEncoding::export),
+            loudness: self.loudness.as_ref().map(Loudness::export),
+            retry_policy: self.retry_policy.as_ref().map(RetryPolicy::export),
         }
     }
 }
@@ -182,12 +313,23 @@ impl OutputId {
 /// - [RTMP] URL (starting with `rtmp://` or `rtmps://` scheme and having a
 ///   host);
 /// - [SRT] URL (starting with `srt://` scheme and having a host);
-/// - [Icecast] URL (starting with `icecast://` scheme and having a host);
-/// - [FLV]|[WAV]|[MP3] file URL (starting with `file:///` scheme,
-///   without host and subdirectories, and with `.flv`|`.wav`|`.mp3`
-///    extension in its path).
+/// - [Icecast] URL (starting with `icecast://` scheme and having a host),
+///   optionally with `name`, `genre`, `description` and `password` query
+///   parameters (see [`Icecast`]);
+/// - [HLS] push URL (starting with `hls+http://` or `hls+https://` scheme and
+///   having a host), optionally with `segment_duration` (in seconds),
+///   `playlist_size`, `method` (`put` or `post`) and `file` query parameters
+///   (see [`HlsPush`]);
+/// - [FLV]|[MP4]|[MKV]|[WAV]|[MP3] file URL (starting with `file:///` scheme,
+///   without host and subdirectories, and with
+///   `.flv`|`.mp4`|`.mkv`|`.wav`|`.mp3` extension in its path), optionally
+///   with `rotate_minutes` or `rotate_mb` query parameter (see
+///   [`dvr::Rotation`]).
 ///
 /// [FLV]: https://en.wikipedia.org/wiki/Flash_Video
+/// [MKV]: https://en.wikipedia.org/wiki/Matroska
+/// [MP4]: https://en.wikipedia.org/wiki/MP4_file_format
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
 /// [WAV]: https://en.wikipedia.org/wiki/WAV
 /// [MP3]: https://en.wikipedia.org/wiki/MP3
 /// [Icecast]: https://icecast.org
@@ -229,21 +371,53 @@ impl OutputDstUrl {
     #[must_use]
     pub fn validate(url: &Url) -> bool {
         match url.scheme() {
-            "icecast" | "rtmp" | "rtmps" | "srt" => url.has_host(),
+            "icecast" => Icecast::parse(url).is_some(),
+            "rtmp" | "rtmps" | "srt" => url.has_host(),
+            "hls+http" | "hls+https" => HlsPush::parse(url).is_some(),
             "file" => {
                 let path = Path::new(url.path());
                 !url.has_host()
                     && path.is_absolute()
-                    && (path.extension() == Some("flv".as_ref())
-                        || path.extension() == Some("wav".as_ref())
-                        || path.extension() == Some("mp3".as_ref()))
+                    && matches!(
+                        path.extension().and_then(OsStr::to_str),
+                        Some("flv" | "mkv" | "mp3" | "mp4" | "wav"),
+                    )
                     && path.parent() == Some("/".as_ref())
                     && !url.path().contains("/../")
+                    && dvr::Rotation::parse(url).is_some()
             }
             _ => false,
         }
     }
 
+    /// Removes the [`Icecast::PASSWORD_PARAM`] query parameter from this
+    /// `icecast://` [`OutputDstUrl`], returning its value, if there was any.
+    pub fn take_password(&mut self) -> Option<String> {
+        if self.0.scheme() != "icecast" {
+            return None;
+        }
+        let mut value = None;
+        let rest = self
+            .0
+            .query_pairs()
+            .into_owned()
+            .filter(|(k, v)| {
+                if k == Icecast::PASSWORD_PARAM {
+                    value = Some(v.clone());
+                }
+                k != Icecast::PASSWORD_PARAM
+            })
+            .collect::<Vec<_>>();
+        if value.is_some() {
+            if rest.is_empty() {
+                self.0.set_query(None);
+            } else {
+                _ = self.0.query_pairs_mut().clear().extend_pairs(rest);
+            }
+        }
+        value
+    }
+
     /// Check if [`Restream`] key belong to restream
     ///
     /// [`Restream`]: crate::state::Restream
@@ -277,3 +451,50 @@ impl<'de> Deserialize<'de> for OutputDstUrl {
         })
     }
 }
+
+#[cfg(test)]
+mod output_spec {
+    use serde_json::json;
+
+    use super::Output;
+
+    fn output(dst: &str) -> Output {
+        Output::new(serde_json::from_value(json!({ "dst": dst })).unwrap())
+    }
+
+    #[test]
+    fn stores_icecast_password_out_of_dst() {
+        let output = output(
+            "icecast://source@example.com:8000/live.mp3?name=EN&password=a",
+        );
+
+        assert_eq!(
+            output.dst.as_str(),
+            "icecast://source@example.com:8000/live.mp3?name=EN",
+        );
+        assert_eq!(output.password.as_deref(), Some("a"));
+        assert_eq!(output.export().password.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn clears_icecast_password_with_empty_param() {
+        let mut output = output("icecast://example.com/live.mp3?password=a");
+        let mut spec = output.export();
+        spec.dst = serde_json::from_value(json!(
+            "icecast://example.com/live.mp3?password="
+        ))
+        .unwrap();
+
+        output.apply(spec, true);
+        assert_eq!(output.dst.as_str(), "icecast://example.com/live.mp3");
+        assert_eq!(output.password, None);
+    }
+
+    #[test]
+    fn keeps_password_param_of_non_icecast_dst() {
+        let output = output("srt://example.com:9000?password=a");
+
+        assert_eq!(output.dst.as_str(), "srt://example.com:9000?password=a");
+        assert_eq!(output.password, None);
+    }
+}
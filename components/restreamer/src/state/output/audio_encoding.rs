@@ -0,0 +1,185 @@
+//! Audio encoding profile of an audio-only `Output`.
+
+use std::{ffi::OsStr, path::Path};
+
+use derive_more::Display;
+use juniper::{GraphQLEnum, GraphQLObject};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{spec, state::AudioCodec, types::UNumber};
+
+/// Profile of encoding audio re-streamed to an audio-only `Output`'s
+/// destination ([Icecast] or `.mp3` DVR file).
+///
+/// [Icecast]: https://icecast.org
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct AudioEncoding {
+    /// Format to encode audio into.
+    #[serde(default)]
+    pub format: AudioFormat,
+
+    /// Bitrate of the encoded audio, in kbit/s.
+    pub bitrate: UNumber,
+}
+
+impl Default for AudioEncoding {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            format: AudioFormat::Mp3,
+            bitrate: UNumber(64),
+        }
+    }
+}
+
+impl AudioEncoding {
+    /// Creates a new [`AudioEncoding`] out of the given
+    /// [`spec::v2::AudioEncoding`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::AudioEncoding) -> Self {
+        Self {
+            format: spec.format,
+            bitrate: spec.bitrate,
+        }
+    }
+
+    /// Exports this [`AudioEncoding`] as a [`spec::v2::AudioEncoding`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::AudioEncoding {
+        spec::v2::AudioEncoding {
+            format: self.format,
+            bitrate: self.bitrate,
+        }
+    }
+
+    /// Validates invariants of the given [`spec::v2::AudioEncoding`]: its
+    /// bitrate should be supported by the encoder.
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    pub fn validate(spec: &spec::v2::AudioEncoding) -> Result<(), String> {
+        if !(16..=320).contains(&spec.bitrate.0) {
+            return Err(
+                "AudioEncoding.bitrate should be within 16 and 320".into()
+            );
+        }
+        Ok(())
+    }
+
+    /// Validates the given [`spec::v2::AudioEncoding`] to be usable for the
+    /// given `Output` destination `dst`: a `.mp3` DVR file can be encoded into
+    /// [`AudioFormat::Mp3`] only.
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    pub fn validate_dst(
+        spec: &spec::v2::AudioEncoding,
+        dst: &Url,
+    ) -> Result<(), String> {
+        if dst.scheme() == "file"
+            && Path::new(dst.path()).extension().and_then(OsStr::to_str)
+                == Some("mp3")
+            && spec.format != AudioFormat::Mp3
+        {
+            return Err(format!(
+                "AudioEncoding.format cannot be `{}` for `.mp3` file",
+                spec.format,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Format of audio encoded according to an `AudioEncoding`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Display,
+    Eq,
+    GraphQLEnum,
+    PartialEq,
+    Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    /// [MP3] audio.
+    ///
+    /// [MP3]: https://en.wikipedia.org/wiki/MP3
+    #[default]
+    #[display(fmt = "mp3")]
+    Mp3,
+
+    /// [AAC] audio in [ADTS] stream.
+    ///
+    /// [AAC]: https://en.wikipedia.org/wiki/Advanced_Audio_Coding
+    /// [ADTS]: https://wiki.multimedia.cx/index.php/ADTS
+    #[display(fmt = "aac")]
+    Aac,
+
+    /// [Opus] audio in [Ogg] container.
+    ///
+    /// [Ogg]: https://en.wikipedia.org/wiki/Ogg
+    /// [Opus]: https://opus-codec.org
+    #[display(fmt = "opus")]
+    Opus,
+
+    /// [Vorbis] audio in [Ogg] container.
+    ///
+    /// [Ogg]: https://en.wikipedia.org/wiki/Ogg
+    /// [Vorbis]: https://en.wikipedia.org/wiki/Vorbis
+    #[display(fmt = "vorbis")]
+    Vorbis,
+}
+
+impl AudioFormat {
+    /// Returns name of the [FFmpeg] encoder of this [`AudioFormat`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[inline]
+    #[must_use]
+    pub fn encoder(self) -> &'static str {
+        match self {
+            Self::Mp3 => AudioCodec::Mp3.encoder(),
+            Self::Aac => AudioCodec::Aac.encoder(),
+            Self::Opus => "libopus",
+            Self::Vorbis => "libvorbis",
+        }
+    }
+
+    /// Returns name of the [FFmpeg] muxer of this [`AudioFormat`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[inline]
+    #[must_use]
+    pub fn muxer(self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::Aac => "adts",
+            Self::Opus | Self::Vorbis => "ogg",
+        }
+    }
+
+    /// Returns MIME content type of this [`AudioFormat`], as expected by
+    /// [Icecast] server.
+    ///
+    /// [Icecast]: https://icecast.org
+    #[inline]
+    #[must_use]
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Mp3 => "audio/mpeg",
+            Self::Aac => "audio/aac",
+            Self::Opus | Self::Vorbis => "audio/ogg",
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! [HLS] push destination of an `Output`.
+//!
+//! [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+
+use std::{ffi::OsStr, path::Path};
+
+use derive_more::Display;
+use itertools::Itertools as _;
+use url::{form_urlencoded, Url};
+
+/// Parameters of pushing [HLS] segments and playlist over HTTP to a
+/// `hls+http://` or `hls+https://` `OutputDstUrl`.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HlsPush {
+    /// HTTP [`Url`] to upload the [HLS] playlist onto.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    pub playlist_url: Url,
+
+    /// Template of HTTP URLs to upload [HLS] segments onto, with a `%d`
+    /// placeholder of the segment number, as understood by the
+    /// `-hls_segment_filename` option of [FFmpeg].
+    ///
+    /// Segments are uploaded next to the playlist, or, if the playlist is
+    /// distinguished by the [`HlsPush::FILE_PARAM`] query parameter, onto the
+    /// same path with this parameter naming the segment.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    pub segment_url: String,
+
+    /// HTTP method to upload the playlist and segments with.
+    pub method: HlsPushMethod,
+
+    /// Target duration of a single segment, in seconds.
+    pub segment_duration: u16,
+
+    /// Maximum number of segments listed in the playlist.
+    pub playlist_size: u16,
+}
+
+impl HlsPush {
+    /// Name of the query parameter specifying the
+    /// [`HlsPush::segment_duration`].
+    pub const SEGMENT_DURATION_PARAM: &'static str = "segment_duration";
+
+    /// Name of the query parameter specifying the [`HlsPush::playlist_size`].
+    pub const PLAYLIST_SIZE_PARAM: &'static str = "playlist_size";
+
+    /// Name of the query parameter specifying the [`HlsPush::method`].
+    pub const METHOD_PARAM: &'static str = "method";
+
+    /// Name of the query parameter naming the uploaded file, if the ingest
+    /// distinguishes files by it rather than by the path (like [YouTube]
+    /// does).
+    ///
+    /// [YouTube]: https://developers.google.com/youtube/v3/live/guides/hls-ingestion
+    pub const FILE_PARAM: &'static str = "file";
+
+    /// Default [`HlsPush::segment_duration`], used if the URL doesn't specify
+    /// it.
+    pub const DEFAULT_SEGMENT_DURATION: u16 = 4;
+
+    /// Default [`HlsPush::playlist_size`], used if the URL doesn't specify it.
+    pub const DEFAULT_PLAYLIST_SIZE: u16 = 5;
+
+    /// Default name of the playlist, used if the [`HlsPush::FILE_PARAM`] query
+    /// parameter is empty.
+    pub const DEFAULT_PLAYLIST_NAME: &'static str = "playlist.m3u8";
+
+    /// Parses [`HlsPush`] parameters out of the given `hls+http(s)://` [`Url`].
+    ///
+    /// Unknown query parameters are kept in both the
+    /// [`HlsPush::playlist_url`] and the [`HlsPush::segment_url`].
+    ///
+    /// Returns [`None`] if the given [`Url`] is not a valid [HLS] push
+    /// destination.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    #[must_use]
+    pub fn parse(url: &Url) -> Option<Self> {
+        if !matches!(url.scheme(), "hls+http" | "hls+https") || !url.has_host()
+        {
+            return None;
+        }
+
+        let mut segment_duration = Self::DEFAULT_SEGMENT_DURATION;
+        let mut playlist_size = Self::DEFAULT_PLAYLIST_SIZE;
+        let mut method = HlsPushMethod::default();
+        let mut query = vec![];
+        for (k, v) in url.query_pairs().into_owned() {
+            match k.as_str() {
+                Self::SEGMENT_DURATION_PARAM => {
+                    segment_duration =
+                        v.parse::<u16>().ok().filter(|d| *d > 0)?;
+                }
+                Self::PLAYLIST_SIZE_PARAM => {
+                    playlist_size = v.parse::<u16>().ok().filter(|s| *s > 0)?;
+                }
+                Self::METHOD_PARAM => method = HlsPushMethod::from_param(&v)?,
+                _ => query.push((k, v)),
+            }
+        }
+
+        let mut playlist_url =
+            Url::parse(url.as_str().strip_prefix("hls+")?).ok()?;
+        playlist_url.set_query(None);
+        playlist_url.set_fragment(None);
+
+        let by_file_param = query.iter().any(|(k, _)| k == Self::FILE_PARAM);
+        let segment_base = if by_file_param {
+            for (k, v) in &mut query {
+                if *k == Self::FILE_PARAM && v.is_empty() {
+                    *v = Self::DEFAULT_PLAYLIST_NAME.into();
+                }
+            }
+            playlist_url.as_str().to_owned()
+        } else {
+            let name = playlist_url.path_segments()?.last()?;
+            let segment = playlist_url
+                .join(&format!("{}_.ts", file_stem(name)))
+                .ok()?;
+            segment.as_str().strip_suffix(".ts")?.to_owned()
+        };
+
+        // `%` is special in FFmpeg's segment filename template, so should be
+        // escaped in the already percent-encoded parts.
+        let mut segment_url = segment_base.replace('%', "%%");
+        if !by_file_param {
+            segment_url.push_str("%d.ts");
+        }
+        if !query.is_empty() {
+            let encode = |s: &str| {
+                form_urlencoded::byte_serialize(s.as_bytes())
+                    .collect::<String>()
+                    .replace('%', "%%")
+            };
+            segment_url.push('?');
+            segment_url.push_str(
+                &query
+                    .iter()
+                    .map(|(k, v)| {
+                        let v = if k == Self::FILE_PARAM {
+                            let stem = format!("{}_", file_stem(v));
+                            format!("{}%d.ts", encode(&stem))
+                        } else {
+                            encode(v)
+                        };
+                        format!("{}={v}", encode(k))
+                    })
+                    .join("&"),
+            );
+            _ = playlist_url.query_pairs_mut().extend_pairs(query);
+        }
+
+        Some(Self {
+            playlist_url,
+            segment_url,
+            method,
+            segment_duration,
+            playlist_size,
+        })
+    }
+}
+
+/// Returns the stem of the given file `name` to name [HLS] segments after,
+/// falling back to `segment` if it has none.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+fn file_stem(name: &str) -> &str {
+    Path::new(name)
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .filter(|s| !s.is_empty())
+        .unwrap_or("segment")
+}
+
+/// HTTP method to push [HLS] playlist and segments with.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+#[derive(Clone, Copy, Debug, Default, Display, Eq, PartialEq)]
+pub enum HlsPushMethod {
+    /// `PUT` request, overwriting the uploaded file.
+    #[default]
+    #[display(fmt = "PUT")]
+    Put,
+
+    /// `POST` request.
+    #[display(fmt = "POST")]
+    Post,
+}
+
+impl HlsPushMethod {
+    /// Parses the given value of the [`HlsPush::METHOD_PARAM`] query
+    /// parameter.
+    #[must_use]
+    pub fn from_param(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "PUT" => Some(Self::Put),
+            "POST" => Some(Self::Post),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod hls_push_spec {
+    use url::Url;
+
+    use super::{HlsPush, HlsPushMethod};
+
+    fn parse(url: &str) -> Option<HlsPush> {
+        HlsPush::parse(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn parses_path_based_destination() {
+        assert_eq!(
+            parse(
+                "hls+https://ingest.example.com/live/stream.m3u8\
+                 ?token=a%26b&segment_duration=2&playlist_size=10",
+            ),
+            Some(HlsPush {
+                playlist_url: Url::parse(
+                    "https://ingest.example.com/live/stream.m3u8?token=a%26b",
+                )
+                .unwrap(),
+                segment_url: "https://ingest.example.com/live/stream_%d.ts\
+                              ?token=a%%26b"
+                    .into(),
+                method: HlsPushMethod::Put,
+                segment_duration: 2,
+                playlist_size: 10,
+            }),
+        );
+    }
+
+    #[test]
+    fn parses_destination_without_extension() {
+        let hls = parse("hls+http://example.com/upload/live").unwrap();
+
+        assert_eq!(hls.playlist_url.as_str(), "http://example.com/upload/live");
+        assert_eq!(hls.segment_url, "http://example.com/upload/live_%d.ts");
+    }
+
+    #[test]
+    fn parses_youtube_destination() {
+        let hls = parse(
+            "hls+https://a.upload.youtube.com/http_upload_hls\
+             ?cid=abc-123&copy=0&file=index.m3u8&method=post",
+        )
+        .unwrap();
+
+        assert_eq!(
+            hls.playlist_url.as_str(),
+            "https://a.upload.youtube.com/http_upload_hls\
+             ?cid=abc-123&copy=0&file=index.m3u8",
+        );
+        assert_eq!(
+            hls.segment_url,
+            "https://a.upload.youtube.com/http_upload_hls\
+             ?cid=abc-123&copy=0&file=index_%d.ts",
+        );
+        assert_eq!(hls.method, HlsPushMethod::Post);
+    }
+
+    #[test]
+    fn names_playlist_if_file_param_is_empty() {
+        let hls =
+            parse("hls+https://a.upload.youtube.com/http_upload_hls?file=")
+                .unwrap();
+
+        assert_eq!(
+            hls.playlist_url.as_str(),
+            "https://a.upload.youtube.com/http_upload_hls?file=playlist.m3u8",
+        );
+        assert_eq!(
+            hls.segment_url,
+            "https://a.upload.youtube.com/http_upload_hls?file=playlist_%d.ts",
+        );
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        for url in [
+            "https://example.com/live.m3u8",
+            "hls+http:live.m3u8",
+            "hls+http://example.com/live.m3u8?segment_duration=0",
+            "hls+http://example.com/live.m3u8?playlist_size=x",
+            "hls+http://example.com/live.m3u8?method=get",
+        ] {
+            assert_eq!(parse(url), None, "{url}");
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! [Icecast] destination of an `Output`.
+//!
+//! [Icecast]: https://icecast.org
+
+use url::Url;
+
+/// Parameters of publishing audio onto an `icecast://` `OutputDstUrl`.
+///
+/// [Icecast]: https://icecast.org
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Icecast {
+    /// `icecast://` [`Url`] of the mount to publish audio onto, without the
+    /// query parameters understood by [`Icecast::parse`].
+    pub mount_url: Url,
+
+    /// Name of the station, advertised to listeners.
+    pub name: Option<String>,
+
+    /// Genre of the station, advertised to listeners.
+    pub genre: Option<String>,
+
+    /// Description of the station, advertised to listeners.
+    pub description: Option<String>,
+
+    /// Password of the mount, overriding the one specified in the
+    /// [`Icecast::mount_url`] itself, if any.
+    pub password: Option<String>,
+}
+
+impl Icecast {
+    /// Name of the query parameter specifying the [`Icecast::name`].
+    pub const NAME_PARAM: &'static str = "name";
+
+    /// Name of the query parameter specifying the [`Icecast::genre`].
+    pub const GENRE_PARAM: &'static str = "genre";
+
+    /// Name of the query parameter specifying the [`Icecast::description`].
+    pub const DESCRIPTION_PARAM: &'static str = "description";
+
+    /// Name of the query parameter specifying the [`Icecast::password`].
+    pub const PASSWORD_PARAM: &'static str = "password";
+
+    /// Parses [`Icecast`] parameters out of the given `icecast://` [`Url`].
+    ///
+    /// Empty parameters are omitted. Unknown ones are left in the
+    /// [`Icecast::mount_url`].
+    ///
+    /// Returns [`None`] if the given [`Url`] is not a valid [Icecast]
+    /// destination.
+    ///
+    /// [Icecast]: https://icecast.org
+    #[must_use]
+    pub fn parse(url: &Url) -> Option<Self> {
+        if url.scheme() != "icecast" || !url.has_host() {
+            return None;
+        }
+
+        let (mut name, mut genre, mut description, mut password) =
+            (None, None, None, None);
+        let mut query = vec![];
+        for (k, v) in url.query_pairs().into_owned() {
+            let param = match k.as_str() {
+                Self::NAME_PARAM => &mut name,
+                Self::GENRE_PARAM => &mut genre,
+                Self::DESCRIPTION_PARAM => &mut description,
+                Self::PASSWORD_PARAM => &mut password,
+                _ => {
+                    query.push((k, v));
+                    continue;
+                }
+            };
+            *param = Some(v).filter(|v| !v.is_empty());
+        }
+
+        let mut mount_url = url.clone();
+        mount_url.set_query(None);
+        if !query.is_empty() {
+            _ = mount_url.query_pairs_mut().extend_pairs(query);
+        }
+
+        Some(Self {
+            mount_url,
+            name,
+            genre,
+            description,
+            password,
+        })
+    }
+}
+
+#[cfg(test)]
+mod icecast_spec {
+    use url::Url;
+
+    use super::Icecast;
+
+    #[test]
+    fn parses_station_metadata() {
+        let url = Url::parse(
+            "icecast://source@radio.example.com:8000/live.ogg\
+             ?name=Ephyr%20FM&genre=Talk&description=&password=s3cr%26t\
+             &other=1",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Icecast::parse(&url),
+            Some(Icecast {
+                mount_url: Url::parse(
+                    "icecast://source@radio.example.com:8000/live.ogg?other=1",
+                )
+                .unwrap(),
+                name: Some("Ephyr FM".into()),
+                genre: Some("Talk".into()),
+                description: None,
+                password: Some("s3cr&t".into()),
+            }),
+        );
+    }
+
+    #[test]
+    fn rejects_non_icecast() {
+        for url in ["rtmp://example.com/live", "icecast:live.mp3"] {
+            assert_eq!(Icecast::parse(&Url::parse(url).unwrap()), None);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Loudness normalization of a mixed `Output`.
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::{spec, types::UNumber};
+
+/// Parameters of normalizing loudness of a mixed `Output`'s audio according to
+/// [EBU R128][1], and limiting its true peaks.
+///
+/// [1]: https://en.wikipedia.org/wiki/EBU_R_128
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct Loudness {
+    /// Integrated loudness to normalize the audio to, in LUFS.
+    ///
+    /// If `null`, then the audio is not normalized, but only limited to the
+    /// `truePeak`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<i32>,
+
+    /// Loudness range to normalize the audio to, in LU.
+    ///
+    /// Has no effect if the `target` is `null`.
+    pub range: UNumber,
+
+    /// Maximum true peak level of the audio, in dBTP, enforced by a limiter.
+    pub true_peak: i32,
+}
+
+impl Loudness {
+    /// Creates a new [`Loudness`] out of the given [`spec::v2::Loudness`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::Loudness) -> Self {
+        Self {
+            target: spec.target,
+            range: spec.range,
+            true_peak: spec.true_peak,
+        }
+    }
+
+    /// Exports this [`Loudness`] as a [`spec::v2::Loudness`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::Loudness {
+        spec::v2::Loudness {
+            target: self.target,
+            range: self.range,
+            true_peak: self.true_peak,
+        }
+    }
+
+    /// Validates invariants of the given [`spec::v2::Loudness`]: its
+    /// parameters should fit into the ranges supported by [FFmpeg].
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn validate(spec: &spec::v2::Loudness) -> Result<(), String> {
+        if spec.target.map_or(false, |t| !(-70..=-5).contains(&t)) {
+            return Err("Loudness.target should be within -70 and -5".into());
+        }
+        if !(1..=50).contains(&spec.range.0) {
+            return Err("Loudness.range should be within 1 and 50".into());
+        }
+        if !(-9..=0).contains(&spec.true_peak) {
+            return Err("Loudness.truePeak should be within -9 and 0".into());
+        }
+        Ok(())
+    }
+
+    /// Returns [FFmpeg] filters normalizing and limiting audio according to
+    /// this [`Loudness`], to be applied right after mixing.
+    ///
+    /// True peaks are limited on 4x oversampled audio (the `loudnorm` filter
+    /// oversamples it on its own), which is resampled back to 48 kHz then.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn ffmpeg_filters(&self) -> String {
+        let limit = 10_f64.powf(f64::from(self.true_peak) / 20.0);
+        let oversampling = self.target.map_or_else(
+            || "aresample=192000".to_owned(),
+            |target| {
+                format!(
+                    "loudnorm=I={target}:LRA={range}:TP={tp}:linear=false",
+                    range = self.range.0,
+                    tp = self.true_peak,
+                )
+            },
+        );
+        format!(
+            "{oversampling},\
+             alimiter=limit={limit:.6}:attack=5:release=50:level=false,\
+             aresample=48000",
+        )
+    }
+}
@@ -3,16 +3,22 @@
 
 use crate::{
     spec,
-    state::{output::Volume, Status},
+    state::{output::Volume, Sidechain, SpeakerFilter, Status},
 };
 use derive_more::{Deref, Display, From, Into};
-use ephyr_serde::is_false;
 use juniper::{
     GraphQLObject, GraphQLScalar, InputValue, ParseScalarResult,
     ParseScalarValue, ScalarToken, ScalarValue, Value,
 };
+use percent_encoding::percent_decode_str;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
-use std::{convert::TryInto, path::Path, time::Duration};
+use std::{
+    convert::TryInto,
+    ffi::OsStr,
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+use tsclientlib::Identity;
 use url::Url;
 use uuid::Uuid;
 
@@ -29,9 +35,10 @@ pub struct Mixin {
 
     /// URL of the source to be mixed with an `Output`.
     ///
-    /// At the moment, only [TeamSpeak] is supported.
+    /// Only audio of the source is mixed, even if it contains video.
     ///
-    /// [TeamSpeak]: https://teamspeak.com
+    /// Never contains `password` and `channel_password` query parameters, as
+    /// they're stored separately.
     pub src: MixinSrcUrl,
 
     /// Volume rate of this `Mixin`'s audio tracks to mix them with.
@@ -50,47 +57,167 @@ pub struct Mixin {
     #[serde(skip)]
     pub status: Status,
 
+    /// Error preventing this `Mixin` from capturing its source, which cannot
+    /// be recovered without changing the `src` (like a wrong TeamSpeak server
+    /// or channel password).
+    ///
+    /// `null` if there is no such error.
+    #[serde(skip)]
+    pub error: Option<String>,
+
     /// Side-chain audio of `Output` with this `Mixin`.
     ///
-    /// Helps to automatically control audio level of `Mixin`
-    /// based on level of `Output`.
-    #[serde(default, skip_serializing_if = "is_false")]
-    pub sidechain: bool,
+    /// Helps to automatically duck audio of `Output` while this `Mixin` is
+    /// sounding. If `null`, then no side-chaining is performed.
+    #[serde(
+        default,
+        deserialize_with = "spec::v2::deserialize_sidechain",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sidechain: Option<Sidechain>,
+
+    /// Filter of [TeamSpeak] channel members to capture audio of, if `src` is
+    /// a `ts://` URL.
+    ///
+    /// Changing it is applied in real-time, without interrupting the `Output`.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[serde(default, skip_serializing_if = "SpeakerFilter::is_empty")]
+    pub speakers: SpeakerFilter,
+
+    /// [TeamSpeak] identity (its private key) to join a TeamSpeak channel
+    /// with, if `src` is a `ts://` URL.
+    ///
+    /// Generated once and reused across restarts, so the same client is
+    /// always recognized by a TeamSpeak server. Is not exposed via GraphQL
+    /// and is exported into a spec only on demand, being a secret.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub teamspeak_identity: Option<String>,
+
+    /// Password to authenticate on a [TeamSpeak] or [Mumble] server with, if
+    /// `src` is a `ts://` or `mumble://` URL.
+    ///
+    /// Is not exposed via GraphQL, being a secret.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// Password to join a [TeamSpeak] or [Mumble] channel with, if `src` is a
+    /// `ts://` or `mumble://` URL.
+    ///
+    /// Is not exposed via GraphQL, being a secret.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_password: Option<String>,
 }
 
 impl Mixin {
-    /// Creates a new [`Mixin`] out of the given [`spec::v1::Mixin`].
+    /// Creates a new [`Mixin`] out of the given [`spec::v2::Mixin`].
     #[inline]
     #[must_use]
-    pub fn new(spec: spec::v1::Mixin) -> Self {
-        Self {
+    pub fn new(spec: spec::v2::Mixin) -> Self {
+        let mut mixin = Self {
             id: MixinId::random(),
             src: spec.src,
             volume: Volume::new(&spec.volume),
             delay: spec.delay,
             status: Status::Offline,
-            sidechain: spec.sidechain,
-        }
+            error: None,
+            sidechain: spec.sidechain.map(Sidechain::new),
+            speakers: SpeakerFilter::new(spec.speakers),
+            teamspeak_identity: spec.teamspeak_identity,
+            password: spec.password,
+            channel_password: spec.channel_password,
+        };
+        _ = mixin.extract_passwords();
+        _ = mixin.ensure_teamspeak_identity();
+        mixin
     }
 
-    /// Applies the given [`spec::v1::Mixin`] to this [`Mixin`].
+    /// Applies the given [`spec::v2::Mixin`] to this [`Mixin`].
     #[inline]
-    pub fn apply(&mut self, new: spec::v1::Mixin) {
+    pub fn apply(&mut self, new: spec::v2::Mixin) {
         self.src = new.src;
         self.volume = Volume::new(&new.volume);
         self.delay = new.delay;
-        self.sidechain = new.sidechain;
+        self.sidechain = new.sidechain.map(Sidechain::new);
+        self.speakers = SpeakerFilter::new(new.speakers);
+        self.password = new.password;
+        self.channel_password = new.channel_password;
+        // Specs are exported without identities by default, so the existing
+        // one is kept unless a new one is specified explicitly.
+        if new.teamspeak_identity.is_some() {
+            self.teamspeak_identity = new.teamspeak_identity;
+        }
+        _ = self.extract_passwords();
+        _ = self.ensure_teamspeak_identity();
     }
 
-    /// Exports this [`Mixin`] as a [`spec::v1::Mixin`].
+    /// Moves `password` and `channel_password` query parameters of the
+    /// [`Mixin::src`] into the [`Mixin::password`] and
+    /// [`Mixin::channel_password`], so they're not exposed along with it.
+    ///
+    /// Empty parameters clear the stored passwords.
+    ///
+    /// Returns `true` if any parameter has been moved.
+    pub fn extract_passwords(&mut self) -> bool {
+        let mut moved = false;
+        for (name, password) in [
+            (MixinSrcUrl::PASSWORD_PARAM, &mut self.password),
+            (
+                MixinSrcUrl::CHANNEL_PASSWORD_PARAM,
+                &mut self.channel_password,
+            ),
+        ] {
+            if let Some(value) = self.src.take_param(name) {
+                *password = Some(value).filter(|v| !v.is_empty());
+                moved = true;
+            }
+        }
+        moved
+    }
+
+    /// Generates a new [`Mixin::teamspeak_identity`], if this [`Mixin`] is
+    /// sourced from [TeamSpeak] and has no identity yet.
+    ///
+    /// Returns `true` if a new identity has been generated.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub fn ensure_teamspeak_identity(&mut self) -> bool {
+        if self.src.scheme() != "ts" || self.teamspeak_identity.is_some() {
+            return false;
+        }
+        self.teamspeak_identity = Some(Identity::create().key().to_ts());
+        true
+    }
+
+    /// Exports this [`Mixin`] as a [`spec::v2::Mixin`].
+    ///
+    /// [`Mixin::teamspeak_identity`] is not exported, see
+    /// [`Restream::export_with_secrets()`] for that.
+    ///
+    /// [`Restream::export_with_secrets()`]: crate::state::Restream::export_with_secrets
     #[inline]
     #[must_use]
-    pub fn export(&self) -> spec::v1::Mixin {
-        spec::v1::Mixin {
+    pub fn export(&self) -> spec::v2::Mixin {
+        spec::v2::Mixin {
             src: self.src.clone(),
             volume: self.volume.export(),
             delay: self.delay,
-            sidechain: self.sidechain,
+            sidechain: self.sidechain.as_ref().map(Sidechain::export),
+            speakers: self.speakers.export(),
+            teamspeak_identity: None,
+            password: self.password.clone(),
+            channel_password: self.channel_password.clone(),
         }
     }
 }
@@ -105,6 +232,7 @@ impl Mixin {
     Eq,
     From,
     GraphQLScalar,
+    Hash,
     Into,
     PartialEq,
     Serialize,
@@ -124,11 +252,28 @@ impl MixinId {
 /// [`Url`] of a [`Mixin::src`].
 ///
 /// Only the following URLs are allowed at the moment:
-/// - [TeamSpeak] URL (starting with `ts://` scheme and having a host);
-/// - [MP3] HTTP URL (starting with `http://` or `https://` scheme, having a
-///   host and `.mp3` extension in its path).
+/// - [TeamSpeak] URL (starting with `ts://` scheme and having a host),
+///   optionally having a path of the (possibly nested) channel to join, and
+///   `password` and `channel_password` query parameters for authenticating
+///   on the server and in the channel;
+/// - [Mumble] URL (starting with `mumble://` scheme and having a host),
+///   optionally having a port, a path of the (possibly nested) channel to
+///   join, and `password` and `channel_password` query parameters, the same
+///   way as [TeamSpeak] URL has;
+/// - [MP3] or [HLS] HTTP URL (starting with `http://` or `https://` scheme,
+///   having a host and `.mp3` or `.m3u8` extension in its path);
+/// - [RTMP] URL (starting with `rtmp://` or `rtmps://` scheme and having a
+///   host);
+/// - [SRT] URL (starting with `srt://` scheme and having a host and a port);
+/// - local file URL (starting with `file:///` scheme and having a path
+///   relative to the directory of local files), optionally played in a loop
+///   if its `loop` query parameter is `true`.
 ///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
 /// [MP3]: https://en.wikipedia.org/wiki/MP3
+/// [Mumble]: https://www.mumble.info
+/// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+/// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
 /// [TeamSpeak]: https://teamspeak.com
 #[derive(
     Clone,
@@ -146,6 +291,28 @@ impl MixinId {
 pub struct MixinSrcUrl(Url);
 
 impl MixinSrcUrl {
+    /// Name of the query parameter of a `file:///` [`MixinSrcUrl`] indicating
+    /// whether the file should be played in a loop.
+    pub const LOOP_PARAM: &'static str = "loop";
+
+    /// Name of the query parameter of a `ts://` or `mumble://` [`MixinSrcUrl`]
+    /// providing the [TeamSpeak] or [Mumble] server password.
+    ///
+    /// It's moved into the [`Mixin::password`] once provided.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    pub const PASSWORD_PARAM: &'static str = "password";
+
+    /// Name of the query parameter of a `ts://` or `mumble://` [`MixinSrcUrl`]
+    /// providing the [TeamSpeak] or [Mumble] channel password.
+    ///
+    /// It's moved into the [`Mixin::channel_password`] once provided.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    pub const CHANNEL_PASSWORD_PARAM: &'static str = "channel_password";
+
     /// Creates a new [`MixinSrcUrl`] if the given [`Url`] is suitable for that.
     ///
     /// # Errors
@@ -164,17 +331,122 @@ impl MixinSrcUrl {
     /// Validates the given [`Url`] to represent a valid [`MixinSrcUrl`].
     #[must_use]
     pub fn validate(url: &Url) -> bool {
-        url.has_host()
-            && match url.scheme() {
-                "ts" => true,
-                "http" | "https" => {
-                    Path::new(url.path()).extension() == Some("mp3".as_ref())
+        match url.scheme() {
+            "ts" | "mumble" | "rtmp" | "rtmps" => url.has_host(),
+            "srt" => url.has_host() && url.port().is_some(),
+            "http" | "https" => {
+                url.has_host()
+                    && matches!(
+                        Path::new(url.path())
+                            .extension()
+                            .and_then(OsStr::to_str),
+                        Some("mp3" | "m3u8"),
+                    )
+            }
+            "file" => {
+                !url.has_host()
+                    && local_path(url).is_some()
+                    && url.query_pairs().all(|(k, v)| {
+                        k != Self::LOOP_PARAM || matches!(&*v, "true" | "false")
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the path of this `file:///` [`MixinSrcUrl`] relative to the
+    /// directory of local files.
+    ///
+    /// Returns [`None`] if this [`MixinSrcUrl`] doesn't point to a local file.
+    #[inline]
+    #[must_use]
+    pub fn local_path(&self) -> Option<PathBuf> {
+        local_path(&self.0)
+    }
+
+    /// Returns the path of the [TeamSpeak] or [Mumble] channel to join, with
+    /// its nested channels separated by `/`, like `Event/Booth EN`.
+    ///
+    /// Returns [`None`] if this [`MixinSrcUrl`] is not a `ts://` or
+    /// `mumble://` one, or an empty [`String`] if no channel is specified, so
+    /// the default one should be joined.
+    ///
+    /// [Mumble]: https://www.mumble.info
+    /// [TeamSpeak]: https://teamspeak.com
+    #[inline]
+    #[must_use]
+    pub fn voice_channel(&self) -> Option<String> {
+        voice_channel(&self.0)
+    }
+
+    /// Removes the `name`d query parameter from this [`MixinSrcUrl`],
+    /// returning its value, if there was any.
+    pub fn take_param(&mut self, name: &str) -> Option<String> {
+        let mut value = None;
+        let rest = self
+            .query_pairs()
+            .into_owned()
+            .filter(|(k, v)| {
+                if k == name {
+                    value = Some(v.clone());
                 }
-                _ => false,
+                k != name
+            })
+            .collect::<Vec<_>>();
+        if value.is_some() {
+            if rest.is_empty() {
+                self.0.set_query(None);
+            } else {
+                _ = self.0.query_pairs_mut().clear().extend_pairs(rest);
             }
+        }
+        value
+    }
+
+    /// Indicates whether the local file of this [`MixinSrcUrl`] should be
+    /// played in a loop.
+    #[inline]
+    #[must_use]
+    pub fn is_looped(&self) -> bool {
+        self.query_pairs()
+            .any(|(k, v)| k == Self::LOOP_PARAM && v == "true")
     }
 }
 
+/// Extracts a relative path of a local file from the given `file:///` [`Url`].
+///
+/// Returns [`None`] if the path is empty or escapes its root directory.
+fn local_path(url: &Url) -> Option<PathBuf> {
+    if url.scheme() != "file" {
+        return None;
+    }
+    let path = url.to_file_path().ok()?;
+    let path = path.strip_prefix("/").ok()?;
+    (path.components().count() > 0
+        && path.components().all(|c| matches!(c, Component::Normal(_))))
+    .then(|| path.to_path_buf())
+}
+
+/// Extracts a percent-decoded path of a [TeamSpeak] or [Mumble] channel from
+/// the given `ts://` or `mumble://` [`Url`].
+///
+/// Empty channel names (caused by repeated or trailing slashes) are omitted.
+///
+/// Returns [`None`] if the [`Url`] is not a `ts://` or `mumble://` one.
+///
+/// [Mumble]: https://www.mumble.info
+/// [TeamSpeak]: https://teamspeak.com
+fn voice_channel(url: &Url) -> Option<String> {
+    matches!(url.scheme(), "ts" | "mumble").then(|| {
+        url.path()
+            .split('/')
+            .filter(|c| !c.is_empty())
+            .map(|c| percent_decode_str(c).decode_utf8_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    })
+}
+
 impl<'de> Deserialize<'de> for MixinSrcUrl {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -259,3 +531,53 @@ impl Delay {
         <String as ParseScalarValue<S>>::from_str(value)
     }
 }
+
+#[cfg(test)]
+mod mixin_spec {
+    use serde_json::json;
+    use url::Url;
+
+    use crate::spec;
+
+    use super::{Mixin, MixinSrcUrl};
+
+    fn mixin(src: &str) -> Mixin {
+        Mixin::new(serde_json::from_value(json!({ "src": src })).unwrap())
+    }
+
+    #[test]
+    fn stores_passwords_out_of_src() {
+        let mixin = mixin(
+            "ts://example.com/Event?name=EN&password=a&channel_password=b",
+        );
+
+        assert_eq!(mixin.src.as_str(), "ts://example.com/Event?name=EN");
+        assert_eq!(mixin.password.as_deref(), Some("a"));
+        assert_eq!(mixin.channel_password.as_deref(), Some("b"));
+        assert_eq!(
+            mixin.export(),
+            spec::v2::Mixin {
+                password: Some("a".into()),
+                channel_password: Some("b".into()),
+                ..serde_json::from_value(json!({
+                    "src": "ts://example.com/Event?name=EN",
+                }))
+                .unwrap()
+            },
+        );
+    }
+
+    #[test]
+    fn clears_passwords_with_empty_params() {
+        let mut mixin = mixin("ts://example.com/Event?password=a");
+        mixin.src = MixinSrcUrl::new(
+            Url::parse("ts://example.com/Event?password=").unwrap(),
+        )
+        .unwrap();
+
+        assert!(mixin.extract_passwords());
+        assert_eq!(mixin.src.as_str(), "ts://example.com/Event");
+        assert_eq!(mixin.password, None);
+        assert!(!mixin.extract_passwords());
+    }
+}
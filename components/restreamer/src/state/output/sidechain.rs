@@ -0,0 +1,136 @@
+//! Side-chain compression of an `Output` with a `Mixin`.
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::{spec, types::UNumber};
+
+/// Parameters of [side-chain compression][1] ducking an `Output`'s audio
+/// while its `Mixin` is sounding.
+///
+/// [1]: https://ffmpeg.org/ffmpeg-filters.html#sidechaincompress
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct Sidechain {
+    /// Level of a `Mixin`'s audio above which an `Output`'s audio is ducked,
+    /// in dBFS.
+    pub threshold: i32,
+
+    /// Ratio by which an `Output`'s audio is ducked.
+    pub ratio: UNumber,
+
+    /// Time of ducking an `Output`'s audio once the `threshold` is exceeded,
+    /// in milliseconds.
+    pub attack: UNumber,
+
+    /// Time of restoring an `Output`'s audio once a `Mixin`'s audio falls
+    /// below the `threshold`, in milliseconds.
+    pub release: UNumber,
+
+    /// Width of the curve knee around the `threshold`, in dB.
+    pub knee: UNumber,
+
+    /// Gain applied to the ducked audio, in dB.
+    pub makeup: UNumber,
+}
+
+impl Sidechain {
+    /// Creates a new [`Sidechain`] out of the given [`spec::v2::Sidechain`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::Sidechain) -> Self {
+        Self {
+            threshold: spec.threshold,
+            ratio: spec.ratio,
+            attack: spec.attack,
+            release: spec.release,
+            knee: spec.knee,
+            makeup: spec.makeup,
+        }
+    }
+
+    /// Exports this [`Sidechain`] as a [`spec::v2::Sidechain`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::Sidechain {
+        spec::v2::Sidechain {
+            threshold: self.threshold,
+            ratio: self.ratio,
+            attack: self.attack,
+            release: self.release,
+            knee: self.knee,
+            makeup: self.makeup,
+        }
+    }
+
+    /// Validates invariants of the given [`spec::v2::Sidechain`]: its
+    /// parameters should fit into the ranges supported by [FFmpeg].
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn validate(spec: &spec::v2::Sidechain) -> Result<(), String> {
+        if !(-60..=0).contains(&spec.threshold) {
+            return Err("Sidechain.threshold should be within -60 and 0".into());
+        }
+        for (name, value, range) in [
+            ("ratio", spec.ratio, 1..=20),
+            ("attack", spec.attack, 1..=2000),
+            ("release", spec.release, 1..=9000),
+            ("knee", spec.knee, 1..=8),
+            ("makeup", spec.makeup, 0..=36),
+        ] {
+            if !range.contains(&value.0) {
+                return Err(format!(
+                    "Sidechain.{name} should be within {} and {}",
+                    range.start(),
+                    range.end(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns [FFmpeg] options of the `sidechaincompress` filter
+    /// corresponding to this [`Sidechain`], as `(name, value)` pairs.
+    ///
+    /// All of them may be tuned in real-time, as [FFmpeg] supports them as
+    /// filter commands.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn ffmpeg_options(&self) -> [(&'static str, String); 6] {
+        [
+            ("threshold", format!("{:.6}", db_to_linear(self.threshold))),
+            ("ratio", self.ratio.0.to_string()),
+            ("attack", self.attack.0.to_string()),
+            ("release", self.release.0.to_string()),
+            ("knee", self.knee.0.to_string()),
+            (
+                "makeup",
+                format!("{:.6}", db_to_linear(i32::from(self.makeup.0))),
+            ),
+        ]
+    }
+}
+
+impl Default for Sidechain {
+    fn default() -> Self {
+        Self {
+            threshold: -26,
+            ratio: UNumber(10),
+            attack: UNumber(10),
+            release: UNumber(1500),
+            knee: UNumber(4),
+            makeup: UNumber(0),
+        }
+    }
+}
+
+/// Converts the given decibels into a linear amplitude ratio.
+fn db_to_linear(db: i32) -> f64 {
+    10_f64.powf(f64::from(db) / 20.0)
+}
@@ -0,0 +1,258 @@
+//! Filtering of [TeamSpeak] channel members captured by a `Mixin`.
+//!
+//! [TeamSpeak]: https://teamspeak.com
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::{spec, state::MixinId, types::UNumber};
+
+/// Filter of [TeamSpeak] channel members whose audio is captured by a `Mixin`.
+///
+/// A member is captured if it's not denied and either is allowed explicitly or
+/// the `allow` list is empty.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(
+    Clone, Debug, Default, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct SpeakerFilter {
+    /// Channel members to capture audio of only.
+    ///
+    /// If empty, then all the not denied members are captured.
+    #[serde(default, skip_serializing_if = "SpeakerList::is_empty")]
+    pub allow: SpeakerList,
+
+    /// Channel members to never capture audio of.
+    #[serde(default, skip_serializing_if = "SpeakerList::is_empty")]
+    pub deny: SpeakerList,
+}
+
+impl SpeakerFilter {
+    /// Maximum total number of entries in a [`SpeakerFilter`].
+    pub const MAX_ENTRIES: usize = 100;
+
+    /// Creates a new [`SpeakerFilter`] out of the given
+    /// [`spec::v2::SpeakerFilter`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::SpeakerFilter) -> Self {
+        Self {
+            allow: SpeakerList::new(spec.allow),
+            deny: SpeakerList::new(spec.deny),
+        }
+    }
+
+    /// Exports this [`SpeakerFilter`] as a [`spec::v2::SpeakerFilter`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::SpeakerFilter {
+        spec::v2::SpeakerFilter {
+            allow: self.allow.export(),
+            deny: self.deny.export(),
+        }
+    }
+
+    /// Validates invariants of the given [`spec::v2::SpeakerFilter`]: it
+    /// shouldn't contain blank nicknames or UIDs, and shouldn't exceed the
+    /// [`SpeakerFilter::MAX_ENTRIES`].
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    pub fn validate(spec: &spec::v2::SpeakerFilter) -> Result<(), String> {
+        let lists = [&spec.allow, &spec.deny];
+        if lists
+            .iter()
+            .flat_map(|l| l.nicknames.iter().chain(&l.uids))
+            .any(|v| v.trim().is_empty())
+        {
+            return Err("SpeakerFilter cannot contain blank values".into());
+        }
+        let total: usize = lists
+            .iter()
+            .map(|l| l.nicknames.len() + l.uids.len() + l.server_groups.len())
+            .sum();
+        if total > Self::MAX_ENTRIES {
+            return Err(format!(
+                "SpeakerFilter cannot contain more than {} entries",
+                Self::MAX_ENTRIES,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Indicates whether this [`SpeakerFilter`] captures all the channel
+    /// members.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Indicates whether audio of a channel member with the given `nickname`,
+    /// `uid` and `server_groups` should be captured.
+    #[must_use]
+    pub fn allows(
+        &self,
+        nickname: &str,
+        uid: Option<&str>,
+        server_groups: &[u64],
+    ) -> bool {
+        !self.deny.matches(nickname, uid, server_groups)
+            && (self.allow.is_empty()
+                || self.allow.matches(nickname, uid, server_groups))
+    }
+}
+
+/// List of [TeamSpeak] channel members, identified by their nicknames, UIDs
+/// or server groups.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(
+    Clone, Debug, Default, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct SpeakerList {
+    /// Nicknames of the members, matched case-insensitively.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nicknames: Vec<String>,
+
+    /// Unique IDs of the members (base64-encoded public key hashes).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uids: Vec<String>,
+
+    /// IDs of the server groups the members belong to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub server_groups: Vec<UNumber>,
+}
+
+impl SpeakerList {
+    /// Creates a new [`SpeakerList`] out of the given
+    /// [`spec::v2::SpeakerList`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::SpeakerList) -> Self {
+        Self {
+            nicknames: spec
+                .nicknames
+                .into_iter()
+                .map(|n| n.trim().to_owned())
+                .collect(),
+            uids: spec.uids.into_iter().map(|u| u.trim().to_owned()).collect(),
+            server_groups: spec.server_groups,
+        }
+    }
+
+    /// Exports this [`SpeakerList`] as a [`spec::v2::SpeakerList`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::SpeakerList {
+        spec::v2::SpeakerList {
+            nicknames: self.nicknames.clone(),
+            uids: self.uids.clone(),
+            server_groups: self.server_groups.clone(),
+        }
+    }
+
+    /// Indicates whether this [`SpeakerList`] contains no members.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nicknames.is_empty()
+            && self.uids.is_empty()
+            && self.server_groups.is_empty()
+    }
+
+    /// Indicates whether a channel member with the given `nickname`, `uid`
+    /// and `server_groups` is contained in this [`SpeakerList`].
+    #[must_use]
+    pub fn matches(
+        &self,
+        nickname: &str,
+        uid: Option<&str>,
+        server_groups: &[u64],
+    ) -> bool {
+        self.nicknames
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(nickname))
+            || uid.map_or(false, |uid| self.uids.iter().any(|u| u == uid))
+            || self
+                .server_groups
+                .iter()
+                .any(|g| server_groups.contains(&u64::from(g.0)))
+    }
+}
+
+/// [TeamSpeak] channel member talking at the moment.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct Speaker {
+    /// Nickname of the member.
+    pub nickname: String,
+
+    /// Unique ID of the member (base64-encoded public key hash), if known.
+    pub uid: Option<String>,
+
+    /// Indicator whether audio of the member is captured, according to the
+    /// `SpeakerFilter` of its `Mixin`.
+    pub captured: bool,
+}
+
+/// [`Speaker`]s talking at the moment in a [TeamSpeak] channel of a `Mixin`.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct TalkingSpeakers {
+    /// ID of the `Mixin` capturing the channel.
+    pub mixin_id: MixinId,
+
+    /// Channel members talking at the moment.
+    pub speakers: Vec<Speaker>,
+}
+
+#[cfg(test)]
+mod speaker_filter_spec {
+    use super::{SpeakerFilter, SpeakerList};
+
+    fn list(nicknames: &[&str], uids: &[&str], groups: &[u16]) -> SpeakerList {
+        SpeakerList {
+            nicknames: nicknames.iter().map(|&n| n.to_owned()).collect(),
+            uids: uids.iter().map(|&u| u.to_owned()).collect(),
+            server_groups: groups.iter().map(|&g| g.into()).collect(),
+        }
+    }
+
+    #[test]
+    fn allows_everyone_by_default() {
+        let filter = SpeakerFilter::default();
+
+        assert!(filter.allows("Host", None, &[]));
+        assert!(filter.allows("Guest", Some("dGVzdA=="), &[8]));
+    }
+
+    #[test]
+    fn allows_only_listed() {
+        let filter = SpeakerFilter {
+            allow: list(&["host"], &["dGVzdA=="], &[6]),
+            deny: SpeakerList::default(),
+        };
+
+        assert!(filter.allows("Host", None, &[]));
+        assert!(filter.allows("Guest", Some("dGVzdA=="), &[]));
+        assert!(filter.allows("Admin", None, &[8, 6]));
+        assert!(!filter.allows("Guest", Some("b3RoZXI="), &[8]));
+    }
+
+    #[test]
+    fn denial_has_priority() {
+        let filter = SpeakerFilter {
+            allow: list(&[], &[], &[6]),
+            deny: list(&["Bot"], &[], &[]),
+        };
+
+        assert!(filter.allows("Host", None, &[6]));
+        assert!(!filter.allows("bot", None, &[6]));
+        assert!(!filter.allows("Guest", None, &[8]));
+    }
+}
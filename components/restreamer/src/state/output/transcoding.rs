@@ -0,0 +1,228 @@
+//! Transcoding profile of an `Output`.
+
+use derive_more::Display;
+use juniper::{GraphQLEnum, GraphQLObject};
+use serde::{Deserialize, Serialize};
+
+use crate::{spec, types::UNumber};
+
+/// Profile of transcoding a live stream before re-streaming it to an
+/// `Output`'s destination.
+///
+/// Unspecified parameters are left as in the source live stream, or default
+/// ones of the encoder are used.
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct TranscodingProfile {
+    /// Codec to encode video with.
+    #[serde(default)]
+    pub video_codec: VideoCodec,
+
+    /// Bitrate of the encoded video, in kbit/s.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_bitrate: Option<UNumber>,
+
+    /// Width of the encoded video, in pixels.
+    ///
+    /// If only one of `width` and `height` is specified, then the other one
+    /// is calculated preserving the aspect ratio.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<UNumber>,
+
+    /// Height of the encoded video, in pixels.
+    ///
+    /// If only one of `width` and `height` is specified, then the other one
+    /// is calculated preserving the aspect ratio.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<UNumber>,
+
+    /// Frame rate of the encoded video.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fps: Option<UNumber>,
+
+    /// Size of the GOP (distance between keyframes) of the encoded video, in
+    /// frames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gop: Option<UNumber>,
+
+    /// Codec to encode audio with.
+    #[serde(default)]
+    pub audio_codec: AudioCodec,
+
+    /// Bitrate of the encoded audio, in kbit/s.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_bitrate: Option<UNumber>,
+}
+
+impl TranscodingProfile {
+    /// Creates a new [`TranscodingProfile`] out of the given
+    /// [`spec::v2::TranscodingProfile`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::TranscodingProfile) -> Self {
+        Self {
+            video_codec: spec.video_codec,
+            video_bitrate: spec.video_bitrate,
+            width: spec.width,
+            height: spec.height,
+            fps: spec.fps,
+            gop: spec.gop,
+            audio_codec: spec.audio_codec,
+            audio_bitrate: spec.audio_bitrate,
+        }
+    }
+
+    /// Exports this [`TranscodingProfile`] as a
+    /// [`spec::v2::TranscodingProfile`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::TranscodingProfile {
+        spec::v2::TranscodingProfile {
+            video_codec: self.video_codec,
+            video_bitrate: self.video_bitrate,
+            width: self.width,
+            height: self.height,
+            fps: self.fps,
+            gop: self.gop,
+            audio_codec: self.audio_codec,
+            audio_bitrate: self.audio_bitrate,
+        }
+    }
+
+    /// Validates invariants of the given [`spec::v2::TranscodingProfile`]:
+    /// its parameters should be supported by the encoders.
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    pub fn validate(spec: &spec::v2::TranscodingProfile) -> Result<(), String> {
+        let positive = [
+            ("videoBitrate", spec.video_bitrate),
+            ("width", spec.width),
+            ("height", spec.height),
+            ("fps", spec.fps),
+            ("gop", spec.gop),
+        ];
+        for (name, val) in positive {
+            if val.map_or(false, |v| v.0 == 0) {
+                return Err(format!(
+                    "TranscodingProfile.{name} should be positive",
+                ));
+            }
+        }
+        // Chroma subsampling of the encoded video requires even dimensions.
+        for (name, val) in [("width", spec.width), ("height", spec.height)] {
+            if val.map_or(false, |v| v.0 % 2 != 0) {
+                return Err(format!(
+                    "TranscodingProfile.{name} should be even"
+                ));
+            }
+        }
+        if spec.fps.map_or(false, |v| v.0 > 120) {
+            return Err("TranscodingProfile.fps should not exceed 120".into());
+        }
+        if spec
+            .audio_bitrate
+            .map_or(false, |v| !(16..=320).contains(&v.0))
+        {
+            return Err(
+                "TranscodingProfile.audioBitrate should be within 16 and 320"
+                    .into(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Codec to encode video of a `TranscodingProfile` with.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Display,
+    Eq,
+    GraphQLEnum,
+    PartialEq,
+    Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoCodec {
+    /// [H.264] video codec.
+    ///
+    /// [H.264]: https://en.wikipedia.org/wiki/Advanced_Video_Coding
+    #[default]
+    #[display(fmt = "h264")]
+    H264,
+
+    /// [H.265] video codec.
+    ///
+    /// [H.265]: https://en.wikipedia.org/wiki/High_Efficiency_Video_Coding
+    #[display(fmt = "h265")]
+    H265,
+}
+
+impl VideoCodec {
+    /// Returns name of the [FFmpeg] encoder of this [`VideoCodec`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[inline]
+    #[must_use]
+    pub fn encoder(self) -> &'static str {
+        match self {
+            Self::H264 => "libx264",
+            Self::H265 => "libx265",
+        }
+    }
+}
+
+/// Codec to encode audio of a `TranscodingProfile` with.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Display,
+    Eq,
+    GraphQLEnum,
+    PartialEq,
+    Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioCodec {
+    /// [AAC] audio codec.
+    ///
+    /// [AAC]: https://en.wikipedia.org/wiki/Advanced_Audio_Coding
+    #[default]
+    #[display(fmt = "aac")]
+    Aac,
+
+    /// [MP3] audio codec.
+    ///
+    /// [MP3]: https://en.wikipedia.org/wiki/MP3
+    #[display(fmt = "mp3")]
+    Mp3,
+}
+
+impl AudioCodec {
+    /// Returns name of the [FFmpeg] encoder of this [`AudioCodec`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[inline]
+    #[must_use]
+    pub fn encoder(self) -> &'static str {
+        match self {
+            Self::Aac => {
+                if cfg!(target_os = "linux") {
+                    "libfdk_aac"
+                } else {
+                    "aac"
+                }
+            }
+            Self::Mp3 => "libmp3lame",
+        }
+    }
+}
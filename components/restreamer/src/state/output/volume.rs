@@ -1,5 +1,6 @@
 //! Volume rate of an audio track in percents and flag if it is muted.
 use crate::spec;
+use ephyr_serde::is_false;
 use juniper::{
     GraphQLObject, GraphQLScalar, InputValue, ParseScalarResult,
     ParseScalarValue, ScalarToken, ScalarValue, Value,
@@ -17,6 +18,10 @@ pub struct Volume {
     pub level: VolumeLevel,
     /// Whether it is muted or not
     pub muted: bool,
+    /// Whether it is soloed, so all the other non-soloed audio tracks mixed
+    /// with it are muted
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub solo: bool,
 }
 
 impl Volume {
@@ -25,17 +30,19 @@ impl Volume {
     pub const ORIGIN: Volume = Volume {
         level: VolumeLevel::ORIGIN,
         muted: false,
+        solo: false,
     };
 
     /// Creates a new [`Volume`] rate value if it satisfies the required
     /// invariants:
     /// - within [`VolumeLevel::OFF`] and [`VolumeLevel::MAX`] values.
     #[must_use]
-    pub fn new(num: &spec::v1::Volume) -> Self {
+    pub fn new(num: &spec::v2::Volume) -> Self {
         VolumeLevel::new(num.level.0).map_or_else(Self::default, |volume| {
             Self {
                 level: volume,
                 muted: num.muted,
+                solo: num.solo,
             }
         })
     }
@@ -51,6 +58,21 @@ impl Volume {
         }
     }
 
+    /// Returns the effective [`Volume`] of this audio track when it's mixed
+    /// with other ones, considering whether any of them is `soloing`: all the
+    /// non-soloed tracks are muted then.
+    ///
+    /// [`Volume::solo`] of the returned [`Volume`] is always `false`, as it's
+    /// already applied.
+    #[must_use]
+    pub fn effective(&self, soloing: bool) -> Self {
+        Self {
+            level: self.level,
+            muted: self.muted || (soloing && !self.solo),
+            solo: false,
+        }
+    }
+
     /// Indicates whether this [`Volume`] rate value corresponds is the
     /// [`Volume::ORIGIN`]al one.
     #[allow(clippy::trivially_copy_pass_by_ref)] // required for `serde`
@@ -60,13 +82,14 @@ impl Volume {
         *self == Self::ORIGIN
     }
 
-    /// Export this struct as [`spec::v1::Volume`]
+    /// Export this struct as [`spec::v2::Volume`]
     #[inline]
     #[must_use]
-    pub fn export(&self) -> spec::v1::Volume {
-        spec::v1::Volume {
+    pub fn export(&self) -> spec::v2::Volume {
+        spec::v2::Volume {
             level: self.level,
             muted: self.muted,
+            solo: self.solo,
         }
     }
 }
@@ -86,6 +109,7 @@ impl TryFrom<VolumeLevel> for Volume {
         Ok(Volume {
             level: value,
             muted: false,
+            solo: false,
         })
     }
 }
@@ -163,64 +187,72 @@ impl VolumeLevel {
 #[cfg(test)]
 mod volume_spec {
     use super::{Volume, VolumeLevel};
-    use crate::spec::v1;
+    use crate::spec::v2;
 
     #[test]
     fn displays_as_fraction() {
         for (input, expected) in &[
             (
-                v1::Volume {
+                v2::Volume {
                     level: VolumeLevel(1),
                     muted: false,
+                    solo: false,
                 },
                 "0.01",
             ),
             (
-                v1::Volume {
+                v2::Volume {
                     level: VolumeLevel(10),
                     muted: false,
+                    solo: false,
                 },
                 "0.10",
             ),
             (
-                v1::Volume {
+                v2::Volume {
                     level: VolumeLevel(200),
                     muted: false,
+                    solo: false,
                 },
                 "2.00",
             ),
             (
-                v1::Volume {
+                v2::Volume {
                     level: VolumeLevel(107),
                     muted: false,
+                    solo: false,
                 },
                 "1.07",
             ),
             (
-                v1::Volume {
+                v2::Volume {
                     level: VolumeLevel(170),
                     muted: false,
+                    solo: false,
                 },
                 "1.70",
             ),
             (
-                v1::Volume {
+                v2::Volume {
                     level: VolumeLevel(1000),
                     muted: false,
+                    solo: false,
                 },
                 "10.00",
             ),
             (
-                v1::Volume {
+                v2::Volume {
                     level: VolumeLevel(0),
                     muted: false,
+                    solo: false,
                 },
                 "0.00",
             ),
             (
-                v1::Volume {
+                v2::Volume {
                     level: VolumeLevel(200),
                     muted: true,
+                    solo: false,
                 },
                 "0.00",
             ),
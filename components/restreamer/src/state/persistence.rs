@@ -0,0 +1,490 @@
+//! Crash-safe persistence of a [`State`] into a file.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use chrono::Utc;
+use ephyr_log::tracing;
+use tokio::{
+    fs,
+    io::AsyncWriteExt as _,
+    sync::{Mutex, Notify},
+    time,
+};
+
+use crate::{state::State, Spec};
+
+/// Persistence of a [`State`] into a file.
+///
+/// The file is written atomically (via a temporary file being renamed), and
+/// its timestamped backups are rotated next to it.
+#[derive(Clone, Debug)]
+pub(crate) struct Persistence {
+    /// Path to the file to persist a [`State`] in.
+    file: PathBuf,
+
+    /// Maximum number of backups of the [`Persistence::file`] to keep.
+    backups: usize,
+
+    /// Serialized [`State`] written into the [`Persistence::file`] last time.
+    ///
+    /// Being locked, it also prevents concurrent writes of the
+    /// [`Persistence::file`].
+    last_persisted: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Persistence {
+    /// Duration to wait for more [`State`] changes before persisting it, so
+    /// bursts of changes are written only once.
+    const DEBOUNCE: Duration = Duration::from_millis(500);
+
+    /// Duration to wait before retrying to persist a [`State`] after a
+    /// failure.
+    const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Minimal interval between creating new backups of the
+    /// [`Persistence::file`].
+    const BACKUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+    /// Extension of backup files.
+    const BACKUP_EXT: &'static str = "bak";
+
+    /// Creates a new [`Persistence`] of the given `file`, keeping at most
+    /// `backups` of it.
+    #[must_use]
+    pub(crate) fn new<P: AsRef<Path>>(file: P, backups: usize) -> Self {
+        Self {
+            file: file.as_ref().to_owned(),
+            backups,
+            last_persisted: Arc::default(),
+        }
+    }
+
+    /// Loads a [`State`] from the [`Persistence::file`].
+    ///
+    /// If the file is corrupted, then falls back to the newest valid backup.
+    /// Returns [`None`] if the file is empty (or doesn't exist yet).
+    ///
+    /// # Errors
+    ///
+    /// - If the [`Persistence::file`] cannot be opened or read.
+    /// - If neither the [`Persistence::file`], nor any of its backups can be
+    ///   parsed.
+    pub(crate) async fn load(&self) -> Result<Option<State>, anyhow::Error> {
+        let file = &self.file;
+
+        // Opening with creation ensures the file is writable before anything
+        // is run.
+        drop(
+            fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(file)
+                .await
+                .map_err(|e| {
+                    anyhow!("Failed to open '{}' file: {}", file.display(), e)
+                })?,
+        );
+        let contents = fs::read(file).await.map_err(|e| {
+            anyhow!("Failed to read '{}' file: {}", file.display(), e)
+        })?;
+        if contents.is_empty() {
+            return Ok(None);
+        }
+
+        let err = match Self::parse(&contents, file) {
+            Ok(state) => return Ok(Some(state)),
+            Err(e) => e,
+        };
+        tracing::error!(
+            "Failed to deserialize state from '{}' file: {err}",
+            file.display(),
+        );
+
+        for backup in self.list_backups().await {
+            let Ok(contents) = fs::read(&backup).await else {
+                continue;
+            };
+            match Self::parse(&contents, &backup) {
+                Ok(state) => {
+                    tracing::warn!(
+                        "Restored state from '{}' backup file",
+                        backup.display(),
+                    );
+                    return Ok(Some(state));
+                }
+                Err(e) => tracing::error!(
+                    "Failed to deserialize state from '{}' backup file: {e}",
+                    backup.display(),
+                ),
+            }
+        }
+
+        Err(anyhow!(
+            "Failed to deserialize state from '{}' file: {}",
+            file.display(),
+            err,
+        ))
+    }
+
+    /// Parses a [`State`] out of the given `contents` of the `file`.
+    ///
+    /// The `contents` may be an exported [`Spec`] of any version (an archived
+    /// config, for example), so it's migrated into a [`State`] in such case.
+    fn parse(contents: &[u8], file: &Path) -> Result<State, serde_json::Error> {
+        serde_json::from_slice(contents).or_else(|e| {
+            let spec =
+                serde_json::from_slice::<Spec>(contents).map_err(|_| e)?;
+            tracing::info!(
+                "Migrating state from spec in '{}' file",
+                file.display(),
+            );
+            let state = State::default();
+            state.apply(spec, true);
+            Ok(state)
+        })
+    }
+
+    /// Spawns a background task persisting the given [`State`] each time the
+    /// returned [`Notify`] is notified.
+    ///
+    /// Notifications are debounced, so a burst of them results in a single
+    /// write.
+    pub(crate) fn spawn(self, state: State) -> Arc<Notify> {
+        let notify = Arc::new(Notify::new());
+        let notified = Arc::clone(&notify);
+        drop(tokio::spawn(async move {
+            let mut last_backup: Option<Instant> = None;
+            loop {
+                notified.notified().await;
+                time::sleep(Self::DEBOUNCE).await;
+
+                match self.write(&state).await {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed to persist server state, retrying in \
+                             {:?}: {e}",
+                            Self::RETRY_INTERVAL,
+                        );
+                        time::sleep(Self::RETRY_INTERVAL).await;
+                        notified.notify_one();
+                        continue;
+                    }
+                }
+
+                if last_backup
+                    .map_or(true, |at| at.elapsed() >= Self::BACKUP_INTERVAL)
+                {
+                    match self.backup().await {
+                        Ok(()) => last_backup = Some(Instant::now()),
+                        Err(e) => tracing::error!(
+                            "Failed to backup server state: {e}",
+                        ),
+                    }
+                }
+            }
+        }));
+        notify
+    }
+
+    /// Immediately writes the given [`State`] into the [`Persistence::file`]
+    /// if it has changed, without waiting for debounced changes.
+    ///
+    /// # Errors
+    ///
+    /// If the [`State`] fails to be serialized or written.
+    pub(crate) async fn flush(
+        &self,
+        state: &State,
+    ) -> Result<(), anyhow::Error> {
+        _ = self.write(state).await?;
+        Ok(())
+    }
+
+    /// Writes the given [`State`] into the [`Persistence::file`], unless it
+    /// hasn't changed since the last write.
+    ///
+    /// Returns `true` if the [`Persistence::file`] has been written.
+    ///
+    /// # Errors
+    ///
+    /// If the [`State`] fails to be serialized or written.
+    async fn write(&self, state: &State) -> Result<bool, anyhow::Error> {
+        let contents = serde_json::to_vec(state)
+            .map_err(|e| anyhow!("Failed to serialize server state: {e}"))?;
+
+        let mut last_persisted = self.last_persisted.lock().await;
+        // Most of changes are runtime-only (statuses, progress), so don't
+        // touch the file if nothing persistent has changed.
+        if contents == *last_persisted {
+            return Ok(false);
+        }
+        self.persist(&contents).await?;
+        *last_persisted = contents;
+        Ok(true)
+    }
+
+    /// Atomically writes the given serialized [`State`] `contents` into the
+    /// [`Persistence::file`].
+    ///
+    /// # Errors
+    ///
+    /// If the `contents` fail to be written.
+    async fn persist(&self, contents: &[u8]) -> Result<(), anyhow::Error> {
+        let tmp = self.sibling("tmp");
+        let mut f = fs::File::create(&tmp).await.map_err(|e| {
+            anyhow!("Failed to create '{}' file: {}", tmp.display(), e)
+        })?;
+        f.write_all(contents).await.map_err(|e| {
+            anyhow!("Failed to write '{}' file: {}", tmp.display(), e)
+        })?;
+        f.sync_all().await.map_err(|e| {
+            anyhow!("Failed to sync '{}' file: {}", tmp.display(), e)
+        })?;
+        drop(f);
+
+        // Renaming is the only way to replace the file atomically, so the
+        // file is never overwritten in place. Note, that renaming fails if the
+        // file is a mount point, so its directory should be mounted instead.
+        if let Err(e) = fs::rename(&tmp, &self.file).await {
+            _ = fs::remove_file(&tmp).await;
+            return Err(anyhow!(
+                "Failed to rename '{}' file to '{}': {}",
+                tmp.display(),
+                self.file.display(),
+                e,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Creates a new timestamped backup of the [`Persistence::file`] and
+    /// removes the oldest ones exceeding the [`Persistence::backups`] limit.
+    ///
+    /// # Errors
+    ///
+    /// If the [`Persistence::file`] fails to be copied.
+    async fn backup(&self) -> Result<(), anyhow::Error> {
+        if self.backups == 0 {
+            return Ok(());
+        }
+
+        let backup = self.sibling(&format!(
+            "{}.{}",
+            Utc::now().format("%Y%m%d%H%M%S%3f"),
+            Self::BACKUP_EXT,
+        ));
+        _ = fs::copy(&self.file, &backup).await.map_err(|e| {
+            anyhow!("Failed to create '{}' backup: {}", backup.display(), e)
+        })?;
+
+        for outdated in self.list_backups().await.into_iter().skip(self.backups)
+        {
+            if let Err(e) = fs::remove_file(&outdated).await {
+                tracing::warn!(
+                    "Failed to remove '{}' backup: {e}",
+                    outdated.display(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists all the existing backups of the [`Persistence::file`], ordered
+    /// from the newest to the oldest one.
+    async fn list_backups(&self) -> Vec<PathBuf> {
+        let Some(name) = self.file.file_name().and_then(|n| n.to_str()) else {
+            return vec![];
+        };
+        let prefix = format!("{name}.");
+        let suffix = format!(".{}", Self::BACKUP_EXT);
+
+        let mut backups = vec![];
+        let Ok(mut dir) = fs::read_dir(self.dir()).await else {
+            return backups;
+        };
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            if entry.file_name().to_str().map_or(false, |n| {
+                n.starts_with(&prefix) && n.ends_with(&suffix)
+            }) {
+                backups.push(entry.path());
+            }
+        }
+        // Timestamps in names are sortable lexicographically.
+        backups.sort_unstable_by(|a, b| b.cmp(a));
+        backups
+    }
+
+    /// Returns a path of the file next to the [`Persistence::file`] with the
+    /// given `ext`ension appended to its name.
+    fn sibling(&self, ext: &str) -> PathBuf {
+        let mut name = self.file.file_name().unwrap_or_default().to_owned();
+        name.push(".");
+        name.push(ext);
+        self.dir().join(name)
+    }
+
+    /// Returns the directory containing the [`Persistence::file`].
+    fn dir(&self) -> &Path {
+        self.file
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."))
+    }
+}
+
+#[cfg(test)]
+mod persistence_spec {
+    use std::{path::PathBuf, time::Duration};
+
+    use tokio::{fs, time};
+    use uuid::Uuid;
+
+    use super::{Persistence, State};
+
+    /// Creates a new empty directory to place test files into.
+    async fn tmp_dir() -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("ephyr-persistence-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).await.unwrap();
+        dir
+    }
+
+    /// Serializes a [`State`] with the given `title` of its settings.
+    fn state_json(title: &str) -> Vec<u8> {
+        let state = State::default();
+        state.settings.lock_mut().title = Some(title.into());
+        serde_json::to_vec(&state).unwrap()
+    }
+
+    /// Returns the title of the given [`State`]'s settings.
+    fn title(state: &State) -> Option<String> {
+        state.settings.lock_ref().title.clone()
+    }
+
+    #[tokio::test]
+    async fn loads_nothing_from_empty_file() {
+        let dir = tmp_dir().await;
+        let p = Persistence::new(dir.join("state.json"), 3);
+
+        assert!(p.load().await.unwrap().is_none());
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_newest_valid_backup() {
+        let dir = tmp_dir().await;
+        let p = Persistence::new(dir.join("state.json"), 3);
+        fs::write(&p.file, b"{corrupted").await.unwrap();
+        let backups = [
+            ("20240101000000000", state_json("oldest")),
+            ("20240102000000000", state_json("newest valid")),
+            ("20240103000000000", b"{corrupted".to_vec()),
+        ];
+        for (ts, contents) in backups {
+            fs::write(p.sibling(&format!("{ts}.bak")), contents)
+                .await
+                .unwrap();
+        }
+
+        let state = p.load().await.unwrap().unwrap();
+        assert_eq!(title(&state).as_deref(), Some("newest valid"));
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn fails_when_no_valid_backup() {
+        let dir = tmp_dir().await;
+        let p = Persistence::new(dir.join("state.json"), 3);
+        fs::write(&p.file, b"{corrupted").await.unwrap();
+        fs::write(p.sibling("20240101000000000.bak"), b"[]")
+            .await
+            .unwrap();
+
+        assert!(p.load().await.is_err());
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rotates_backups() {
+        let dir = tmp_dir().await;
+        let p = Persistence::new(dir.join("state.json"), 2);
+
+        let mut created = vec![];
+        for n in 0..4 {
+            p.persist(&state_json(&n.to_string())).await.unwrap();
+            p.backup().await.unwrap();
+            created.push(p.list_backups().await.remove(0));
+            // Backups are timestamped with milliseconds precision.
+            time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let backups = p.list_backups().await;
+        assert_eq!(backups, vec![created[3].clone(), created[2].clone()]);
+        let newest = fs::read(&backups[0]).await.unwrap();
+        assert_eq!(newest, state_json("3"));
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn keeps_no_backups_if_disabled() {
+        let dir = tmp_dir().await;
+        let p = Persistence::new(dir.join("state.json"), 0);
+
+        p.persist(&state_json("title")).await.unwrap();
+        p.backup().await.unwrap();
+
+        assert!(p.list_backups().await.is_empty());
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn debounces_writes() {
+        let dir = tmp_dir().await;
+        let p = Persistence::new(dir.join("state.json"), 3);
+        let file = p.file.clone();
+        let state = State::default();
+        let notify = p.clone().spawn(state.clone());
+
+        for n in 0..10 {
+            state.settings.lock_mut().title = Some(n.to_string());
+            notify.notify_one();
+        }
+        time::sleep(Persistence::DEBOUNCE / 2).await;
+        assert!(fs::metadata(&file).await.is_err(), "written too early");
+
+        time::sleep(Persistence::DEBOUNCE).await;
+        assert_eq!(fs::read(&file).await.unwrap(), state_json("9"));
+        assert_eq!(p.list_backups().await.len(), 1);
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn flushes_pending_changes() {
+        let dir = tmp_dir().await;
+        let p = Persistence::new(dir.join("state.json"), 3);
+        let state = State::default();
+        let notify = p.clone().spawn(state.clone());
+
+        state.settings.lock_mut().title = Some("pending".into());
+        notify.notify_one();
+        p.flush(&state).await.unwrap();
+
+        assert_eq!(fs::read(&p.file).await.unwrap(), state_json("pending"));
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+}
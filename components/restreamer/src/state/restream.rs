@@ -11,7 +11,7 @@ use uuid::Uuid;
 
 use crate::{
     spec,
-    state::{Input, Label, Output, Playlist},
+    state::{Input, Label, Output, Playlist, Schedule},
 };
 
 /// Re-stream of a live stream from one `Input` to many `Output`s.
@@ -44,13 +44,17 @@ pub struct Restream {
 
     /// If need to set input playback encoding
     pub with_playback_encoding: bool,
+
+    /// `Schedule`s of enabling and disabling this `Restream`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<Schedule>,
 }
 
 impl Restream {
-    /// Creates a new [`Restream`] out of the given [`spec::v1::Restream`].
+    /// Creates a new [`Restream`] out of the given [`spec::v2::Restream`].
     #[inline]
     #[must_use]
-    pub fn new(spec: spec::v1::Restream) -> Self {
+    pub fn new(spec: spec::v2::Restream) -> Self {
         Self {
             id: RestreamId::random(),
             key: spec.key,
@@ -58,22 +62,21 @@ impl Restream {
             input: Input::new(spec.input),
             outputs: spec.outputs.into_iter().map(Output::new).collect(),
             playlist: Playlist::new(spec.playlist),
-            with_playback_encoding: spec
-                .with_playback_encoding
-                .unwrap_or(false),
+            with_playback_encoding: spec.with_playback_encoding,
+            schedules: spec.schedules.into_iter().map(Schedule::new).collect(),
         }
     }
 
-    /// Applies the given [`spec::v1::Restream`] to this [`Restream`].
+    /// Applies the given [`spec::v2::Restream`] to this [`Restream`].
     ///
-    /// If `replace` is `true` then all the [`Restream::outputs`] will be
-    /// replaced with new ones, otherwise new ones will be merged with already
-    /// existing [`Restream::outputs`].
-    pub fn apply(&mut self, new: spec::v1::Restream, replace: bool) {
+    /// If `replace` is `true` then all the [`Restream::outputs`] and
+    /// [`Restream::schedules`] will be replaced with new ones, otherwise new
+    /// ones will be merged with already existing ones.
+    pub fn apply(&mut self, new: spec::v2::Restream, replace: bool) {
         self.key = new.key;
         self.label = new.label;
-        self.with_playback_encoding =
-            new.with_playback_encoding.unwrap_or(false);
+        self.with_playback_encoding = new.with_playback_encoding;
+        Schedule::apply_all(&mut self.schedules, new.schedules, replace);
         self.input.apply(new.input);
 
         if let Some(p) = new.playlist {
@@ -111,19 +114,37 @@ impl Restream {
         }
     }
 
-    /// Exports this [`Restream`] as a [`spec::v1::Restream`].
+    /// Exports this [`Restream`] as a [`spec::v2::Restream`].
     #[inline]
     #[must_use]
-    pub fn export(&self) -> spec::v1::Restream {
-        spec::v1::Restream {
+    pub fn export(&self) -> spec::v2::Restream {
+        spec::v2::Restream {
             id: Some(self.id),
             key: self.key.clone(),
             label: self.label.clone(),
             playlist: Some(self.playlist.export()),
             input: self.input.export(),
             outputs: self.outputs.iter().map(Output::export).collect(),
-            with_playback_encoding: Some(self.with_playback_encoding),
+            with_playback_encoding: self.with_playback_encoding,
+            schedules: self.schedules.iter().map(Schedule::export).collect(),
+        }
+    }
+
+    /// Exports this [`Restream`] as a [`spec::v2::Restream`] along with the
+    /// secrets not exported by [`Restream::export()`] (like
+    /// [`Mixin::teamspeak_identity`]s).
+    ///
+    /// [`Mixin::teamspeak_identity`]: crate::state::Mixin::teamspeak_identity
+    #[must_use]
+    pub fn export_with_secrets(&self) -> spec::v2::Restream {
+        let mut spec = self.export();
+        for (output, spec) in self.outputs.iter().zip(&mut spec.outputs) {
+            for (mixin, spec) in output.mixins.iter().zip(&mut spec.mixins) {
+                spec.teamspeak_identity
+                    .clone_from(&mixin.teamspeak_identity);
+            }
         }
+        spec
     }
 
     /// Returns an URL on a local [SRS] server of the endpoint representing a
@@ -0,0 +1,106 @@
+//! Policy of restarting failed re-streaming processes.
+
+use std::time::Duration;
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::{spec, types::UNumber};
+
+/// Policy of restarting a failed re-streaming process.
+///
+/// Restarts are delayed with an exponential backoff: each consecutive failure
+/// doubles the delay, until it reaches the `maxDelay`.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct RetryPolicy {
+    /// Delay before the first restart after a failure, in seconds.
+    pub initial_delay: UNumber,
+
+    /// Maximum delay between restarts, in seconds.
+    pub max_delay: UNumber,
+
+    /// Maximum number of consecutive failures, after which an `Output` is
+    /// disabled automatically.
+    ///
+    /// If `null`, then restarts are performed infinitely.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<UNumber>,
+}
+
+impl RetryPolicy {
+    /// Creates a new [`RetryPolicy`] out of the given
+    /// [`spec::v2::RetryPolicy`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::RetryPolicy) -> Self {
+        Self {
+            initial_delay: spec.initial_delay,
+            max_delay: spec.max_delay,
+            max_attempts: spec.max_attempts,
+        }
+    }
+
+    /// Exports this [`RetryPolicy`] as a [`spec::v2::RetryPolicy`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::RetryPolicy {
+        spec::v2::RetryPolicy {
+            initial_delay: self.initial_delay,
+            max_delay: self.max_delay,
+            max_attempts: self.max_attempts,
+        }
+    }
+
+    /// Validates invariants of the given [`spec::v2::RetryPolicy`]: its
+    /// delays should be positive and ordered, and at least one attempt
+    /// should be allowed.
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    pub fn validate(spec: &spec::v2::RetryPolicy) -> Result<(), String> {
+        if spec.initial_delay.0 == 0 {
+            return Err("RetryPolicy.initialDelay should be positive".into());
+        }
+        if spec.max_delay.0 < spec.initial_delay.0 {
+            return Err("RetryPolicy.maxDelay should not be less than \
+                        RetryPolicy.initialDelay"
+                .into());
+        }
+        if spec.max_attempts.map_or(false, |n| n.0 == 0) {
+            return Err("RetryPolicy.maxAttempts should be positive".into());
+        }
+        Ok(())
+    }
+
+    /// Returns the delay before restarting after the given number of
+    /// consecutive `failures`.
+    #[must_use]
+    pub fn delay(&self, failures: u32) -> Duration {
+        let exp = failures.saturating_sub(1).min(16);
+        let secs = (u64::from(self.initial_delay.0) << exp)
+            .min(u64::from(self.max_delay.0));
+        Duration::from_secs(secs)
+    }
+
+    /// Indicates whether the given number of consecutive `failures` exhausts
+    /// this [`RetryPolicy`], so no more restarts should be performed.
+    #[inline]
+    #[must_use]
+    pub fn is_exhausted(&self, failures: u32) -> bool {
+        self.max_attempts
+            .map_or(false, |max| failures >= u32::from(max.0))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: UNumber(2),
+            max_delay: UNumber(60),
+            max_attempts: None,
+        }
+    }
+}
@@ -0,0 +1,414 @@
+//! Time-based schedules of enabling and disabling `Restream`s and `Output`s.
+
+use chrono::{DateTime, Duration, Utc};
+use derive_more::{Display, From, Into};
+use juniper::{GraphQLEnum, GraphQLObject, GraphQLScalar};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    spec,
+    state::{OutputId, RestreamId},
+};
+
+/// Time window during which a `Restream` or an `Output` should be enabled.
+///
+/// It's enabled at the window's `start` and disabled at its `end`.
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct Schedule {
+    /// Unique ID of this `Schedule`.
+    ///
+    /// Once assigned, it never changes.
+    pub id: ScheduleId,
+
+    /// Moment when the first window of this `Schedule` starts.
+    pub start: DateTime<Utc>,
+
+    /// Moment when the first window of this `Schedule` ends.
+    pub end: DateTime<Utc>,
+
+    /// Period of repeating the window of this `Schedule`.
+    #[serde(default)]
+    pub repeat: ScheduleRepeat,
+}
+
+impl Schedule {
+    /// Creates a new [`Schedule`] out of the given [`spec::v2::Schedule`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::Schedule) -> Self {
+        Self {
+            id: ScheduleId::random(),
+            start: spec.start,
+            end: spec.end,
+            repeat: spec.repeat,
+        }
+    }
+
+    /// Applies the given [`spec::v2::Schedule`]s to the `olds` ones, retaining
+    /// IDs of the unchanged ones.
+    ///
+    /// If `replace` is `true` then the `olds` are replaced with the new ones,
+    /// otherwise the new ones are merged into the `olds`.
+    pub fn apply_all(
+        olds: &mut Vec<Self>,
+        news: Vec<spec::v2::Schedule>,
+        replace: bool,
+    ) {
+        if replace {
+            *olds = news
+                .into_iter()
+                .map(|new| {
+                    olds.iter()
+                        .find(|o| o.export() == new)
+                        .cloned()
+                        .unwrap_or_else(|| Self::new(new))
+                })
+                .collect();
+        } else {
+            for new in news {
+                if !olds.iter().any(|o| o.export() == new) {
+                    olds.push(Self::new(new));
+                }
+            }
+        }
+    }
+
+    /// Exports this [`Schedule`] as a [`spec::v2::Schedule`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v2::Schedule {
+        spec::v2::Schedule {
+            start: self.start,
+            end: self.end,
+            repeat: self.repeat,
+        }
+    }
+
+    /// Validates invariants of the given window: its `start` should precede
+    /// its `end`, and it should fit into the `repeat` period.
+    ///
+    /// # Errors
+    ///
+    /// With a human-readable description of the violated invariant.
+    pub fn validate(
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        repeat: ScheduleRepeat,
+    ) -> Result<(), String> {
+        if start >= end {
+            return Err("Schedule.start should precede Schedule.end".into());
+        }
+        if let Some(period) = repeat.period() {
+            if end - start > period {
+                return Err(format!(
+                    "Schedule window should not be longer than its {repeat} \
+                     repeat period",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all the [`ScheduleAction`]s of this [`Schedule`] happening
+    /// within the `(from, to]` time range, ordered by their time.
+    #[must_use]
+    pub fn actions_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, ScheduleAction)> {
+        let mut actions = vec![];
+        for (at, action) in [
+            (self.start, ScheduleAction::Enable),
+            (self.end, ScheduleAction::Disable),
+        ] {
+            match self.repeat.period() {
+                None => {
+                    if from < at && at <= to {
+                        actions.push((at, action));
+                    }
+                }
+                Some(period) => {
+                    // Skip occurrences before `from` without iterating them.
+                    let skip = if from > at {
+                        (from - at).num_seconds() / period.num_seconds()
+                    } else {
+                        0
+                    };
+                    let mut at =
+                        at + period * i32::try_from(skip).unwrap_or_default();
+                    while at <= to {
+                        if from < at {
+                            actions.push((at, action));
+                        }
+                        at = at + period;
+                    }
+                }
+            }
+        }
+        actions.sort_by_key(|(at, _)| *at);
+        actions
+    }
+
+    /// Returns the nearest [`ScheduleAction`]s of this [`Schedule`] happening
+    /// after the given moment (at most one of each kind).
+    #[must_use]
+    pub fn next_actions(
+        &self,
+        after: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, ScheduleAction)> {
+        let horizon = self
+            .repeat
+            .period()
+            .map_or(self.end, |p| after.max(self.end) + p);
+        self.actions_between(after, horizon)
+            .into_iter()
+            .take(2)
+            .collect()
+    }
+}
+
+/// ID of a `Schedule`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Display,
+    Eq,
+    From,
+    GraphQLScalar,
+    Hash,
+    Into,
+    PartialEq,
+    Serialize,
+)]
+#[graphql(transparent)]
+pub struct ScheduleId(Uuid);
+
+impl ScheduleId {
+    /// Generates a new random [`ScheduleId`].
+    #[inline]
+    #[must_use]
+    pub fn random() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+/// Period of repeating a `Schedule` window.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Display,
+    Eq,
+    GraphQLEnum,
+    PartialEq,
+    Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleRepeat {
+    /// `Schedule` window happens only once.
+    #[default]
+    #[display(fmt = "once")]
+    Once,
+
+    /// `Schedule` window repeats every day.
+    #[display(fmt = "daily")]
+    Daily,
+
+    /// `Schedule` window repeats every week.
+    #[display(fmt = "weekly")]
+    Weekly,
+}
+
+impl ScheduleRepeat {
+    /// Returns the period of repeating, if any.
+    #[must_use]
+    pub fn period(self) -> Option<Duration> {
+        match self {
+            Self::Once => None,
+            Self::Daily => Some(Duration::days(1)),
+            Self::Weekly => Some(Duration::weeks(1)),
+        }
+    }
+
+    /// Indicates whether this [`ScheduleRepeat`] is [`ScheduleRepeat::Once`].
+    #[allow(clippy::trivially_copy_pass_by_ref)] // required for `serde`
+    #[inline]
+    #[must_use]
+    pub fn is_once(&self) -> bool {
+        *self == Self::Once
+    }
+}
+
+/// Action performed by a `Schedule`.
+#[derive(Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq)]
+pub enum ScheduleAction {
+    /// Enabling a `Restream` or an `Output`.
+    Enable,
+
+    /// Disabling a `Restream` or an `Output`.
+    Disable,
+}
+
+/// Upcoming `ScheduleAction` to be performed on a `Restream` or an `Output`.
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct ScheduledAction {
+    /// Moment when the `action` will be performed.
+    pub at: DateTime<Utc>,
+
+    /// Action to be performed.
+    pub action: ScheduleAction,
+
+    /// ID of the `Schedule` performing the `action`.
+    pub schedule_id: ScheduleId,
+
+    /// ID of the `Restream` the `action` is performed on (or of the one
+    /// owning the `Output`).
+    pub restream_id: RestreamId,
+
+    /// ID of the `Output` the `action` is performed on, if any.
+    ///
+    /// If `null`, then the `action` is performed on the whole `Restream`.
+    pub output_id: Option<OutputId>,
+}
+
+#[cfg(test)]
+mod schedule_spec {
+    use chrono::{DateTime, Duration, Utc};
+
+    use super::{Schedule, ScheduleAction, ScheduleId, ScheduleRepeat};
+
+    use ScheduleAction::{Disable, Enable};
+
+    fn t(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    /// Creates a [`Schedule`] with a window from 10:00 till 12:00 of
+    /// 2024-01-01.
+    fn schedule(repeat: ScheduleRepeat) -> Schedule {
+        Schedule {
+            id: ScheduleId::random(),
+            start: t("2024-01-01T10:00:00Z"),
+            end: t("2024-01-01T12:00:00Z"),
+            repeat,
+        }
+    }
+
+    #[test]
+    fn validates_window() {
+        use ScheduleRepeat::{Daily, Once, Weekly};
+
+        let valid =
+            |start, end, repeat| Schedule::validate(start, end, repeat).is_ok();
+        let (start, end) =
+            (t("2024-01-01T10:00:00Z"), t("2024-01-01T12:00:00Z"));
+
+        assert!(valid(start, end, Once));
+        assert!(!valid(end, start, Once));
+        assert!(!valid(start, start, Once));
+
+        let end = start + Duration::days(1);
+        assert!(valid(start, end, Daily));
+        let end = end + Duration::seconds(1);
+        assert!(!valid(start, end, Daily));
+        assert!(valid(start, end, Weekly));
+    }
+
+    #[test]
+    fn once_actions_between() {
+        let s = schedule(ScheduleRepeat::Once);
+
+        assert_eq!(
+            s.actions_between(t("2024-01-01T00:00:00Z"), s.end),
+            vec![(s.start, Enable), (s.end, Disable)],
+        );
+        assert_eq!(
+            s.actions_between(s.start, t("2024-01-02T00:00:00Z")),
+            vec![(s.end, Disable)],
+        );
+        assert_eq!(s.actions_between(s.end, t("2024-02-01T00:00:00Z")), vec![]);
+    }
+
+    #[test]
+    fn daily_actions_between() {
+        let s = schedule(ScheduleRepeat::Daily);
+
+        assert_eq!(
+            s.actions_between(
+                t("2023-12-31T00:00:00Z"),
+                t("2024-01-03T11:00:00Z"),
+            ),
+            vec![
+                (t("2024-01-01T10:00:00Z"), Enable),
+                (t("2024-01-01T12:00:00Z"), Disable),
+                (t("2024-01-02T10:00:00Z"), Enable),
+                (t("2024-01-02T12:00:00Z"), Disable),
+                (t("2024-01-03T10:00:00Z"), Enable),
+            ],
+        );
+    }
+
+    #[test]
+    fn skips_long_passed_occurrences() {
+        let s = schedule(ScheduleRepeat::Daily);
+
+        assert_eq!(
+            s.actions_between(
+                t("2024-04-10T11:00:00Z"),
+                t("2024-04-11T11:00:00Z"),
+            ),
+            vec![
+                (t("2024-04-10T12:00:00Z"), Disable),
+                (t("2024-04-11T10:00:00Z"), Enable),
+            ],
+        );
+    }
+
+    #[test]
+    fn weekly_actions_between() {
+        let s = schedule(ScheduleRepeat::Weekly);
+
+        assert_eq!(
+            s.actions_between(
+                t("2024-01-01T12:00:00Z"),
+                t("2024-01-15T10:00:00Z"),
+            ),
+            vec![
+                (t("2024-01-08T10:00:00Z"), Enable),
+                (t("2024-01-08T12:00:00Z"), Disable),
+                (t("2024-01-15T10:00:00Z"), Enable),
+            ],
+        );
+    }
+
+    #[test]
+    fn next_actions() {
+        let once = schedule(ScheduleRepeat::Once);
+        assert_eq!(
+            once.next_actions(t("2024-01-01T00:00:00Z")),
+            vec![(once.start, Enable), (once.end, Disable)],
+        );
+        assert_eq!(
+            once.next_actions(t("2024-01-01T11:00:00Z")),
+            vec![(once.end, Disable)],
+        );
+        assert_eq!(once.next_actions(t("2024-01-01T13:00:00Z")), vec![]);
+
+        let daily = schedule(ScheduleRepeat::Daily);
+        assert_eq!(
+            daily.next_actions(t("2024-03-05T11:00:00Z")),
+            vec![
+                (t("2024-03-05T12:00:00Z"), Disable),
+                (t("2024-03-06T10:00:00Z"), Enable),
+            ],
+        );
+    }
+}
@@ -1,5 +1,9 @@
 //! Server's settings.
-use crate::{spec, types::UNumber};
+use crate::{
+    spec,
+    state::{DvrRetention, RetryPolicy},
+    types::UNumber,
+};
 use serde::{Deserialize, Serialize};
 
 /// Server's settings.
@@ -35,30 +39,50 @@ pub struct Settings {
     ///
     /// [`Restream`]: crate::state::Restream
     pub max_downloading_files: Option<UNumber>,
+
+    /// Global [`RetryPolicy`] of restarting failed re-streaming processes,
+    /// used unless an [`Output`] has its own one.
+    ///
+    /// [`Output`]: crate::state::Output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_policy: Option<RetryPolicy>,
+
+    /// [`DvrRetention`] policy of recorded files.
+    ///
+    /// If [`None`], then the default one is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dvr_retention: Option<DvrRetention>,
 }
 
 impl Settings {
-    /// Exports this [`Settings`] as a [`spec::v1::Settings`].
+    /// Exports this [`Settings`] as a [`spec::v2::Settings`].
     #[inline]
     #[must_use]
-    pub fn export(&self) -> spec::v1::Settings {
-        spec::v1::Settings {
+    pub fn export(&self) -> spec::v2::Settings {
+        spec::v2::Settings {
             delete_confirmation: self.delete_confirmation,
             enable_confirmation: self.enable_confirmation,
             title: self.title.clone(),
             google_api_key: self.google_api_key.clone(),
             max_downloading_files: self.max_downloading_files,
+            retry_policy: self.retry_policy.as_ref().map(RetryPolicy::export),
+            dvr_retention: self
+                .dvr_retention
+                .as_ref()
+                .map(DvrRetention::export),
         }
     }
 
-    // Applies the given [`spec::v1::Settings`] to this [`Settings`].
+    // Applies the given [`spec::v2::Settings`] to this [`Settings`].
     ///
-    pub fn apply(&mut self, new: spec::v1::Settings) {
+    pub fn apply(&mut self, new: spec::v2::Settings) {
         self.title = new.title;
         self.delete_confirmation = new.delete_confirmation;
         self.enable_confirmation = new.enable_confirmation;
         self.google_api_key = new.google_api_key;
         self.max_downloading_files = new.max_downloading_files;
+        self.retry_policy = new.retry_policy.map(RetryPolicy::new);
+        self.dvr_retention = new.dvr_retention.map(DvrRetention::new);
     }
 }
 
@@ -72,6 +96,8 @@ impl Default for Settings {
             enable_confirmation: Some(true),
             google_api_key: None,
             max_downloading_files: None,
+            retry_policy: None,
+            dvr_retention: None,
         }
     }
 }
@@ -0,0 +1,405 @@
+//! Point-in-time snapshots of a [`State`] configuration.
+//!
+//! [`State`]: crate::state::State
+
+use chrono::{DateTime, Utc};
+use derive_more::{Display, From, Into};
+use juniper::{GraphQLObject, GraphQLScalar};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{spec, state::State};
+
+/// Snapshot of an exported [`State`] configuration, taken right before
+/// performing a mutation of it.
+///
+/// [`State`]: crate::state::State
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct Snapshot {
+    /// Unique ID of this [`Snapshot`].
+    pub id: SnapshotId,
+
+    /// Time when this [`Snapshot`] was taken.
+    pub created_at: DateTime<Utc>,
+
+    /// Name of the mutation this [`Snapshot`] was taken before.
+    pub mutation: String,
+
+    /// Exported configuration of this [`Snapshot`].
+    #[graphql(skip)]
+    pub spec: spec::v2::Spec,
+}
+
+impl Snapshot {
+    /// Maximum number of [`Snapshot`]s kept in a history.
+    pub const HISTORY_LIMIT: usize = 100;
+
+    /// Creates a new [`Snapshot`] of the given `spec`, taken before the given
+    /// `mutation`.
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v2::Spec, mutation: &str) -> Self {
+        Self {
+            id: SnapshotId::random(),
+            created_at: Utc::now(),
+            mutation: mutation.to_owned(),
+            spec,
+        }
+    }
+}
+
+/// [`Snapshot`] taken before performing a mutation of a [`State`], and not
+/// recorded into its [`State::history`] yet.
+///
+/// It's recorded once dropped, but only if the mutation has changed the
+/// [`State`] configuration.
+#[derive(Debug)]
+#[must_use = "`PendingSnapshot` is recorded on drop, so should be held until \
+              the mutation is performed"]
+pub struct PendingSnapshot {
+    /// [`State`] to record the [`Snapshot`] into.
+    state: State,
+
+    /// [`Snapshot`] to be recorded.
+    snapshot: Option<Snapshot>,
+}
+
+impl PendingSnapshot {
+    /// Takes a new [`PendingSnapshot`] of the given [`State`] configuration,
+    /// before performing the given `mutation`.
+    #[inline]
+    pub fn new(state: State, mutation: &str) -> Self {
+        let spec = state.export().into_v2();
+        Self {
+            state,
+            snapshot: Some(Snapshot::new(spec, mutation)),
+        }
+    }
+}
+
+impl Drop for PendingSnapshot {
+    fn drop(&mut self) {
+        let Some(snapshot) = self.snapshot.take() else {
+            return;
+        };
+        // Mutation has failed or changed nothing.
+        if self.state.export().into_v2() == snapshot.spec {
+            return;
+        }
+
+        let mut history = self.state.history.lock_mut();
+        if history.last().map_or(false, |s| s.spec == snapshot.spec) {
+            return;
+        }
+        let len = history.len();
+        if len >= Snapshot::HISTORY_LIMIT {
+            drop(history.drain(..=len - Snapshot::HISTORY_LIMIT));
+        }
+        history.push(snapshot);
+    }
+}
+
+/// ID of a [`Snapshot`].
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Display,
+    Eq,
+    From,
+    GraphQLScalar,
+    Hash,
+    Into,
+    PartialEq,
+    Serialize,
+)]
+#[graphql(transparent)]
+pub struct SnapshotId(Uuid);
+
+impl SnapshotId {
+    /// Generates a new random [`SnapshotId`].
+    #[inline]
+    #[must_use]
+    pub fn random() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+/// Single difference between two exported configurations.
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct SpecChange {
+    /// Path to the changed value, like `restreams[<id>].outputs[<id>].label`.
+    ///
+    /// Array items having an `id` are addressed by it, others by their index.
+    pub path: String,
+
+    /// JSON-encoded value before the change, if it existed.
+    pub before: Option<String>,
+
+    /// JSON-encoded value after the change, if it exists.
+    pub after: Option<String>,
+}
+
+impl SpecChange {
+    /// Computes all the [`SpecChange`]s required to turn the `before` spec into
+    /// the `after` one.
+    #[must_use]
+    pub fn diff(before: &spec::v2::Spec, after: &spec::v2::Spec) -> Vec<Self> {
+        let mut changes = vec![];
+        Self::diff_values(
+            String::new(),
+            serde_json::to_value(before).ok().as_ref(),
+            serde_json::to_value(after).ok().as_ref(),
+            &mut changes,
+        );
+        changes
+    }
+
+    /// Recursively collects [`SpecChange`]s between the given JSON values.
+    fn diff_values(
+        path: String,
+        before: Option<&Value>,
+        after: Option<&Value>,
+        changes: &mut Vec<Self>,
+    ) {
+        match (before, after) {
+            (Some(Value::Object(b)), Some(Value::Object(a))) => {
+                for (k, v) in b {
+                    Self::diff_values(
+                        Self::field_path(&path, k),
+                        Some(v),
+                        a.get(k),
+                        changes,
+                    );
+                }
+                for (k, v) in a.iter().filter(|(k, _)| !b.contains_key(*k)) {
+                    Self::diff_values(
+                        Self::field_path(&path, k),
+                        None,
+                        Some(v),
+                        changes,
+                    );
+                }
+            }
+            (Some(Value::Array(b)), Some(Value::Array(a)))
+                if Self::all_identified(b) && Self::all_identified(a) =>
+            {
+                let id = |v: &Value| v.get("id").map(Value::to_string);
+                for v in b {
+                    let other = a.iter().find(|o| id(o) == id(v));
+                    Self::diff_values(
+                        Self::item_path(&path, &id(v).unwrap_or_default()),
+                        Some(v),
+                        other,
+                        changes,
+                    );
+                }
+                for v in a.iter().filter(|v| !b.iter().any(|o| id(o) == id(v)))
+                {
+                    Self::diff_values(
+                        Self::item_path(&path, &id(v).unwrap_or_default()),
+                        None,
+                        Some(v),
+                        changes,
+                    );
+                }
+            }
+            (Some(Value::Array(b)), Some(Value::Array(a))) => {
+                for i in 0..b.len().max(a.len()) {
+                    Self::diff_values(
+                        Self::item_path(&path, &i.to_string()),
+                        b.get(i),
+                        a.get(i),
+                        changes,
+                    );
+                }
+            }
+            (b, a) if b != a => changes.push(Self {
+                path,
+                before: b.map(Value::to_string),
+                after: a.map(Value::to_string),
+            }),
+            _ => {}
+        }
+    }
+
+    /// Indicates whether all the given array `items` are objects having an
+    /// `id` field.
+    fn all_identified(items: &[Value]) -> bool {
+        items
+            .iter()
+            .all(|v| v.get("id").map_or(false, |id| !id.is_null()))
+    }
+
+    /// Forms a path to the `field` of an object located by the `path`.
+    fn field_path(path: &str, field: &str) -> String {
+        if path.is_empty() {
+            field.to_owned()
+        } else {
+            format!("{path}.{field}")
+        }
+    }
+
+    /// Forms a path to the `item` of an array located by the `path`.
+    fn item_path(path: &str, item: &str) -> String {
+        format!("{path}[{}]", item.trim_matches('"'))
+    }
+}
+
+#[cfg(test)]
+mod spec_change_spec {
+    use serde_json::json;
+
+    use crate::spec;
+
+    use super::SpecChange;
+
+    const RESTREAM: &str = "0d7a1b3e-5a9e-4f0c-9d39-1d1e0f4c2a11";
+    const OUTPUT: &str = "9b2f6d1c-8a3e-4e7f-b1c2-5d6e7f8a9b31";
+    const NEW_OUTPUT: &str = "1c2d3e4f-5a6b-4c7d-8e9f-a0b1c2d3e441";
+
+    fn spec(label: &str, outputs: &[&str], queue: &[&str]) -> spec::v2::Spec {
+        let outputs = outputs
+            .iter()
+            .map(|id| {
+                json!({
+                    "id": id,
+                    "dst": format!("rtmp://example.com/live/{id}"),
+                    "label": label,
+                })
+            })
+            .collect::<Vec<_>>();
+        let queue = queue
+            .iter()
+            .map(|name| json!({"file_id": "1AbCdEf", "name": name}))
+            .collect::<Vec<_>>();
+        serde_json::from_value(json!({
+            "settings": {"title": label},
+            "restreams": [{
+                "id": RESTREAM,
+                "key": "en",
+                "input": {"key": "origin", "endpoints": [{"kind": "rtmp"}]},
+                "outputs": outputs,
+                "playlist": {"queue": queue},
+            }],
+        }))
+        .unwrap()
+    }
+
+    fn change(
+        path: &str,
+        before: Option<&str>,
+        after: Option<&str>,
+    ) -> SpecChange {
+        SpecChange {
+            path: path.into(),
+            before: before.map(Into::into),
+            after: after.map(Into::into),
+        }
+    }
+
+    /// Computes [`SpecChange`]s sorted by their paths, as the order of object
+    /// fields is not guaranteed.
+    fn diff(
+        before: &spec::v2::Spec,
+        after: &spec::v2::Spec,
+    ) -> Vec<SpecChange> {
+        let mut changes = SpecChange::diff(before, after);
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        changes
+    }
+
+    #[test]
+    fn no_changes_for_equal_specs() {
+        let s = spec("A", &[OUTPUT], &["intro.mp4"]);
+
+        assert_eq!(diff(&s, &s), vec![]);
+    }
+
+    #[test]
+    fn addresses_changed_fields_by_ids() {
+        let before = spec("A", &[OUTPUT], &[]);
+        let after = spec("B", &[OUTPUT], &[]);
+
+        assert_eq!(
+            diff(&before, &after),
+            vec![
+                change(
+                    &format!("restreams[{RESTREAM}].outputs[{OUTPUT}].label"),
+                    Some(r#""A""#),
+                    Some(r#""B""#),
+                ),
+                change("settings.title", Some(r#""A""#), Some(r#""B""#)),
+            ],
+        );
+    }
+
+    #[test]
+    fn detects_added_and_removed_items() {
+        let before = spec("A", &[OUTPUT], &[]);
+        let after = spec("A", &[NEW_OUTPUT], &[]);
+        let output = |id| {
+            serde_json::to_string(&json!({
+                "id": id,
+                "dst": format!("rtmp://example.com/live/{id}"),
+                "label": "A",
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(
+            diff(&before, &after),
+            vec![
+                change(
+                    &format!("restreams[{RESTREAM}].outputs[{NEW_OUTPUT}]"),
+                    None,
+                    Some(&output(NEW_OUTPUT)),
+                ),
+                change(
+                    &format!("restreams[{RESTREAM}].outputs[{OUTPUT}]"),
+                    Some(&output(OUTPUT)),
+                    None,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn addresses_unidentified_items_by_index() {
+        let before = spec("A", &[], &["intro.mp4", "main.mp4"]);
+        let after = spec("A", &[], &["intro.mp4"]);
+
+        assert_eq!(
+            diff(&before, &after),
+            vec![change(
+                &format!("restreams[{RESTREAM}].playlist.queue[1]"),
+                Some(r#"{"file_id":"1AbCdEf","name":"main.mp4"}"#),
+                None,
+            )],
+        );
+    }
+}
+
+#[cfg(test)]
+mod pending_snapshot_spec {
+    use crate::state::State;
+
+    #[test]
+    fn records_only_changing_mutations() {
+        let state = State::default();
+
+        drop(state.record_snapshot("failed"));
+        assert!(state.history.lock_ref().is_empty());
+
+        let snapshot = state.record_snapshot("setSettings");
+        state.settings.lock_mut().title = Some("changed".into());
+        drop(snapshot);
+
+        let history = state.history.lock_ref();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].mutation, "setSettings");
+        assert_eq!(history[0].spec.settings.as_ref().unwrap().title, None);
+    }
+}
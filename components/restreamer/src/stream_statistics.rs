@@ -1,6 +1,7 @@
 //! Stream statistics
-use crate::{stream_probe::StreamInfo, types::UNumber};
+use crate::{state::MixinId, stream_probe::StreamInfo, types::UNumber};
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
 use juniper::GraphQLObject;
 use serde::{Deserialize, Serialize};
 
@@ -85,3 +86,123 @@ impl StreamStatistics {
         }
     }
 }
+
+/// Real-time progress of a running [FFmpeg] process, as reported by its
+/// `-progress` output.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug, Default, GraphQLObject, PartialEq)]
+pub struct StreamProgress {
+    /// Current bitrate of the output, in kbit/s.
+    ///
+    /// `null` if it cannot be calculated (an audio-only output before its
+    /// first frame, for example).
+    pub bitrate: Option<f64>,
+
+    /// Frame rate of the output video.
+    ///
+    /// Always `0` for audio-only outputs.
+    pub fps: f64,
+
+    /// Speed of processing relatively to real time, where `1` means real
+    /// time.
+    pub speed: Option<f64>,
+
+    /// Number of dropped frames.
+    pub drop_frames: i32,
+
+    /// Number of duplicated frames.
+    pub dup_frames: i32,
+
+    /// Total size of the output written so far, in bytes.
+    ///
+    /// `null` if it's unknown (for HLS and segmenting outputs, for example).
+    pub total_size: Option<f64>,
+
+    /// Time position of the output written so far, in seconds.
+    ///
+    /// `null` if it's unknown (before the first frame, for example).
+    pub out_time: Option<f64>,
+
+    /// Total number of processed video frames.
+    pub frame: i32,
+}
+
+// All the floating point values are guaranteed to be finite by the parser, so
+// the `PartialEq` is a total one.
+impl Eq for StreamProgress {}
+
+/// Real-time audio level of a mixed `Output`'s origin audio or one of its
+/// `Mixin`s, as measured by [FFmpeg] right before mixing.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Copy, Debug, GraphQLObject, PartialEq)]
+pub struct AudioLevel {
+    /// ID of the measured `Mixin`.
+    ///
+    /// `null` if the origin audio of the `Output` is measured.
+    pub mixin_id: Option<MixinId>,
+
+    /// [Momentary loudness][1] (over the last 400 ms), in LUFS.
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/EBU_R_128
+    pub momentary: f64,
+
+    /// [Short-term loudness][1] (over the last 3 s), in LUFS.
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/EBU_R_128
+    pub short_term: f64,
+
+    /// Peak sample level since the previous measurement, in dBFS.
+    pub peak: f64,
+}
+
+impl AudioLevel {
+    /// Lowest level ever reported, used for silence.
+    pub const FLOOR: f64 = -120.0;
+}
+
+// All the floating point values are guaranteed to be finite by the parser, so
+// the `PartialEq` is a total one.
+impl Eq for AudioLevel {}
+
+/// Unsuccessful exit of a [FFmpeg] process.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct StreamFailure {
+    /// Time when the [FFmpeg] process exited.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub happened_at: DateTime<Utc>,
+
+    /// Exit status of the [FFmpeg] process, like `exit status: 1`.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub exit_status: String,
+
+    /// Last log lines of the [FFmpeg] process preceding its exit.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub stderr: String,
+}
+
+impl StreamFailure {
+    /// Maximum number of the last [`StreamFailure`]s to be kept.
+    pub const HISTORY_LIMIT: usize = 10;
+
+    /// Maximum number of log lines kept in [`StreamFailure::stderr`].
+    pub const STDERR_LINES: usize = 20;
+
+    /// Appends the `new` [`StreamFailure`] to the given `failures`, removing
+    /// the oldest ones exceeding the [`StreamFailure::HISTORY_LIMIT`].
+    pub fn push(failures: &mut Vec<Self>, new: Self) {
+        failures.push(new);
+        if failures.len() > Self::HISTORY_LIMIT {
+            let excess = failures.len() - Self::HISTORY_LIMIT;
+            drop(failures.drain(..excess));
+        }
+    }
+}
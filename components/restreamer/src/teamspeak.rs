@@ -9,15 +9,13 @@ use std::{
     mem::ManuallyDrop,
     pin::Pin,
     str,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
-    },
+    sync::{Arc, Mutex},
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use backoff::{future::retry_notify, ExponentialBackoff};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use byteorder::{BigEndian, ByteOrder as _};
 use derive_more::{Display, Error};
 use ephyr_log::tracing;
@@ -32,9 +30,14 @@ use tokio::{
     task::JoinHandle,
     time,
 };
-use tsclientlib::{DisconnectOptions, StreamItem};
+use tsclientlib::{
+    data::{self, Client},
+    ChannelId, ClientId, DisconnectOptions, StreamItem, TsError,
+};
 use tsproto_packets::packets::AudioData;
 
+use crate::state::{Speaker, SpeakerFilter};
+
 use ephyr_log::tracing::{instrument, Span};
 pub use tsclientlib::{ConnectOptions as Config, Connection};
 
@@ -83,6 +86,10 @@ pub struct Input {
     /// [TeamSpeak]: https://teamspeak.com
     pub audio: Arc<Mutex<AudioHandler>>,
 
+    /// [`Speakers`] filtering whose audio is captured by this [`Input`], and
+    /// tracking the ones talking at the moment.
+    pub speakers: Arc<Mutex<Speakers>>,
+
     /// Abort handle and waiter of the spawned [`AudioCapture`], which receives
     /// audio packets from [TeamSpeak] server and feeds them into the
     /// [`Input::audio`] handler.
@@ -95,9 +102,12 @@ pub struct Input {
     /// [TeamSpeak]: https://teamspeak.com
     pub conn: Option<(future::AbortHandle, JoinHandle<()>)>,
 
-    /// Indicator whether the spawned [`AudioCapture`] is unable to recover from
-    /// its last error, and so this [`Input`] should return an error too.
-    is_conn_unrecoverable: Arc<AtomicBool>,
+    /// Description of the last error the spawned [`AudioCapture`] is unable to
+    /// recover from (like failed authentication on [TeamSpeak] server), so
+    /// this [`Input`] should return an error too.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub failure: Arc<Mutex<Option<String>>>,
 
     span: Span,
 }
@@ -125,8 +135,6 @@ impl Input {
             let is_debug = current_level == tracing::Level::DEBUG;
             let is_trace = current_level == tracing::Level::TRACE;
 
-            // TODO #6: Memoize TeamSpeak Identity and reuse.
-            //      https://github.com/ALLATRA-IT/ephyr/issues/6
             let mut cfg =
                 cfg.into().log_commands(is_debug).log_packets(is_trace);
             // TeamSpeak limits client names by 30 UTF-8 characters max. If the
@@ -147,8 +155,9 @@ impl Input {
             frame: vec![0.0; Self::FRAME_SIZE],
             cursor: 0,
             audio: Arc::new(Mutex::new(AudioHandler::new())),
+            speakers: Arc::new(Mutex::new(Speakers::default())),
             conn: None,
-            is_conn_unrecoverable: Arc::new(AtomicBool::default()),
+            failure: Arc::new(Mutex::new(None)),
             span: tracing::info_span!("teamspeak::Input"),
         }
     }
@@ -160,7 +169,8 @@ impl Input {
     fn spawn_audio_capturing(&mut self) {
         let cfg = self.cfg.clone();
         let audio = self.audio.clone();
-        let is_conn_unrecoverable = self.is_conn_unrecoverable.clone();
+        let speakers = self.speakers.clone();
+        let failure = self.failure.clone();
 
         let capturing = retry_notify(
             ExponentialBackoff {
@@ -168,7 +178,7 @@ impl Input {
                 ..ExponentialBackoff::default()
             },
             move || {
-                AudioCapture::run(cfg.clone(), audio.clone())
+                AudioCapture::run(cfg.clone(), audio.clone(), speakers.clone())
                     .map_err(AudioCaptureError::into_backoff)
             },
             |err, dur| {
@@ -181,7 +191,7 @@ impl Input {
         )
         .map_err(move |e| {
             tracing::error!("Cannot capture audio from TeamSpeak server: {e}");
-            is_conn_unrecoverable.store(true, Ordering::SeqCst);
+            *failure.lock().unwrap() = Some(e.to_string());
         });
 
         let (abort, on_abort) = future::AbortHandle::new_pair();
@@ -209,7 +219,7 @@ impl AsyncRead for Input {
         if self.conn.is_none() {
             self.spawn_audio_capturing();
         }
-        if self.is_conn_unrecoverable.load(Ordering::SeqCst) {
+        if self.failure.lock().unwrap().is_some() {
             return Poll::Ready(Err(InputError::NoData.into()));
         }
 
@@ -263,8 +273,9 @@ impl fmt::Debug for Input {
             .field("frame", &self.frame)
             .field("cursor", &self.cursor)
             .field("audio", &"Arc<Mutex<AudioHandler>>")
+            .field("speakers", &self.speakers)
             .field("conn", &self.conn)
-            .field("is_conn_unrecoverable", &self.is_conn_unrecoverable)
+            .field("failure", &self.failure)
             .finish_non_exhaustive()
     }
 }
@@ -313,6 +324,76 @@ impl From<InputError> for io::Error {
     }
 }
 
+/// [TeamSpeak] channel members talking into an [`Input`], filtered by a
+/// [`SpeakerFilter`].
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Debug, Default)]
+pub struct Speakers {
+    /// [`SpeakerFilter`] deciding whose audio is captured.
+    pub filter: SpeakerFilter,
+
+    /// Channel members talked recently, along with the time of their last
+    /// audio packet.
+    talking: HashMap<MemberId, (Speaker, Instant)>,
+}
+
+impl Speakers {
+    /// Period since the last audio packet of a channel member, during which
+    /// it's still considered as talking.
+    pub const TALKING_TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Registers an audio packet received from the given channel `member`,
+    /// described by the given [`Client`] (if it's known already).
+    ///
+    /// Returns `true` if the audio packet should be captured, according to
+    /// the [`Speakers::filter`].
+    fn register(&mut self, member: MemberId, client: Option<&Client>) -> bool {
+        let nickname =
+            client.map_or_else(|| format!("#{member}"), |c| c.name.clone());
+        let uid = client
+            .and_then(|c| c.uid.as_ref())
+            .map(|uid| BASE64.encode(&uid.0));
+        let server_groups = client
+            .map(|c| c.server_groups.iter().map(|g| g.0).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let captured =
+            self.filter
+                .allows(&nickname, uid.as_deref(), &server_groups);
+        _ = self.talking.insert(
+            member,
+            (
+                Speaker {
+                    nickname,
+                    uid,
+                    captured,
+                },
+                Instant::now(),
+            ),
+        );
+        captured
+    }
+
+    /// Returns the channel members talking at the moment, ordered by their
+    /// nicknames.
+    ///
+    /// Forgets the ones being silent longer than the
+    /// [`Speakers::TALKING_TIMEOUT`].
+    #[must_use]
+    pub fn talking(&mut self) -> Vec<Speaker> {
+        self.talking
+            .retain(|_, (_, at)| at.elapsed() < Self::TALKING_TIMEOUT);
+        let mut talking = self
+            .talking
+            .values()
+            .map(|(s, _)| s.clone())
+            .collect::<Vec<_>>();
+        talking.sort_by(|a, b| a.nickname.cmp(&b.nickname));
+        talking
+    }
+}
+
 /// Listener of [TeamSpeak] channel, which captures audio packets of each
 /// talking channel member and feeds them into an [`AudioHandler`] to be mixed.
 ///
@@ -329,24 +410,44 @@ pub struct AudioCapture {
     /// [TeamSpeak]: https://teamspeak.com
     audio: Arc<Mutex<AudioHandler>>,
 
+    /// [`Speakers`] deciding whose audio packets are fed into the
+    /// [`AudioCapture::audio`] handler.
+    speakers: Arc<Mutex<Speakers>>,
+
+    /// Path of the [TeamSpeak] channel to be joined, if not the default one.
+    ///
+    /// Reset once the channel is verified to be joined.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    channel: Option<String>,
+
     span: Span,
 }
 
 impl AudioCapture {
     /// Creates new [`AudioCapture`] from the given [`Connection`] and for
-    /// the given [`AudioHandler`].
+    /// the given [`AudioHandler`], capturing only the [`Speakers`] allowed by
+    /// their [`SpeakerFilter`].
+    ///
+    /// If the given `channel` path is not empty, then it's verified to be
+    /// actually joined once the [`Connection`] is established.
     #[inline]
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
     pub fn new(
         conn: Connection,
         audio: Arc<Mutex<AudioHandler>>,
+        speakers: Arc<Mutex<Speakers>>,
+        channel: Option<String>,
         span: Span,
     ) -> Self {
         audio.lock().unwrap().reset();
+        speakers.lock().unwrap().talking.clear();
         Self {
             conn: ManuallyDrop::new(conn),
             audio,
+            speakers,
+            channel: channel.filter(|c| !c.is_empty()),
             span,
         }
     }
@@ -383,7 +484,7 @@ impl AudioCapture {
     }
 
     /// Creates a new [`AudioCapture`] using the given [`Config`] for the given
-    /// [`AudioHandler`] and awaits its completion.
+    /// [`AudioHandler`] and [`Speakers`], and awaits its completion.
     ///
     /// Generates new HWID (hardware identification string) to uniquely
     /// distinguish this [`AudioCapture`] for [TeamSpeak] server.
@@ -400,17 +501,85 @@ impl AudioCapture {
     pub async fn run(
         cfg: Config,
         audio: Arc<Mutex<AudioHandler>>,
+        speakers: Arc<Mutex<Speakers>>,
     ) -> Result<(), AudioCaptureError> {
         tracing::debug!(
             "Connecting to TeamSpeak server: {}/{:?}",
             cfg.get_address(),
             cfg.get_channel()
         );
+        let channel = cfg.get_channel().map(ToOwned::to_owned);
         let conn = cfg
             .hardware_id(Self::new_hwid())
             .connect()
             .map_err(AudioCaptureError::InitializationFailed)?;
-        AudioCapture::new(conn, audio, Span::current()).await
+        AudioCapture::new(conn, audio, speakers, channel, Span::current()).await
+    }
+
+    /// Verifies the [`AudioCapture::channel`] to be joined, once the
+    /// [`AudioCapture::conn`] is established and knows its own client.
+    ///
+    /// [TeamSpeak] server doesn't fail the connection if a wrong channel
+    /// password is provided, but puts the client into its default channel
+    /// instead.
+    ///
+    /// # Errors
+    ///
+    /// If the client has been put into another channel.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    fn verify_channel(&mut self) -> Result<(), AudioCaptureError> {
+        let Some(channel) = &self.channel else {
+            return Ok(());
+        };
+        let Ok(state) = self.conn.get_state() else {
+            return Ok(());
+        };
+        let Some(own) = state.clients.get(&state.own_client) else {
+            return Ok(());
+        };
+        let joined = channel_path(state, own.channel);
+        if joined.as_deref() != Some(channel.as_str()) {
+            return Err(AudioCaptureError::ChannelNotJoined(channel.clone()));
+        }
+        self.channel = None;
+        Ok(())
+    }
+}
+
+/// Forms a path of the [TeamSpeak] channel with the given `id`, with its
+/// parent channels separated by `/`, like `Event/Booth EN`.
+///
+/// Returns [`None`] if any of the channels is unknown.
+///
+/// [TeamSpeak]: https://teamspeak.com
+fn channel_path(state: &data::Connection, mut id: ChannelId) -> Option<String> {
+    let mut names = vec![];
+    while id.0 != 0 {
+        let channel = state.channels.get(&id)?;
+        names.push(channel.name.as_str());
+        id = channel.parent;
+    }
+    names.reverse();
+    Some(names.join("/"))
+}
+
+/// Returns ID of the channel member who sent the given S2C (server-to-client)
+/// [`AudioData`], if it's talking into the whole channel.
+///
+/// Whispers are addressed to particular clients only (an interpreter talking
+/// to its booth, for example), so are never captured.
+///
+/// # Errors
+///
+/// If the given [`AudioData`] is a C2S (client-to-server) one.
+fn channel_speaker(
+    data: &AudioData<'_>,
+) -> Result<Option<MemberId>, AudioCaptureError> {
+    match data {
+        AudioData::S2C { from, .. } => Ok(Some(*from)),
+        AudioData::S2CWhisper { .. } => Ok(None),
+        _ => Err(AudioCaptureError::UnexpectedC2sPacket),
     }
 }
 
@@ -418,7 +587,7 @@ impl Future for AudioCapture {
     type Output = Result<(), AudioCaptureError>;
 
     /// Processes [`AudioCapture::conn`] lifecycle and feeds all received audio
-    /// packets into the [`AudioHandler`].
+    /// packets of the captured [`Speakers`] into the [`AudioHandler`].
     fn poll(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -430,17 +599,26 @@ impl Future for AudioCapture {
             let StreamItem::Audio(audio_packet) =
                 ready!(Pin::new(&mut self.conn.events()).poll_next(cx))
                     .ok_or_else(|| E::UnexpectedFinish)?
-                    .map_err(E::ConnectionFailed)?
+                    .map_err(E::from_connection)?
             else {
+                self.verify_channel()?;
                 continue;
             };
 
-            let member_id = match audio_packet.data().data() {
-                AudioData::S2C { from, .. }
-                | AudioData::S2CWhisper { from, .. } => *from,
-                _ => return Poll::Ready(Err(E::UnexpectedC2sPacket)),
+            let Some(member_id) = channel_speaker(audio_packet.data().data())?
+            else {
+                continue;
             };
 
+            let client = self
+                .conn
+                .get_state()
+                .ok()
+                .and_then(|state| state.clients.get(&ClientId(member_id)));
+            if !self.speakers.lock().unwrap().register(member_id, client) {
+                continue;
+            }
+
             if let Err(e) = self
                 .audio
                 .lock()
@@ -496,6 +674,21 @@ pub enum AudioCaptureError {
     #[display(fmt = "Connecting to TeamSpeak server failed: {_0}")]
     ConnectionFailed(tsclientlib::Error),
 
+    /// [TeamSpeak] server rejected the provided server or channel password.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[display(fmt = "Authentication on TeamSpeak server failed: {_0}")]
+    AuthenticationFailed(tsclientlib::Error),
+
+    /// [TeamSpeak] server put the client into another channel than the
+    /// requested one, which happens on a wrong channel password.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[display(
+        fmt = "Joining TeamSpeak channel `{_0}` failed, check its password"
+    )]
+    ChannelNotJoined(#[error(not(source))] String),
+
     /// Receiving packets from [TeamSpeak] server finished unexpectedly.
     ///
     /// [TeamSpeak]: https://teamspeak.com
@@ -521,6 +714,31 @@ pub enum AudioCaptureError {
 }
 
 impl AudioCaptureError {
+    /// Wraps the given [`tsclientlib::Error`] of a [`Connection`] into an
+    /// [`AudioCaptureError`], distinguishing authentication failures.
+    ///
+    /// [TeamSpeak] server rejects a wrong server or channel password with a
+    /// [`TsError`] code, which may be nested into the returned error.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[must_use]
+    pub fn from_connection(err: tsclientlib::Error) -> Self {
+        let mut cause: Option<&(dyn std::error::Error + 'static)> = Some(&err);
+        while let Some(e) = cause {
+            if matches!(
+                e.downcast_ref::<TsError>(),
+                Some(
+                    TsError::ServerInvalidPassword
+                        | TsError::ChannelInvalidPassword
+                ),
+            ) {
+                return Self::AuthenticationFailed(err);
+            }
+            cause = e.source();
+        }
+        Self::ConnectionFailed(err)
+    }
+
     /// Wraps this [`AudioCaptureError`] into a [`backoff::Error`] carefully
     /// distinguishing transient and permanent errors.
     #[must_use]
@@ -528,7 +746,9 @@ impl AudioCaptureError {
         use tsclientlib::audio::Error as E;
 
         let is_permanent = match &self {
-            Self::InitializationFailed(_) => true,
+            Self::InitializationFailed(_)
+            | Self::AuthenticationFailed(_)
+            | Self::ChannelNotJoined(_) => true,
             Self::ConnectionFailed(_)
             | Self::UnexpectedFinish
             | Self::UnexpectedC2sPacket => false,
@@ -641,3 +861,47 @@ pub async fn finish_all_disconnects() {
 
     drop(future::join_all(disconnects).await);
 }
+
+#[cfg(test)]
+mod channel_speaker_spec {
+    use tsproto_packets::packets::{AudioData, CodecType};
+
+    use super::channel_speaker;
+
+    const CODEC: CodecType = CodecType::OpusVoice;
+
+    #[test]
+    fn captures_channel_audio() {
+        let data = AudioData::S2C {
+            id: 1,
+            codec: CODEC,
+            from: 7,
+            data: &[0; 4],
+        };
+
+        assert_eq!(channel_speaker(&data).unwrap(), Some(7));
+    }
+
+    #[test]
+    fn drops_whispers() {
+        let data = AudioData::S2CWhisper {
+            id: 1,
+            codec: CODEC,
+            from: 7,
+            data: &[0; 4],
+        };
+
+        assert_eq!(channel_speaker(&data).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_c2s_audio() {
+        let data = AudioData::C2S {
+            id: 1,
+            codec: CODEC,
+            data: &[0; 4],
+        };
+
+        assert!(channel_speaker(&data).is_err());
+    }
+}